@@ -0,0 +1,138 @@
+// Times the per-texel and workgroup-tiled compute kernels against each other,
+// with `--submits` one submission per step against one per frame, or with
+// `--block-steps K` single steps against K steps per dispatch.
+//
+//     cargo run --release --example bench -- [--software] [--submits | --block-steps K]
+//         [--sizes 512,1024,2048] [--steps-per-frame 8] [--frames 20] [--kernel 0..4]
+//
+// Each frame runs `steps-per-frame` Gray-Scott steps and waits for the device,
+// as the browser's render loop does. `--software` uses the fallback adapter even
+// if a GPU is available.
+
+use rd_sim_wgpu::simulation::{self, Simulation, SimulationPipelines};
+
+struct Options {
+    software: bool,
+    submits: bool,
+    block_steps: Option<u32>,
+    sizes: Vec<u32>,
+    steps_per_frame: u32,
+    frames: u32,
+    kernel_type: u32,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        software: false,
+        submits: false,
+        block_steps: None,
+        sizes: vec![512, 1024, 2048],
+        steps_per_frame: 8,
+        frames: 20,
+        kernel_type: 0,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        let number = |name: &str, text: &str| text.parse::<u32>().map_err(|_| format!("Invalid {}: {}", name, text));
+        match arg.as_str() {
+            "--software" => options.software = true,
+            "--submits" => options.submits = true,
+            "--block-steps" => options.block_steps = Some(number(&arg, &value(&arg)?)?),
+            "--sizes" => {
+                let text = value("--sizes")?;
+                options.sizes = text.split(',').map(|size| number("--sizes", size)).collect::<Result<_, _>>()?;
+            }
+            "--steps-per-frame" => options.steps_per_frame = number(&arg, &value(&arg)?)?,
+            "--frames" => options.frames = number(&arg, &value(&arg)?)?,
+            "--kernel" => options.kernel_type = number(&arg, &value(&arg)?)?,
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    if options.sizes.contains(&0) || options.steps_per_frame == 0 || options.frames == 0 {
+        return Err("Sizes, steps per frame and frames must be positive".to_string());
+    }
+    Ok(options)
+}
+
+fn wait(device: &wgpu::Device) -> Result<(), String> {
+    device
+        .poll(wgpu::PollType::wait_indefinitely())
+        .map(|_| ())
+        .map_err(|e| format!("Failed to poll device: {:?}", e))
+}
+
+// Milliseconds per frame of `frame`, after a warm-up frame
+fn time_frames(sim: &mut Simulation, options: &Options, frame: impl Fn(&mut Simulation)) -> Result<f64, String> {
+    frame(sim);
+    wait(sim.device())?;
+
+    let started = std::time::Instant::now();
+    for _ in 0..options.frames {
+        frame(sim);
+        wait(sim.device())?;
+    }
+    Ok(started.elapsed().as_secs_f64() * 1000.0 / options.frames as f64)
+}
+
+fn main() -> Result<(), String> {
+    let options = parse_options()?;
+    let (adapter, device, queue) = pollster::block_on(simulation::request_headless_device_with(options.software))?;
+    let info = adapter.get_info();
+    println!(
+        "{} ({:?}), kernel {}, {} steps per frame, {} frames",
+        info.name, info.backend, options.kernel_type, options.steps_per_frame, options.frames
+    );
+
+    let pipelines = SimulationPipelines::new(&device, simulation::preferred_field_format(&adapter));
+    let column = match (options.submits, options.block_steps) {
+        (true, _) => "submits",
+        (false, Some(_)) => "steps/dispatch",
+        (false, None) => "kernel",
+    };
+    println!("{:>6}  {:>14}  {:>12}  {:>10}  {:>10}  {:>7}", "size", column, "ms/frame", "ms/step", "steps/s", "speedup");
+    for &size in &options.sizes {
+        let mut sim = Simulation::with_pipelines(&device, &queue, size, size, &pipelines);
+        sim.kernel_type = options.kernel_type;
+        sim.update_params();
+
+        let steps = options.steps_per_frame;
+        let results = if options.submits {
+            // One encoder and submission per step, as frames used to run, against one per frame
+            let per_step = time_frames(&mut sim, &options, |sim| (0..steps).for_each(|_| sim.step(1)))?;
+            let per_frame = time_frames(&mut sim, &options, |sim| sim.step(steps))?;
+            [("per step".to_string(), per_step), ("per frame".to_string(), per_frame)]
+        } else if let Some(block_steps) = options.block_steps {
+            let single = time_frames(&mut sim, &options, |sim| sim.step(steps))?;
+            sim.block_steps = block_steps;
+            sim.update_params();
+            if let Some(reason) = sim.blocking_fallback() {
+                return Err(reason);
+            }
+            let blocked = time_frames(&mut sim, &options, |sim| sim.step(steps))?;
+            sim.block_steps = 1;
+            sim.update_params();
+            [("1".to_string(), single), (block_steps.to_string(), blocked)]
+        } else {
+            sim.tiled_kernel = false;
+            let per_texel = time_frames(&mut sim, &options, |sim| sim.step(steps))?;
+            sim.tiled_kernel = true;
+            let tiled = time_frames(&mut sim, &options, |sim| sim.step(steps))?;
+            [("per-texel".to_string(), per_texel), ("tiled".to_string(), tiled)]
+        };
+        let baseline = results[0].1;
+        for (name, ms_per_frame) in results {
+            let ms_per_step = ms_per_frame / options.steps_per_frame as f64;
+            println!(
+                "{:>6}  {:>14}  {:>12.2}  {:>10.3}  {:>10.1}  {:>6.2}x",
+                size,
+                name,
+                ms_per_frame,
+                ms_per_step,
+                1000.0 / ms_per_step,
+                baseline / ms_per_frame
+            );
+        }
+    }
+    Ok(())
+}
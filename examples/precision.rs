@@ -0,0 +1,139 @@
+// Runs a preset with an f32 field and an f16 field side by side and reports
+// how far the half-precision run drifts from the f32 one.
+//
+//     cargo run --release --example precision -- [--software] [--preset coral]
+//         [--size 256] [--steps 5000] [--every 1000]
+//
+// At each checkpoint it prints the largest and mean |dU| and |dV| between the
+// two fields and the pattern class of each, so a drift that changes what the
+// pattern looks like shows up as different classes.
+
+use rd_sim_wgpu::classify::{self, ClassifierConfig};
+use rd_sim_wgpu::presets;
+use rd_sim_wgpu::simulation::{self, Simulation};
+
+struct Options {
+    software: bool,
+    preset: String,
+    size: u32,
+    steps: u32,
+    every: u32,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        software: false,
+        preset: "coral".to_string(),
+        size: 256,
+        steps: 5000,
+        every: 1000,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        let number = |name: &str, text: &str| text.parse::<u32>().map_err(|_| format!("Invalid {}: {}", name, text));
+        match arg.as_str() {
+            "--software" => options.software = true,
+            "--preset" => options.preset = value(&arg)?,
+            "--size" => options.size = number(&arg, &value(&arg)?)?,
+            "--steps" => options.steps = number(&arg, &value(&arg)?)?,
+            "--every" => options.every = number(&arg, &value(&arg)?)?,
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    if options.size == 0 || options.steps == 0 || options.every == 0 {
+        return Err("Size, steps and checkpoint interval must be positive".to_string());
+    }
+    Ok(options)
+}
+
+// Largest and mean absolute difference of one channel
+fn channel_error(expected: &[f32], actual: &[f32], channel: usize) -> (f32, f32) {
+    let errors: Vec<f32> = expected
+        .iter()
+        .zip(actual)
+        .skip(channel)
+        .step_by(2)
+        .map(|(a, b)| (a - b).abs())
+        .collect();
+    let max = errors.iter().cloned().fold(0.0, f32::max);
+    (max, errors.iter().sum::<f32>() / errors.len() as f32)
+}
+
+// RMS change of V per step between two reads `steps` apart
+fn change_per_step(previous: &[f32], current: &[f32], steps: u32) -> f32 {
+    let (sum, count) = previous
+        .iter()
+        .zip(current)
+        .skip(1)
+        .step_by(2)
+        .fold((0.0, 0), |(sum, count), (a, b)| (sum + (a - b) * (a - b), count + 1));
+    (sum / count as f32).sqrt() / steps as f32
+}
+
+fn main() -> Result<(), String> {
+    let options = parse_options()?;
+    let (adapter, device, queue) = pollster::block_on(simulation::request_headless_device_with(options.software))?;
+    let full_format = simulation::preferred_field_format(&adapter);
+    let half_format = simulation::half_field_format(&adapter).ok_or("The adapter has no f16 storage texture format")?;
+    let scene = presets::get_preset(&options.preset)
+        .ok_or_else(|| format!("Unknown preset '{}'", options.preset))?
+        .scene();
+    println!(
+        "{} ({:?}), {} on {}x{}, {:?} against {:?}",
+        adapter.get_info().name,
+        adapter.get_info().backend,
+        options.preset,
+        options.size,
+        options.size,
+        half_format,
+        full_format
+    );
+
+    let mut full = Simulation::new(&device, &queue, options.size, options.size, full_format);
+    let mut half = Simulation::new(&device, &queue, options.size, options.size, half_format);
+    scene.start(&mut full)?;
+    scene.start(&mut half)?;
+    let periodic = full.boundary_mode == 0;
+
+    println!(
+        "{:>7}  {:>9}  {:>9}  {:>9}  {:>9}  {:>12}  {:>12}",
+        "step", "max |dU|", "mean |dU|", "max |dV|", "mean |dV|", "f32 class", "f16 class"
+    );
+    let mut previous = (pollster::block_on(full.read_field())?, pollster::block_on(half.read_field())?);
+    let mut step = 0;
+    while step < options.steps {
+        let steps = options.every.min(options.steps - step);
+        full.step(steps);
+        half.step(steps);
+        step += steps;
+
+        let current = (pollster::block_on(full.read_field())?, pollster::block_on(half.read_field())?);
+        let (max_u, mean_u) = channel_error(&current.0, &current.1, 0);
+        let (max_v, mean_v) = channel_error(&current.0, &current.1, 1);
+        let class = |previous: &[f32], field: &[f32]| {
+            let change = change_per_step(previous, field, steps);
+            classify::classify(
+                &classify::v_channel(field),
+                options.size,
+                options.size,
+                change,
+                periodic,
+                &ClassifierConfig::default(),
+            )
+            .class
+        };
+        println!(
+            "{:>7}  {:>9.5}  {:>9.5}  {:>9.5}  {:>9.5}  {:>12}  {:>12}",
+            step,
+            max_u,
+            mean_u,
+            max_v,
+            mean_v,
+            class(&previous.0, &current.0).name(),
+            class(&previous.1, &current.1).name()
+        );
+        previous = current;
+    }
+    Ok(())
+}
@@ -0,0 +1,33 @@
+// Runs a parameter sweep natively and writes the contact sheet and manifests.
+//
+//     cargo run --release --example sweep -- [config.json] [output_dir]
+//
+// The config is a JSON `SweepConfig`; missing fields (or a missing file) take
+// the defaults. Output goes to `sweep_output` unless a directory is given.
+
+use std::path::PathBuf;
+
+use rd_sim_wgpu::{image, simulation, sweep};
+
+fn main() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    let config: sweep::SweepConfig = match args.next() {
+        Some(path) => {
+            let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            serde_json::from_str(&text).map_err(|e| format!("Invalid sweep config {}: {}", path, e))?
+        }
+        None => sweep::SweepConfig::default(),
+    };
+    let output_dir = PathBuf::from(args.next().unwrap_or_else(|| "sweep_output".to_string()));
+
+    let (adapter, device, queue) = pollster::block_on(simulation::request_headless_device())?;
+    println!("Running {} runs on {}", config.runs().len(), adapter.get_info().name);
+
+    let format = simulation::preferred_field_format(&adapter);
+    let started = std::time::Instant::now();
+    let result = pollster::block_on(sweep::run_sweep(&device, &queue, format, &config))?;
+    result.write_outputs(&output_dir, &image::DEFAULT_GRADIENT)?;
+
+    println!("Finished in {:.1}s, wrote {}", started.elapsed().as_secs_f32(), output_dir.display());
+    Ok(())
+}
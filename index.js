@@ -1,4 +1,4 @@
-import init, { init_app, render_frame, set_feed_rate, set_kill_rate, set_paused, reset, handle_mouse_down, handle_mouse_up, handle_mouse_move, apply_preset, set_color_palette, set_kernel, set_zoom, set_pan, clear_canvas, add_random_blobs, set_emboss, set_map_mode, set_diffusion_u, set_diffusion_v, set_steps_per_frame, set_noise, set_boundary, set_gradient } from './pkg/rd_sim_wgpu.js';
+import init, { init_app, render_frame, set_feed_rate, set_kill_rate, set_paused, reset, handle_mouse_down, handle_mouse_up, handle_mouse_move, set_color_palette, set_kernel, set_zoom, set_pan, clear_canvas, add_random_blobs, set_emboss, set_map_mode, set_diffusion_u, set_diffusion_v, set_steps_per_frame, set_noise, set_boundary, set_gradient, list_presets, load_preset, list_palettes, save_scene, perf_stats, set_perf_overlay } from './pkg/rd_sim_wgpu.js';
 
 // Tab switching functionality
 const tabs = document.querySelectorAll('.tab');
@@ -63,6 +63,11 @@ let frameCount = 0;
 let fps = 0;
 const fpsDisplay = document.getElementById('fps-display');
 
+// `?half` in the URL stores the field as f16 where the adapter allows it, and
+// `?perf` shows the frame timing overlay and steps per second
+const halfPrecision = new URLSearchParams(location.search).has('half');
+const perfOverlay = new URLSearchParams(location.search).has('perf');
+
 function showStatus(msg) {
     status.textContent = msg;
     status.style.display = 'block';
@@ -75,10 +80,12 @@ async function start() {
     try {
         showStatus('Initializing WebGPU...');
         await init();
-        await init_app('canvas');
+        await init_app('canvas', halfPrecision);
+        set_perf_overlay(perfOverlay);
 
-        // Apply initial gradient to shader now that WASM is loaded
-        updateGradientFromGrapick();
+        // Build the preset menus and apply the initial gradient now that WASM is loaded
+        loadPresetLibrary();
+        loadGradientPreset('rainbow');
         
         // Mark WASM as initialized so resize can work
         wasmInitialized = true;
@@ -103,6 +110,10 @@ function renderLoop() {
         if (elapsed >= 1000) { // Update every second
             fps = Math.round((frameCount * 1000) / elapsed);
             fpsDisplay.textContent = `FPS: ${fps}`;
+            if (perfOverlay) {
+                const perf = JSON.parse(perf_stats());
+                fpsDisplay.textContent += ` | ${Math.round(perf.steps_per_second)} steps/s, ${perf.ms_per_step.toFixed(3)} ms/step`;
+            }
             frameCount = 0;
             lastFrameTime = currentTime;
         }
@@ -174,57 +185,34 @@ grapick.setColorPicker(handler => {
     }
 });
 
-// Gradient presets
-const gradientPresets = {
-    rainbow: [
-        { pos: 0, color: '#330033' },
-        { pos: 10, color: '#8000ff' },
-        { pos: 20, color: '#0080ff' },
-        { pos: 30, color: '#00ffcc' },
-        { pos: 45, color: '#ff4d00' },
-        { pos: 70, color: '#ff0000' }
-    ],
-    pastel: [
-        { pos: 0, color: '#ff9a9e' },
-        { pos: 10, color: '#fad0c4' },
-        { pos: 30, color: '#fbc2eb' },
-        { pos: 45, color: '#a18cd1' },
-        { pos: 60, color: '#fbc2eb' }
-    ],
-    olivegarden: [
-        { pos: 0, color: '#606c38' },
-        { pos: 10, color: '#6b8e23' },
-        { pos: 20, color: '#fefae0' },
-        { pos: 40, color: '#dda15e' },
-        { pos: 60, color: '#bc6c25' }
-    ],
-    threshold: [
-        { pos: 20, color: '#ffffff' },
-        { pos: 23, color: '#000000' },
-    ],
-    ink: [
-        { pos: 0, color: '#f6eee3' },
-        { pos: 20, color: '#0082ce' },
-        { pos: 40, color: '#0e0955ff' },
-        { pos: 80, color: '#000000' },
-    ],
-    globs: [
-        { pos: 0, color: '#8ecae6' },
-        { pos: 20, color: '#219ebc' },
-        { pos: 33, color: '#023047' },
-        { pos: 40, color: '#ffb703' },
-        { pos: 44, color: '#023047' },
-        { pos: 80, color: '#09e2b3ff' },
-    ],
-    earth: [
-        { pos: 0, color: '#6c584c' },
-        { pos: 10, color: '#a98467' },
-        { pos: 30, color: '#adc178' },
-        { pos: 40, color: '#dde5b6' },
-        { pos: 80, color: '#f0ead2' },
-
-    ]
-};
+// Gradient presets, filled from the crate's palette library once WASM is loaded.
+// Grapick wants positions in percent and hex colors.
+let gradientPresets = {};
+let presetLibrary = {};
+
+function toHex(channel) {
+    return Math.round(channel * 255).toString(16).padStart(2, '0');
+}
+
+function loadPresetLibrary() {
+    gradientPresetSelect.innerHTML = '';
+    for (const palette of JSON.parse(list_palettes())) {
+        gradientPresets[palette.name] = palette.stops.map(stop => ({
+            pos: stop.position * 100,
+            color: '#' + stop.color.map(toHex).join('')
+        }));
+        gradientPresetSelect.add(new Option(palette.label, palette.name));
+    }
+
+    presetSelect.innerHTML = '';
+    for (const preset of JSON.parse(list_presets())) {
+        presetLibrary[preset.name] = preset;
+        const label = preset.equation.type === 'gray_scott'
+            ? `${preset.label} (F=${preset.feed}, k=${preset.kill})`
+            : preset.label;
+        presetSelect.add(new Option(label, preset.name));
+    }
+}
 
 function loadGradientPreset(presetName) {
     const preset = gradientPresets[presetName];
@@ -250,11 +238,6 @@ function loadGradientPreset(presetName) {
     }
 }
 
-// Initialize with default Rainbow gradient (visual only)
-gradientPresets.rainbow.forEach(stop => {
-    grapick.addHandler(stop.pos, stop.color);
-});
-
 function updateGradientFromGrapick() {
     const handlers = grapick.getHandlers();
 
@@ -555,14 +538,39 @@ document.addEventListener('keydown', (e) => {
 
 // Preset selection
 presetSelect.addEventListener('change', (e) => {
-    const [feed, kill] = e.target.value.split(',').map(parseFloat);
-    feedSlider.value = feed;
-    killSlider.value = kill;
-    feedValue.textContent = feed.toFixed(4);
-    killValue.textContent = kill.toFixed(4);
-    apply_preset(feed, kill);
+    const preset = presetLibrary[e.target.value];
+    try {
+        load_preset(preset.name);
+        syncControls(JSON.parse(save_scene('json')));
+        gradientPresetSelect.value = preset.palette;
+        loadGradientPreset(preset.palette);
+    } catch (err) {
+        showStatus('Error: ' + err);
+    }
 });
 
+// Moves the sidebar controls to match a scene, e.g. after loading a preset
+function syncControls(scene) {
+    const setSlider = (slider, label, value, digits) => {
+        slider.value = value;
+        label.textContent = value.toFixed(digits);
+    };
+    setSlider(feedSlider, feedValue, scene.model.feed_rate, 4);
+    setSlider(killSlider, killValue, scene.model.kill_rate, 4);
+    setSlider(duSlider, duValue, scene.model.diffuse_u, 2);
+    setSlider(dvSlider, dvValue, scene.model.diffuse_v, 2);
+    setSlider(noiseSlider, noiseValue, scene.model.noise_strength, 4);
+    speedSlider.value = scene.steps_per_frame;
+    speedValue.textContent = scene.steps_per_frame;
+    kernelSelect.value = scene.model.kernel_type;
+    boundarySelect.value = scene.model.boundary_mode;
+    embossCheckbox.checked = scene.emboss;
+    zoomSlider.value = scene.view.zoom;
+    zoomValue.textContent = scene.view.zoom.toFixed(1) + 'x';
+    setSlider(panXSlider, panXValue, scene.view.pan_x, 2);
+    setSlider(panYSlider, panYValue, scene.view.pan_y, 2);
+}
+
 // Pause button
 pauseBtn.addEventListener('click', () => {
     isPaused = !isPaused;
@@ -646,7 +654,8 @@ async function resizeCanvas() {
             clearTimeout(resizeTimeout);
             resizeTimeout = setTimeout(async () => {
                 try {
-                    await init_app('canvas');
+                    await init_app('canvas', halfPrecision);
+                    set_perf_overlay(perfOverlay);
                     updateGradientFromGrapick();
                 } catch (e) {
                     console.error('Resize reinit error:', e);
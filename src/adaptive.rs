@@ -0,0 +1,586 @@
+// Adaptive time stepping for the explicit solver. Instead of a fixed
+// delta_time, each step is an attempt with the current dt: an Euler step and
+// Heun's step are taken from the same field, their largest difference over the
+// grid estimates the error (a max-reduction in adaptive.wgsl), and the attempt
+// is kept only if that is within `tolerance`. Either way dt then grows or
+// shrinks towards the tolerance, within [min_dt, max_dt].
+//
+// The controller runs on the GPU and copies dt into the simulation's params
+// itself, so steps never wait on the host. The host reads dt and the simulated
+// time back asynchronously (Simulation::read_time_step).
+
+use std::future::Future;
+
+use serde::{Deserialize, Serialize};
+
+use crate::readback;
+use crate::simulation::{field_shader, Simulation};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptiveTimeStep {
+    pub tolerance: f32, // largest change per cell Euler may get wrong in one step
+    pub min_dt: f32,
+    pub max_dt: f32,
+}
+
+impl Default for AdaptiveTimeStep {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.002,
+            min_dt: 0.01,
+            max_dt: 2.0,
+        }
+    }
+}
+
+impl AdaptiveTimeStep {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let adaptive: AdaptiveTimeStep = serde_json::from_str(json).map_err(|e| format!("Invalid adaptive time step: {}", e))?;
+        adaptive.validate()?;
+        Ok(adaptive)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.tolerance.is_finite() && self.tolerance > 0.0) {
+            return Err("tolerance must be a positive number".to_string());
+        }
+        if !(self.min_dt.is_finite() && self.max_dt.is_finite() && 0.0 < self.min_dt && self.min_dt <= self.max_dt) {
+            return Err("min_dt and max_dt must satisfy 0 < min_dt <= max_dt".to_string());
+        }
+        Ok(())
+    }
+}
+
+// The controller's state as last read back from the GPU
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimeStepState {
+    pub dt: f32,       // size of the next attempt
+    pub last_dt: f32,  // size of the last accepted step
+    pub time: f64,     // accepted steps summed since the field was last replaced
+    pub error: f32,    // error estimate of the last attempt
+    pub accepted_steps: u32,
+    pub rejected_steps: u32,
+    pub(crate) epoch: u32,
+}
+
+// Uniform for adaptive.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SettingsUniform {
+    grid_width: u32,
+    grid_height: u32,
+    num_partials: u32,
+    _padding1: u32,
+    tolerance: f32,
+    min_dt: f32,
+    max_dt: f32,
+    _padding2: f32,
+    field_min: f32,
+    field_max: f32,
+    _padding3: [f32; 2],
+}
+
+// `Control` in adaptive.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct ControlData {
+    dt: f32,
+    last_dt: f32,
+    time: f32,
+    compensation: f32,
+    error: f32,
+    accepted: u32,
+    accepted_steps: u32,
+    rejected_steps: u32,
+    epoch: u32,
+    _padding1: u32,
+    _padding2: u32,
+    _padding3: u32,
+}
+
+pub(crate) struct AdaptiveStepper {
+    previous: wgpu::Texture, // the field before the attempt
+    groups_x: u32,           // combine's 16x16 workgroups
+    groups_y: u32,
+
+    combine_pipeline: wgpu::ComputePipeline,
+    control_pipeline: wgpu::ComputePipeline,
+    commit_pipeline: wgpu::ComputePipeline,
+    // Indexed by whether the attempt starts from A
+    combine_bind_groups: [wgpu::BindGroup; 2],
+    commit_bind_groups: [wgpu::BindGroup; 2],
+
+    settings_buffer: wgpu::Buffer,
+    control_buffer: wgpu::Buffer,
+}
+
+impl AdaptiveStepper {
+    pub(crate) fn new(sim: &Simulation) -> Self {
+        let device = sim.device();
+        let format = sim.format();
+        let groups_x = sim.width().div_ceil(16);
+        let groups_y = sim.height().div_ceil(16);
+
+        let create_texture = |label, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: sim.width(),
+                    height: sim.height(),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | usage,
+                view_formats: &[],
+            })
+        };
+        let previous = create_texture("Adaptive Previous Texture", wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST);
+        let heun = create_texture("Adaptive Heun Texture", wgpu::TextureUsages::STORAGE_BINDING);
+        let previous_view = previous.create_view(&wgpu::TextureViewDescriptor::default());
+        let heun_view = heun.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let partials_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Adaptive Partials Buffer"),
+            size: (groups_x * groups_y) as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let control_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Adaptive Control Buffer"),
+            size: std::mem::size_of::<ControlData>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let settings_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Adaptive Settings Buffer"),
+            size: std::mem::size_of::<SettingsUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shader = field_shader(device, "Adaptive Shader", include_str!("adaptive.wgsl"), format);
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let storage_texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::StorageTexture {
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+            count: None,
+        };
+        let buffer_entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let storage = wgpu::BufferBindingType::Storage { read_only: false };
+
+        let combine_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Adaptive Combine Bind Group Layout"),
+            entries: &[
+                // binding 0: previous_field
+                texture_entry(0),
+                // binding 1: euler_field
+                texture_entry(1),
+                // binding 2: euler_twice_field
+                texture_entry(2),
+                // binding 3: heun_dst
+                storage_texture_entry(3),
+                // binding 4: per-workgroup error maxima
+                buffer_entry(4, storage),
+                // binding 5: controller state
+                buffer_entry(5, storage),
+                // binding 6: settings uniform buffer
+                buffer_entry(6, wgpu::BufferBindingType::Uniform),
+            ],
+        });
+
+        let commit_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Adaptive Commit Bind Group Layout"),
+            entries: &[
+                // binding 0: previous_field
+                texture_entry(0),
+                // binding 5: controller state
+                buffer_entry(5, storage),
+                // binding 6: settings uniform buffer
+                buffer_entry(6, wgpu::BufferBindingType::Uniform),
+                // binding 7: heun_field
+                texture_entry(7),
+                // binding 8: texture_dst
+                storage_texture_entry(8),
+            ],
+        });
+
+        let create_pipeline = |label, layout: &wgpu::BindGroupLayout, entry_point| {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let combine_pipeline = create_pipeline("Adaptive Combine Pipeline", &combine_bind_group_layout, "combine");
+        let control_pipeline = create_pipeline("Adaptive Control Pipeline", &combine_bind_group_layout, "control_step");
+        let commit_pipeline = create_pipeline("Adaptive Commit Pipeline", &commit_bind_group_layout, "commit");
+
+        // An attempt from A leaves e1 in B and e2 in A, and commits to B. A then
+        // gets the field from before the attempt back (see `encode`).
+        let (view_a, view_b) = (sim.texture_a_view(), sim.texture_b_view());
+        let create_bind_groups = |from_a: bool| {
+            let (euler, euler_twice) = if from_a { (view_b, view_a) } else { (view_a, view_b) };
+            let combine = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Adaptive Combine Bind Group"),
+                layout: &combine_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&previous_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(euler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(euler_twice),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&heun_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: partials_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: control_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 6,
+                        resource: settings_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+            let commit = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Adaptive Commit Bind Group"),
+                layout: &commit_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&previous_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: control_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 6,
+                        resource: settings_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 7,
+                        resource: wgpu::BindingResource::TextureView(&heun_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 8,
+                        resource: wgpu::BindingResource::TextureView(euler),
+                    },
+                ],
+            });
+            (combine, commit)
+        };
+        let (combine_from_b, commit_from_b) = create_bind_groups(false);
+        let (combine_from_a, commit_from_a) = create_bind_groups(true);
+
+        Self {
+            previous,
+            groups_x,
+            groups_y,
+            combine_pipeline,
+            control_pipeline,
+            commit_pipeline,
+            combine_bind_groups: [combine_from_b, combine_from_a],
+            commit_bind_groups: [commit_from_b, commit_from_a],
+            settings_buffer,
+            control_buffer,
+        }
+    }
+
+    pub(crate) fn update_settings(&self, sim: &Simulation, adaptive: &AdaptiveTimeStep) {
+        let settings = SettingsUniform {
+            grid_width: sim.width(),
+            grid_height: sim.height(),
+            num_partials: self.groups_x * self.groups_y,
+            _padding1: 0,
+            tolerance: adaptive.tolerance,
+            min_dt: adaptive.min_dt,
+            max_dt: adaptive.max_dt,
+            _padding2: 0.0,
+            field_min: sim.equation.field_range().0,
+            field_max: sim.equation.field_range().1,
+            _padding3: [0.0; 2],
+        };
+        sim.queue().write_buffer(&self.settings_buffer, 0, bytemuck::cast_slice(&[settings]));
+    }
+
+    // Restarts the controller at `dt` with no time elapsed
+    pub(crate) fn reset(&self, queue: &wgpu::Queue, dt: f32, epoch: u32) {
+        let control = ControlData {
+            dt,
+            last_dt: dt,
+            epoch,
+            ..Default::default()
+        };
+        queue.write_buffer(&self.control_buffer, 0, bytemuck::cast_slice(&[control]));
+    }
+
+    // Records one attempt from `sim`'s current field into its next texture
+    pub(crate) fn encode(&self, sim: &Simulation, encoder: &mut wgpu::CommandEncoder) {
+        let from_a = sim.current_is_a();
+
+        // The controller's dt replaces whatever the host last wrote
+        let (params_buffer, delta_time_offset) = sim.delta_time_slot();
+        encoder.copy_buffer_to_buffer(&self.control_buffer, 0, params_buffer, delta_time_offset, 4);
+
+        encoder.copy_texture_to_texture(
+            sim.current_texture().as_image_copy(),
+            self.previous.as_image_copy(),
+            self.previous.size(),
+        );
+        sim.encode_step(encoder, from_a);
+        sim.encode_step(encoder, !from_a);
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Adaptive Pass"),
+                timestamp_writes: None,
+            });
+
+            pass.set_bind_group(0, &self.combine_bind_groups[from_a as usize], &[]);
+            pass.set_pipeline(&self.combine_pipeline);
+            pass.dispatch_workgroups(self.groups_x, self.groups_y, 1);
+
+            pass.set_pipeline(&self.control_pipeline);
+            pass.dispatch_workgroups(1, 1, 1);
+
+            pass.set_pipeline(&self.commit_pipeline);
+            pass.set_bind_group(0, &self.commit_bind_groups[from_a as usize], &[]);
+            pass.dispatch_workgroups(sim.width().div_ceil(8), sim.height().div_ceil(8), 1);
+        }
+
+        // The starting texture still holds e2; put the field from before the
+        // attempt back, so the stats' change and read_previous_field compare
+        // against the last accepted state rather than an intermediate one
+        encoder.copy_texture_to_texture(
+            self.previous.as_image_copy(),
+            sim.current_texture().as_image_copy(),
+            self.previous.size(),
+        );
+    }
+
+    // Reads the controller state back. The future doesn't borrow the stepper.
+    pub(crate) fn read(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> impl Future<Output = Result<TimeStepState, String>> + 'static {
+        let device = device.clone();
+        let queue = queue.clone();
+        let control_buffer = self.control_buffer.clone();
+
+        async move {
+            let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Adaptive Staging Buffer"),
+                size: control_buffer.size(),
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Adaptive Readback Encoder"),
+            });
+            encoder.copy_buffer_to_buffer(&control_buffer, 0, &staging_buffer, 0, control_buffer.size());
+            queue.submit(Some(encoder.finish()));
+
+            readback::map_buffer(&device, &staging_buffer).await?;
+
+            let control: ControlData = {
+                let mapped = staging_buffer.slice(..).get_mapped_range();
+                bytemuck::pod_read_unaligned(&mapped)
+            };
+            staging_buffer.unmap();
+
+            Ok(TimeStepState {
+                dt: control.dt,
+                last_dt: control.last_dt,
+                time: control.time as f64 - control.compensation as f64,
+                error: control.error,
+                accepted_steps: control.accepted_steps,
+                rejected_steps: control.rejected_steps,
+                epoch: control.epoch,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{headless_simulation, Simulation};
+
+    #[test]
+    fn diffusion_follows_the_exact_solution_in_sim_time() {
+        let Some(mut sim) = headless_simulation(32, 16) else { return };
+        // No V and no feed, so U only diffuses
+        sim.feed_rate = 0.0;
+        sim.kill_rate = 0.0;
+        sim.delta_time = 0.1;
+        sim.adaptive = Some(AdaptiveTimeStep {
+            tolerance: 1e-4,
+            min_dt: 0.01,
+            max_dt: 1.0,
+        });
+        sim.update_params();
+
+        let wave = |x: u32| (2.0 * std::f32::consts::PI * 3.0 * x as f32 / 32.0).cos();
+        let start: Vec<f32> = (0..32 * 16).flat_map(|i| [0.5 + 0.1 * wave(i % 32), 0.0]).collect();
+        sim.write_field(&start).unwrap();
+        sim.step(40);
+
+        let state = pollster::block_on(sim.read_time_step()).unwrap().unwrap();
+        sim.sync_time_step(state);
+        assert_eq!(state.accepted_steps + state.rejected_steps, 40);
+        assert!(state.dt > 0.1 && state.dt <= 1.0, "dt {}", state.dt);
+        assert_eq!(sim.current_dt(), state.dt);
+        assert!(sim.sim_time() > 4.0, "time {}", sim.sim_time());
+
+        // The default kernel's symbol at (3, 0) is -0.6 + 0.6 cos(2 pi 3 / 32)
+        let symbol = -0.6 + 0.6 * (2.0 * std::f32::consts::PI * 3.0 / 32.0).cos();
+        let amplitude = 0.1 * (symbol * sim.diffuse_u * sim.sim_time() as f32).exp();
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        for (i, uv) in field.chunks(2).enumerate() {
+            let expected = 0.5 + amplitude * wave(i as u32 % 32);
+            assert!((uv[0] - expected).abs() < 1e-3, "cell {}: {} vs {}", i, uv[0], expected);
+        }
+    }
+
+    #[test]
+    fn rejected_attempts_keep_the_field() {
+        let Some(mut sim) = headless_simulation(32, 16) else { return };
+        sim.delta_time = 1.0;
+        sim.adaptive = Some(AdaptiveTimeStep {
+            tolerance: 1e-9,
+            min_dt: 0.001,
+            max_dt: 2.0,
+        });
+        sim.update_params();
+
+        let start: Vec<f32> = (0..32 * 16).flat_map(|i| [1.0, if i % 7 == 0 { 0.5 } else { 0.0 }]).collect();
+        sim.write_field(&start).unwrap();
+        let mut recorder = crate::stats::FieldStatsRecorder::new(&sim);
+        sim.step(1);
+        recorder.record(&sim);
+
+        assert_eq!(pollster::block_on(sim.read_field()).unwrap(), start);
+        // Nothing was accepted, so nothing changed since the previous state
+        assert_eq!(pollster::block_on(sim.read_previous_field()).unwrap(), start);
+        assert_eq!(pollster::block_on(recorder.read_new()).unwrap()[0].change, 0.0);
+        let state = pollster::block_on(sim.read_time_step()).unwrap().unwrap();
+        assert_eq!((state.accepted_steps, state.rejected_steps), (0, 1));
+        assert!((state.dt - 0.2).abs() < 1e-6, "dt {}", state.dt);
+        sim.sync_time_step(state);
+        assert_eq!(sim.sim_time(), 0.0);
+
+        // Reads from before the field was replaced are dropped
+        sim.write_field(&start).unwrap();
+        sim.sync_time_step(state);
+        assert_eq!(sim.current_dt(), sim.delta_time);
+        assert!(sim.adaptive_fallback().is_none());
+        sim.solver = crate::solver::Solver::Spectral;
+        assert!(sim.adaptive_fallback().is_some());
+    }
+
+    #[test]
+    fn fixed_steps_after_adaptive_ones_use_delta_time() {
+        let Some(mut sim) = headless_simulation(32, 16) else { return };
+        sim.delta_time = 0.5;
+        sim.update_params();
+        let start: Vec<f32> = (0..32 * 16).flat_map(|i| [1.0, if i % 5 == 0 { 0.25 } else { 0.0 }]).collect();
+        sim.write_field(&start).unwrap();
+        sim.step(6);
+        let expected = pollster::block_on(sim.read_field()).unwrap();
+
+        // The controller grows its dt away from delta_time
+        let adaptive_run = |sim: &mut Simulation| {
+            sim.adaptive = Some(AdaptiveTimeStep::default());
+            sim.update_params();
+            sim.write_field(&start).unwrap();
+            sim.step(20);
+            let state = pollster::block_on(sim.read_time_step()).unwrap().unwrap();
+            assert!((state.dt - 0.5).abs() > 0.1, "dt {}", state.dt);
+        };
+
+        // Cleared without update_params, as the fallback does too
+        adaptive_run(&mut sim);
+        sim.adaptive = None;
+        sim.write_field(&start).unwrap();
+        sim.step(6);
+        assert_eq!(pollster::block_on(sim.read_field()).unwrap(), expected);
+        assert_eq!(sim.sim_time(), 3.0);
+
+        // Falling back to fixed steps while adaptive steps are still set
+        adaptive_run(&mut sim);
+        sim.flow = crate::flow::Flow::Drift { vx: 0.0, vy: 0.0 };
+        assert!(sim.adaptive_fallback().is_some());
+        sim.write_field(&start).unwrap();
+        sim.step(6);
+        let drifted = pollster::block_on(sim.read_field()).unwrap();
+        let error = expected.iter().zip(&drifted).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+        assert!(error < 1e-6, "{}", error);
+        assert_eq!(sim.sim_time(), 3.0);
+    }
+
+    #[test]
+    fn signed_equations_keep_negative_values() {
+        let Some(mut sim) = headless_simulation(16, 16) else { return };
+        // A uniform A = -0.5 grows towards |A| = 1: dA/dt = A - |A|^2 A
+        sim.equation = crate::equation::Equation::GinzburgLandau { alpha: 0.0, beta: 0.0 };
+        sim.delta_time = 0.05;
+        sim.adaptive = Some(AdaptiveTimeStep::default());
+        sim.update_params();
+        sim.write_field(&[-0.5, 0.0].repeat(16 * 16)).unwrap();
+        sim.step(10);
+
+        let state = pollster::block_on(sim.read_time_step()).unwrap().unwrap();
+        assert!(state.accepted_steps > 0);
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        assert!(field.chunks(2).all(|uv| uv[0] < -0.5 && uv[0] > -1.0), "{:?}", &field[..2]);
+    }
+}
@@ -0,0 +1,152 @@
+// Adaptive time steps (adaptive.rs). Each attempt takes two explicit steps from
+// the saved field u:  e1 = u + dt f(u),  e2 = e1 + dt f(e1).  Their average is
+// Heun's method, u + dt/2 (f(u) + f(e1)), and its distance from the Euler step
+// e1 estimates the local error of e1. The largest error over the grid decides
+// whether the attempt is kept and how dt changes for the next one.
+//
+// Pass 1 (combine): Heun's step into `heun`, per-workgroup error maxima into `partials`.
+// Pass 2 (control): a single workgroup folds the maxima and updates `control`.
+// Pass 3 (commit): writes Heun's step, or u again if the attempt was rejected.
+
+struct Settings {
+    grid_width: u32,
+    grid_height: u32,
+    num_partials: u32,
+    _padding1: u32,
+    tolerance: f32,   // largest error per cell accepted in one step
+    min_dt: f32,
+    max_dt: f32,
+    _padding2: f32,
+    field_min: f32,   // range the explicit kernel clamps the equation's fields to
+    field_max: f32,
+    _padding3: vec2<f32>,
+}
+
+struct Control {
+    dt: f32,              // size of the next attempt
+    last_dt: f32,         // size of the last accepted step
+    time: f32,            // accepted steps summed since the last reset...
+    compensation: f32,    // ...with Kahan compensation, so long runs keep their precision
+    error: f32,           // error estimate of the last attempt
+    accepted: u32,        // 1 if the last attempt was kept
+    accepted_steps: u32,
+    rejected_steps: u32,
+    epoch: u32,           // set by the host on reset, to match reads to resets
+    _padding1: u32,
+    _padding2: u32,
+    _padding3: u32,
+}
+
+@group(0) @binding(0) var previous_field: texture_2d<f32>;   // u
+@group(0) @binding(1) var euler_field: texture_2d<f32>;      // e1
+@group(0) @binding(2) var euler_twice_field: texture_2d<f32>; // e2
+@group(0) @binding(3) var heun_dst: texture_storage_2d<rg32float, write>;
+@group(0) @binding(4) var<storage, read_write> partials: array<f32>;
+@group(0) @binding(5) var<storage, read_write> control: Control;
+@group(0) @binding(6) var<uniform> settings: Settings;
+
+// commit has its own bind group with bindings 0, 5 and 6 plus these, as it
+// writes the texture combine reads e1 from
+@group(0) @binding(7) var heun_field: texture_2d<f32>;
+@group(0) @binding(8) var texture_dst: texture_storage_2d<rg32float, write>;
+
+const WORKGROUP_SIZE: u32 = 256u;
+
+// Step size factors: a safety margin under the optimum, and limits on how fast dt moves
+const SAFETY: f32 = 0.9;
+const MIN_FACTOR: f32 = 0.2;
+const MAX_FACTOR: f32 = 2.0;
+
+var<workgroup> scratch: array<f32, WORKGROUP_SIZE>;
+
+// Tree max over `scratch`; the result ends up in scratch[0]
+fn reduce_scratch(index: u32) {
+    for (var stride = WORKGROUP_SIZE / 2u; stride > 0u; stride = stride / 2u) {
+        workgroupBarrier();
+        if index < stride {
+            scratch[index] = max(scratch[index], scratch[index + stride]);
+        }
+    }
+    workgroupBarrier();
+}
+
+@compute @workgroup_size(16, 16)
+fn combine(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    var error = 0.0;
+
+    if global_id.x < settings.grid_width && global_id.y < settings.grid_height {
+        let pos = vec2<i32>(global_id.xy);
+        let u = textureLoad(previous_field, pos, 0).rg;
+        let euler = textureLoad(euler_field, pos, 0).rg;
+        let heun = clamp(
+            (u + textureLoad(euler_twice_field, pos, 0).rg) * 0.5,
+            vec2<f32>(settings.field_min),
+            vec2<f32>(settings.field_max),
+        );
+        let difference = abs(heun - euler);
+        error = max(difference.x, difference.y);
+        textureStore(heun_dst, pos, vec4<f32>(heun, 0.0, 1.0));
+    }
+
+    scratch[local_index] = error;
+    reduce_scratch(local_index);
+
+    if local_index == 0u {
+        partials[workgroup_id.y * num_workgroups.x + workgroup_id.x] = scratch[0];
+    }
+}
+
+@compute @workgroup_size(256)
+fn control_step(@builtin(local_invocation_index) local_index: u32) {
+    var error = 0.0;
+    for (var i = local_index; i < settings.num_partials; i += WORKGROUP_SIZE) {
+        error = max(error, partials[i]);
+    }
+
+    scratch[local_index] = error;
+    reduce_scratch(local_index);
+
+    if local_index != 0u {
+        return;
+    }
+
+    error = scratch[0];
+    let dt = control.dt;
+    // Steps already at min_dt are kept whatever the error, so the run can't stall
+    let accepted = error <= settings.tolerance || dt <= settings.min_dt;
+    if accepted {
+        let y = dt - control.compensation;
+        let time = control.time + y;
+        control.compensation = (time - control.time) - y;
+        control.time = time;
+        control.last_dt = dt;
+        control.accepted_steps += 1u;
+    } else {
+        control.rejected_steps += 1u;
+    }
+    control.accepted = select(0u, 1u, accepted);
+    control.error = error;
+
+    // The Euler error grows like dt^2
+    let factor = SAFETY * sqrt(settings.tolerance / max(error, 1e-12));
+    control.dt = clamp(dt * clamp(factor, MIN_FACTOR, MAX_FACTOR), settings.min_dt, settings.max_dt);
+}
+
+@compute @workgroup_size(8, 8)
+fn commit(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if global_id.x >= settings.grid_width || global_id.y >= settings.grid_height {
+        return;
+    }
+
+    let pos = vec2<i32>(global_id.xy);
+    var uv = textureLoad(previous_field, pos, 0).rg;
+    if control.accepted == 1u {
+        uv = textureLoad(heun_field, pos, 0).rg;
+    }
+    textureStore(texture_dst, pos, vec4<f32>(uv, 0.0, 1.0));
+}
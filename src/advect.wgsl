@@ -0,0 +1,107 @@
+// Semi-Lagrangian advection of U and V, run before the reaction-diffusion step
+// when a flow is set. Each cell traces its velocity back over one time step and
+// takes the bilinearly interpolated field value found there.
+
+struct FlowParams {
+    mode: u32,           // 0=none, 1=drift, 2=vortex, 3=shear, 4=texture
+    boundary_mode: u32,  // 0=wrap, 1=clamp, 2=reflect
+    grid_width: u32,
+    grid_height: u32,
+    delta_time: f32,
+    speed: f32,
+    center_x: f32,
+    center_y: f32,
+    drift_x: f32,
+    drift_y: f32,
+    radius: f32,
+    bands: f32,
+    scale: f32,
+    _padding1: u32,
+    _padding2: u32,
+    _padding3: u32,
+}
+
+@group(0) @binding(0) var texture_src: texture_2d<f32>;
+@group(0) @binding(1) var texture_dst: texture_storage_2d<rg32float, write>;
+@group(0) @binding(2) var velocity_texture: texture_2d<f32>;
+@group(0) @binding(3) var<uniform> flow: FlowParams;
+
+// Like apply_boundary in compute.wgsl, but for traces that may land far outside the grid
+fn wrap_coord(coord: i32, size: i32) -> i32 {
+    if flow.boundary_mode == 0u {
+        return ((coord % size) + size) % size;
+    } else if flow.boundary_mode == 1u {
+        return clamp(coord, 0, size - 1);
+    } else {
+        // Reflect: mirror within a period of 2 * (size - 1)
+        let period = max(2 * (size - 1), 1);
+        let c = ((coord % period) + period) % period;
+        return min(c, period - c);
+    }
+}
+
+fn load_field(coord: vec2<i32>) -> vec2<f32> {
+    let x = wrap_coord(coord.x, i32(flow.grid_width));
+    let y = wrap_coord(coord.y, i32(flow.grid_height));
+    return textureLoad(texture_src, vec2<i32>(x, y), 0).rg;
+}
+
+fn sample_field(p: vec2<f32>) -> vec2<f32> {
+    let base = floor(p);
+    let f = p - base;
+    let i = vec2<i32>(base);
+    let top = mix(load_field(i), load_field(i + vec2<i32>(1, 0)), f.x);
+    let bottom = mix(load_field(i + vec2<i32>(0, 1)), load_field(i + vec2<i32>(1, 1)), f.x);
+    return mix(top, bottom, f.y);
+}
+
+// Bilinear lookup in the velocity texture, which is stretched over the grid
+fn sample_velocity(p: vec2<f32>, size: vec2<f32>) -> vec2<f32> {
+    let dims = vec2<i32>(textureDimensions(velocity_texture));
+    let q = (p + 0.5) / size * vec2<f32>(dims) - 0.5;
+    let base = floor(q);
+    let f = q - base;
+    let i = vec2<i32>(base);
+    let hi = dims - 1;
+    let v00 = textureLoad(velocity_texture, clamp(i, vec2<i32>(0), hi), 0).rg;
+    let v10 = textureLoad(velocity_texture, clamp(i + vec2<i32>(1, 0), vec2<i32>(0), hi), 0).rg;
+    let v01 = textureLoad(velocity_texture, clamp(i + vec2<i32>(0, 1), vec2<i32>(0), hi), 0).rg;
+    let v11 = textureLoad(velocity_texture, clamp(i + vec2<i32>(1, 1), vec2<i32>(0), hi), 0).rg;
+    return mix(mix(v00, v10, f.x), mix(v01, v11, f.x), f.y);
+}
+
+// Velocity in cells per unit time at cell position p
+fn velocity(p: vec2<f32>) -> vec2<f32> {
+    let size = vec2<f32>(f32(flow.grid_width), f32(flow.grid_height));
+    switch flow.mode {
+        case 1u: {
+            return vec2<f32>(flow.drift_x, flow.drift_y);
+        }
+        case 2u: {
+            let d = p - vec2<f32>(flow.center_x, flow.center_y) * size;
+            let falloff = flow.radius * min(size.x, size.y);
+            return flow.speed * vec2<f32>(-d.y, d.x) * exp(-dot(d, d) / (falloff * falloff));
+        }
+        case 3u: {
+            return vec2<f32>(flow.speed * sin(6.28318530718 * flow.bands * p.y / size.y), 0.0);
+        }
+        case 4u: {
+            return flow.scale * sample_velocity(p, size);
+        }
+        default: {
+            return vec2<f32>(0.0);
+        }
+    }
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if global_id.x >= flow.grid_width || global_id.y >= flow.grid_height {
+        return;
+    }
+
+    let pos = vec2<i32>(global_id.xy);
+    let p = vec2<f32>(pos);
+    let upstream = p - velocity(p) * flow.delta_time;
+    textureStore(texture_dst, pos, vec4<f32>(sample_field(upstream), 0.0, 1.0));
+}
@@ -0,0 +1,276 @@
+// Headless command-line runner: loads a scene, runs the WGSL pipelines on a
+// native adapter (or the software fallback) and writes PNGs and state dumps.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    if let Err(e) = native::run() {
+        eprintln!("rd-sim: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::path::{Path, PathBuf};
+
+    use rd_sim_wgpu::dump::StateDump;
+    use rd_sim_wgpu::image;
+    use rd_sim_wgpu::presets;
+    use rd_sim_wgpu::scene::{self, Scene};
+    use rd_sim_wgpu::simulation::{self, Simulation};
+
+    const USAGE: &str = "\
+Usage: rd-sim [options]
+
+  --scene FILE           scene .json or .toml (defaults to the built-in scene)
+  --preset NAME          start from a built-in preset instead of a scene file
+  --list-presets         list the built-in presets and exit
+  --resume FILE          start from a state dump instead of the scene's initial field
+  --size N               grid width and height (default 512)
+  --width N, --height N  grid size per axis
+  --steps N              steps to run (default 10000)
+  --png FILE             write the final field as a PNG
+  --dump FILE            write the final field as a state dump
+  --snapshot-every N     also write a PNG and dump every N steps...
+  --snapshot-dir DIR     ...into DIR (default: snapshots)
+  --block-steps N        Gray-Scott steps per dispatch with temporal blocking (1-4, default 1)
+  --half                 store the field as f16 if the adapter can (steps still compute in f32)
+  --software             use the software adapter even if a GPU is available
+  --quiet                only print errors
+  --help                 show this message";
+
+    // Steps submitted between waits on the device, which keeps the queue short
+    // and paces progress output
+    const CHUNK_STEPS: u32 = 500;
+
+    struct Options {
+        scene: Option<PathBuf>,
+        preset: Option<String>,
+        resume: Option<PathBuf>,
+        width: u32,
+        height: u32,
+        steps: u32,
+        png: Option<PathBuf>,
+        dump: Option<PathBuf>,
+        snapshot_every: u32,
+        snapshot_dir: PathBuf,
+        block_steps: u32,
+        half: bool,
+        software: bool,
+        quiet: bool,
+    }
+
+    fn parse_args() -> Result<Options, String> {
+        let mut options = Options {
+            scene: None,
+            preset: None,
+            resume: None,
+            width: 512,
+            height: 512,
+            steps: 10_000,
+            png: None,
+            dump: None,
+            snapshot_every: 0,
+            snapshot_dir: PathBuf::from("snapshots"),
+            block_steps: 1,
+            half: false,
+            software: false,
+            quiet: false,
+        };
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            let number = |text: String| text.parse::<u32>().map_err(|_| format!("Invalid number '{}' for {}", text, arg));
+
+            match arg.as_str() {
+                "--scene" => options.scene = Some(value()?.into()),
+                "--preset" => options.preset = Some(value()?),
+                "--list-presets" => {
+                    for preset in presets::list_presets() {
+                        println!("{:<12} F={:<7} k={:<7} {}", preset.name, preset.feed, preset.kill, preset.label);
+                    }
+                    std::process::exit(0);
+                }
+                "--resume" => options.resume = Some(value()?.into()),
+                "--size" => {
+                    options.width = number(value()?)?;
+                    options.height = options.width;
+                }
+                "--width" => options.width = number(value()?)?,
+                "--height" => options.height = number(value()?)?,
+                "--steps" => options.steps = number(value()?)?,
+                "--png" => options.png = Some(value()?.into()),
+                "--dump" => options.dump = Some(value()?.into()),
+                "--snapshot-every" => options.snapshot_every = number(value()?)?,
+                "--snapshot-dir" => options.snapshot_dir = value()?.into(),
+                "--block-steps" => options.block_steps = number(value()?)?,
+                "--half" => options.half = true,
+                "--software" => options.software = true,
+                "--quiet" => options.quiet = true,
+                "--help" | "-h" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                _ => return Err(format!("Unknown argument '{}'\n\n{}", arg, USAGE)),
+            }
+        }
+
+        if options.scene.is_some() && options.preset.is_some() {
+            return Err("--scene and --preset can't be combined".to_string());
+        }
+        if options.width == 0 || options.height == 0 {
+            return Err("Grid size must be at least 1x1".to_string());
+        }
+        if !(1..=simulation::MAX_BLOCK_STEPS).contains(&options.block_steps) {
+            return Err(format!("--block-steps must be 1 to {}", simulation::MAX_BLOCK_STEPS));
+        }
+        Ok(options)
+    }
+
+    fn write_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn write_png(path: &Path, sim: &Simulation, field: &[f32], gradient: &[(f32, [f32; 3])]) -> Result<(), String> {
+        let v: Vec<f32> = field.chunks(2).map(|uv| sim.equation.display_value(uv[0], uv[1])).collect();
+        let png = image::encode_png(sim.width(), sim.height(), &image::colorize(&v, gradient))?;
+        write_file(path, &png)
+    }
+
+    fn write_dump(path: &Path, sim: &Simulation, field: Vec<f32>, step: u64) -> Result<(), String> {
+        let dump = StateDump {
+            width: sim.width(),
+            height: sim.height(),
+            step,
+            field,
+        };
+        write_file(path, &dump.encode())
+    }
+
+    pub fn run() -> Result<(), String> {
+        let options = parse_args()?;
+        let log = |message: String| {
+            if !options.quiet {
+                println!("{}", message);
+            }
+        };
+
+        let scene = match (&options.scene, &options.preset) {
+            (Some(path), _) => scene::load_scene_file(path)?,
+            (None, Some(name)) => presets::get_preset(name)
+                .ok_or_else(|| format!("Unknown preset '{}' (see --list-presets)", name))?
+                .scene(),
+            (None, None) => Scene::default(),
+        };
+
+        let resume = match &options.resume {
+            Some(path) => {
+                let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                Some(StateDump::decode(&bytes)?)
+            }
+            None => None,
+        };
+        // A resumed run keeps the grid size of its dump
+        let (width, height) = resume.as_ref().map_or((options.width, options.height), |d| (d.width, d.height));
+
+        let (adapter, device, queue) = pollster::block_on(simulation::request_headless_device_with(options.software))?;
+        let info = adapter.get_info();
+        log(format!("Adapter: {} ({:?}, {:?})", info.name, info.backend, info.device_type));
+
+        let format = match simulation::half_field_format(&adapter).filter(|_| options.half) {
+            Some(format) => format,
+            None => {
+                if options.half {
+                    eprintln!("rd-sim: keeping the f32 field: the adapter has no f16 storage format");
+                }
+                simulation::preferred_field_format(&adapter)
+            }
+        };
+        log(format!("Field format: {:?}", format));
+        let mut sim = Simulation::new(&device, &queue, width, height, format);
+        scene.start(&mut sim)?;
+        if let Some(reason) = sim.solver_fallback() {
+            eprintln!("rd-sim: using the explicit solver: {}", reason);
+        }
+        if let Some(reason) = sim.adaptive_fallback() {
+            eprintln!("rd-sim: using a fixed time step: {}", reason);
+        }
+        sim.block_steps = options.block_steps;
+        sim.update_params();
+        if let Some(reason) = sim.blocking_fallback() {
+            eprintln!("rd-sim: stepping without temporal blocking: {}", reason);
+        }
+        let gradient = scene.gradient_stops();
+
+        // Steps are counted from the start of the scene, across resumes
+        let mut step = 0u64;
+        if let Some(dump) = resume {
+            sim.write_field(&dump.field)?;
+            step = dump.step;
+        }
+
+        log(format!("Running {} steps on a {}x{} grid", options.steps, width, height));
+        let started = std::time::Instant::now();
+        let mut remaining = options.steps;
+        while remaining > 0 {
+            let mut chunk = remaining.min(CHUNK_STEPS);
+            if options.snapshot_every > 0 {
+                let until_snapshot = options.snapshot_every - (step % options.snapshot_every as u64) as u32;
+                chunk = chunk.min(until_snapshot);
+            }
+
+            sim.step(chunk);
+            remaining -= chunk;
+            step += chunk as u64;
+            device
+                .poll(wgpu::PollType::wait_indefinitely())
+                .map_err(|e| format!("Failed to poll device: {:?}", e))?;
+
+            if options.snapshot_every > 0 && step.is_multiple_of(options.snapshot_every as u64) {
+                let field = pollster::block_on(sim.read_field())?;
+                let stem = options.snapshot_dir.join(format!("step_{:08}", step));
+                write_png(&stem.with_extension("png"), &sim, &field, &gradient)?;
+                write_dump(&stem.with_extension("rdsf"), &sim, field, step)?;
+                log(format!("Snapshot at step {}", step));
+            }
+        }
+
+        let elapsed = started.elapsed().as_secs_f64();
+        log(format!(
+            "Finished in {:.2}s ({:.0} steps/s)",
+            elapsed,
+            options.steps as f64 / elapsed.max(1e-9)
+        ));
+        if let Some(state) = pollster::block_on(sim.read_time_step())? {
+            sim.sync_time_step(state);
+            log(format!(
+                "Simulated time {:.1}: {} steps kept, {} rejected, dt now {:.3}",
+                sim.sim_time(),
+                state.accepted_steps,
+                state.rejected_steps,
+                state.dt
+            ));
+        }
+
+        if options.png.is_some() || options.dump.is_some() {
+            let field = pollster::block_on(sim.read_field())?;
+            if let Some(path) = &options.png {
+                write_png(path, &sim, &field, &gradient)?;
+                log(format!("Wrote {}", path.display()));
+            }
+            if let Some(path) = &options.dump {
+                write_dump(path, &sim, field, step)?;
+                log(format!("Wrote {}", path.display()));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,335 @@
+// Pattern classification into Pearson-style classes. Works on a V field read back
+// from the GPU: the field is split into a high-V and a low-V phase at the middle
+// of its range, the connected components of the smaller phase are measured (spots
+// are round islands of high V, holes round islands of low V), and the local
+// orientation coherence of the V gradient separates stripes from labyrinths.
+// Temporal change (see `FieldStats::change`) flags waves and chaos.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PatternClass {
+    Extinct,
+    Uniform,
+    Waves, // still changing quickly: travelling waves, spirals, chaos
+    Spots,
+    Stripes,
+    Labyrinth,
+    Holes,
+}
+
+impl PatternClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatternClass::Extinct => "extinct",
+            PatternClass::Uniform => "uniform",
+            PatternClass::Waves => "waves",
+            PatternClass::Spots => "spots",
+            PatternClass::Stripes => "stripes",
+            PatternClass::Labyrinth => "labyrinth",
+            PatternClass::Holes => "holes",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ClassifierConfig {
+    pub extinct_v: f32,        // max V below this counts as extinct
+    pub uniform_range: f32,    // V range below this counts as uniform
+    pub wave_change: f32,      // RMS change per step above this counts as waves
+    pub min_area: u32,         // components smaller than this (in cells) are noise
+    pub roundness: f32,        // minority components at least this round are spots/holes
+    pub coherence: f32,        // orientation coherence above this makes stripes
+    pub block_size: u32,       // side of the blocks the coherence is measured over
+}
+
+impl Default for ClassifierConfig {
+    fn default() -> Self {
+        Self {
+            extinct_v: 0.01,
+            uniform_range: 0.05,
+            wave_change: 2e-4,
+            min_area: 4,
+            roundness: 0.5,
+            coherence: 0.6,
+            block_size: 32,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PatternFeatures {
+    pub min_v: f32,
+    pub max_v: f32,
+    pub mean_v: f32,
+    pub coverage: f32,       // fraction of cells in the high-V phase
+    pub high_components: u32,
+    pub low_components: u32,
+    pub roundness: f32,      // area-weighted roundness of the minority phase's components (1 = square/disc)
+    pub coherence: f32,      // energy-weighted local orientation coherence (1 = straight stripes)
+    pub change: f32,         // RMS change per step, as passed in
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Classification {
+    pub class: PatternClass,
+    pub features: PatternFeatures,
+}
+
+struct Component {
+    area: u32,
+    perimeter: u32,
+}
+
+// Labels the 4-connected components of the cells where `mask` equals `phase`
+fn components(mask: &[bool], width: usize, height: usize, phase: bool, periodic: bool) -> Vec<Component> {
+    let mut visited = vec![false; mask.len()];
+    let mut stack = Vec::new();
+    let mut found = Vec::new();
+
+    let neighbours = |index: usize| {
+        let (x, y) = (index % width, index / width);
+        let mut out = [None; 4];
+        let steps = [(-1i64, 0i64), (1, 0), (0, -1), (0, 1)];
+        for (slot, (dx, dy)) in out.iter_mut().zip(steps) {
+            let (mut nx, mut ny) = (x as i64 + dx, y as i64 + dy);
+            if periodic {
+                nx = nx.rem_euclid(width as i64);
+                ny = ny.rem_euclid(height as i64);
+            } else if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                continue;
+            }
+            *slot = Some(ny as usize * width + nx as usize);
+        }
+        out
+    };
+
+    for start in 0..mask.len() {
+        if visited[start] || mask[start] != phase {
+            continue;
+        }
+
+        let mut component = Component { area: 0, perimeter: 0 };
+        visited[start] = true;
+        stack.push(start);
+        while let Some(index) = stack.pop() {
+            component.area += 1;
+            for neighbour in neighbours(index).into_iter().flatten() {
+                if mask[neighbour] != phase {
+                    component.perimeter += 1;
+                } else if !visited[neighbour] {
+                    visited[neighbour] = true;
+                    stack.push(neighbour);
+                }
+            }
+        }
+        found.push(component);
+    }
+
+    found
+}
+
+// Mean coherence of the structure tensor over block_size x block_size blocks,
+// weighted by each block's gradient energy
+fn orientation_coherence(v: &[f32], width: usize, height: usize, block_size: usize, periodic: bool) -> f32 {
+    let at = |x: i64, y: i64| {
+        let (x, y) = if periodic {
+            (x.rem_euclid(width as i64), y.rem_euclid(height as i64))
+        } else {
+            (x.clamp(0, width as i64 - 1), y.clamp(0, height as i64 - 1))
+        };
+        v[y as usize * width + x as usize] as f64
+    };
+
+    let block_size = block_size.clamp(1, width.min(height));
+    let mut weighted = 0.0;
+    let mut total_energy = 0.0;
+
+    for block_y in (0..height).step_by(block_size) {
+        for block_x in (0..width).step_by(block_size) {
+            let (mut jxx, mut jyy, mut jxy) = (0.0, 0.0, 0.0);
+            for y in block_y..(block_y + block_size).min(height) {
+                for x in block_x..(block_x + block_size).min(width) {
+                    let (x, y) = (x as i64, y as i64);
+                    let gx = (at(x + 1, y) - at(x - 1, y)) * 0.5;
+                    let gy = (at(x, y + 1) - at(x, y - 1)) * 0.5;
+                    jxx += gx * gx;
+                    jyy += gy * gy;
+                    jxy += gx * gy;
+                }
+            }
+
+            let energy = jxx + jyy;
+            if energy > 1e-12 {
+                let coherence = ((jxx - jyy).powi(2) + 4.0 * jxy * jxy).sqrt() / energy;
+                weighted += coherence * energy;
+                total_energy += energy;
+            }
+        }
+    }
+
+    if total_energy > 0.0 {
+        (weighted / total_energy) as f32
+    } else {
+        0.0
+    }
+}
+
+// Measures a `width` x `height` V field (row-major) and the RMS change per step
+pub fn extract_features(
+    v: &[f32],
+    width: u32,
+    height: u32,
+    change: f32,
+    periodic: bool,
+    config: &ClassifierConfig,
+) -> PatternFeatures {
+    let (width, height) = (width as usize, height as usize);
+    assert_eq!(v.len(), width * height, "field size does not match its dimensions");
+
+    let min_v = v.iter().cloned().fold(f32::MAX, f32::min);
+    let max_v = v.iter().cloned().fold(f32::MIN, f32::max);
+    let mean_v = (v.iter().map(|&x| x as f64).sum::<f64>() / v.len() as f64) as f32;
+
+    let mut features = PatternFeatures {
+        min_v,
+        max_v,
+        mean_v,
+        change,
+        ..Default::default()
+    };
+    if max_v - min_v < config.uniform_range {
+        return features;
+    }
+
+    let threshold = 0.5 * (min_v + max_v);
+    let mask: Vec<bool> = v.iter().map(|&x| x > threshold).collect();
+    let high_area = mask.iter().filter(|&&high| high).count();
+    features.coverage = high_area as f32 / mask.len() as f32;
+
+    let significant = |found: Vec<Component>| -> Vec<Component> {
+        found.into_iter().filter(|c| c.area >= config.min_area).collect()
+    };
+    let high = significant(components(&mask, width, height, true, periodic));
+    let low = significant(components(&mask, width, height, false, periodic));
+    features.high_components = high.len() as u32;
+    features.low_components = low.len() as u32;
+
+    // A square of side s has 16 * s^2 / (4 * s)^2 = 1; a long stripe tends to 0.
+    // Components with no boundary (the phase fills the grid) don't count as islands.
+    let minority = if features.coverage <= 0.5 { &high } else { &low };
+    let (mut weighted, mut area) = (0.0, 0.0);
+    for component in minority.iter().filter(|c| c.perimeter > 0) {
+        let roundness = (16.0 * component.area as f64 / (component.perimeter as f64).powi(2)).min(1.0);
+        weighted += roundness * component.area as f64;
+        area += component.area as f64;
+    }
+    features.roundness = if area > 0.0 { (weighted / area) as f32 } else { 0.0 };
+
+    features.coherence = orientation_coherence(v, width, height, config.block_size as usize, periodic);
+    features
+}
+
+pub fn classify_features(features: &PatternFeatures, config: &ClassifierConfig) -> PatternClass {
+    if features.max_v < config.extinct_v {
+        PatternClass::Extinct
+    } else if features.max_v - features.min_v < config.uniform_range {
+        PatternClass::Uniform
+    } else if features.change > config.wave_change {
+        PatternClass::Waves
+    } else if features.roundness >= config.roundness {
+        if features.coverage <= 0.5 {
+            PatternClass::Spots
+        } else {
+            PatternClass::Holes
+        }
+    } else if features.coherence >= config.coherence {
+        PatternClass::Stripes
+    } else {
+        PatternClass::Labyrinth
+    }
+}
+
+// V channel of interleaved (U, V) pairs as returned by `Simulation::read_field`
+pub fn v_channel(field: &[f32]) -> Vec<f32> {
+    field.iter().skip(1).step_by(2).cloned().collect()
+}
+
+pub fn classify(
+    v: &[f32],
+    width: u32,
+    height: u32,
+    change: f32,
+    periodic: bool,
+    config: &ClassifierConfig,
+) -> Classification {
+    let features = extract_features(v, width, height, change, periodic, config);
+    Classification {
+        class: classify_features(&features, config),
+        features,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const SIZE: u32 = 128;
+
+    fn field(f: impl Fn(f32, f32) -> f32) -> Vec<f32> {
+        (0..SIZE * SIZE).map(|i| f((i % SIZE) as f32, (i / SIZE) as f32)).collect()
+    }
+
+    // Discs of radius 4 on a 16-cell lattice
+    fn spot_field(inside: f32, outside: f32) -> Vec<f32> {
+        field(|x, y| {
+            let (dx, dy) = (x % 16.0 - 8.0, y % 16.0 - 8.0);
+            if dx * dx + dy * dy < 16.0 { inside } else { outside }
+        })
+    }
+
+    fn class_of(v: &[f32], change: f32) -> PatternClass {
+        classify(v, SIZE, SIZE, change, true, &ClassifierConfig::default()).class
+    }
+
+    #[test]
+    fn flat_fields() {
+        assert_eq!(class_of(&field(|_, _| 0.0), 0.0), PatternClass::Extinct);
+        assert_eq!(class_of(&field(|x, _| 0.2 + 0.01 * (x / 16.0).sin()), 0.0), PatternClass::Uniform);
+    }
+
+    #[test]
+    fn spots_and_holes() {
+        let spots = classify(&spot_field(0.4, 0.0), SIZE, SIZE, 0.0, true, &ClassifierConfig::default());
+        assert_eq!(spots.class, PatternClass::Spots);
+        assert_eq!(spots.features.high_components, 64);
+        assert_eq!(spots.features.low_components, 1);
+
+        let holes = classify(&spot_field(0.0, 0.4), SIZE, SIZE, 0.0, true, &ClassifierConfig::default());
+        assert_eq!(holes.class, PatternClass::Holes);
+        assert_eq!(holes.features.low_components, 64);
+    }
+
+    #[test]
+    fn stripes_and_labyrinths() {
+        let stripes = field(|x, y| 0.2 + 0.2 * (2.0 * PI * (x + 0.5 * y) / 16.0).sin());
+        assert_eq!(class_of(&stripes, 0.0), PatternClass::Stripes);
+
+        // Superposed waves in many directions at one wavelength: tangled, curving bands
+        let labyrinth = field(|x, y| {
+            let sum: f32 = (0..7)
+                .map(|i| {
+                    let angle = i as f32 * PI / 7.0;
+                    let phase = (i * i) as f32 * 1.3;
+                    (2.0 * PI * (x * angle.cos() + y * angle.sin()) / 12.0 + phase).cos()
+                })
+                .sum();
+            0.2 + 0.05 * sum
+        });
+        assert_eq!(class_of(&labyrinth, 0.0), PatternClass::Labyrinth);
+    }
+
+    #[test]
+    fn fast_change_means_waves() {
+        assert_eq!(class_of(&spot_field(0.4, 0.0), 1e-3), PatternClass::Waves);
+    }
+}
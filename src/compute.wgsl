@@ -1,5 +1,7 @@
 // Gray-Scott reaction-diffusion compute shader
 // Uses ping-pong RG32Float textures (R channel=U, G channel=V)
+// Also integrates the complex Ginzburg-Landau and Swift-Hohenberg equations
+// (see equation.rs) on the same field
 
 struct SimParams {
     feed_rate: f32,      // F parameter
@@ -13,9 +15,20 @@ struct SimParams {
     kernel_type: u32,    // 0=default, 1=cross, 2=diagonal, 3=spiral
     boundary_mode: u32,  // 0=wrap, 1=clamp, 2=reflect
     map_mode: u32,       // 0=off, 1=parameter map mode
-    _padding: u32,
+    time: f32,           // steps since the field was seeded, for F/k expressions
+    equation: u32,       // 0=gray-scott, 1=complex ginzburg-landau, 2=swift-hohenberg
+    equation_a: f32,     // CGL alpha, SH r
+    equation_b: f32,     // CGL beta, SH q0
+    equation_c: f32,     // SH g
+    block_steps: u32,    // steps per dispatch of main_blocked
+    _padding1: u32,
+    _padding2: u32,
+    _padding3: u32,
 }
 
+// Keeps the unclamped equations finite if a time step is too large (equation::FIELD_LIMIT)
+const FIELD_LIMIT: f32 = 10.0;
+
 @group(0) @binding(0) var texture_src: texture_2d<f32>;
 @group(0) @binding(1) var texture_dst: texture_storage_2d<rg32float, write>;
 @group(0) @binding(2) var texture_sampler: sampler;
@@ -170,37 +183,95 @@ fn laplacian(pos: vec2<i32>) -> vec2<f32> {
     }
 }
 
-@compute @workgroup_size(8, 8)
-fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let pos = vec2<i32>(global_id.xy);
-    let dims = vec2<i32>(i32(params.grid_width), i32(params.grid_height));
+// F and k at a cell. Simulation::set_expressions compiles user expressions into
+// these bodies; by default they return the uniform settings.
+fn feed_at(x: f32, y: f32, t: f32, r: f32, theta: f32, feed: f32, kill: f32) -> f32 {
+    return feed; // FEED_EXPRESSION
+}
+
+fn kill_at(x: f32, y: f32, t: f32, r: f32, theta: f32, feed: f32, kill: f32) -> f32 {
+    return kill; // KILL_EXPRESSION
+}
+
+// (F, k) at a cell: the expressions above, or the parameter map in map mode
+fn rates_at(pos: vec2<i32>) -> vec2<f32> {
+    let norm_x = f32(pos.x) / f32(params.grid_width);
+    let norm_y = f32(pos.y) / f32(params.grid_height);
+    let r = distance(vec2<f32>(norm_x, norm_y), vec2<f32>(0.5));
+    let theta = atan2(norm_y - 0.5, norm_x - 0.5);
+    var feed = feed_at(norm_x, norm_y, params.time, r, theta, params.feed_rate, params.kill_rate);
+    var kill = kill_at(norm_x, norm_y, params.time, r, theta, params.feed_rate, params.kill_rate);
     
-    // Boundary check
-    if (pos.x >= dims.x || pos.y >= dims.y) {
-        return;
+    if params.map_mode != 0u {
+        // Map mode: F varies along Y axis, k varies along X axis
+        kill = mix(0.045, 0.070, norm_x);  // k: 0.045 to 0.070 along X
+        feed = mix(0.010, 0.100, norm_y);  // F: 0.01 to 0.1 along Y
     }
-    
-    let uv = textureLoad(texture_src, pos, 0).rg;
+    return vec2<f32>(feed, kill);
+}
+
+// Injected noise for U and V, -1..1 times noise_strength (half that on V)
+fn noise_at(pos: vec2<i32>) -> vec2<f32> {
+    let noise = hash(vec2<u32>(pos)) * 2.0 - 1.0;
+    return vec2<f32>(noise, noise * 0.5) * params.noise_strength;
+}
+
+// Complex Ginzburg-Landau with A = U + iV:
+// dA/dt = A + (1 + i alpha) D lap(A) - (1 + i beta) |A|^2 A
+fn ginzburg_landau(pos: vec2<i32>) -> vec2<f32> {
+    let a = textureLoad(texture_src, pos, 0).rg;
+    let lap = laplacian(pos) * params.diffuse_u;
+    let alpha = params.equation_a;
+    let beta = params.equation_b;
+
+    let coupling = vec2<f32>(lap.x - alpha * lap.y, lap.y + alpha * lap.x);
+    let saturation = dot(a, a) * vec2<f32>(a.x - beta * a.y, a.y + beta * a.x);
+    let next = a + (a + coupling - saturation) * params.delta_time;
+    return clamp(next, vec2<f32>(-FIELD_LIMIT), vec2<f32>(FIELD_LIMIT));
+}
+
+fn load_u(x: i32, y: i32) -> f32 {
+    let coord = vec2<i32>(
+        apply_boundary(x, i32(params.grid_width), params.boundary_mode),
+        apply_boundary(y, i32(params.grid_height), params.boundary_mode),
+    );
+    return textureLoad(texture_src, coord, 0).r;
+}
+
+// Swift-Hohenberg: du/dt = r u - (q0^2 + lap)^2 u + g u^2 - u^3
+// lap is the 5-point stencil and the biharmonic is that stencil applied twice:
+// [          1          ]
+// [      2  -8   2      ]
+// [  1  -8  20  -8   1  ]
+// [      2  -8   2      ]
+// [          1          ]
+fn swift_hohenberg(pos: vec2<i32>) -> f32 {
+    let u = load_u(pos.x, pos.y);
+    let near = load_u(pos.x - 1, pos.y) + load_u(pos.x + 1, pos.y) + load_u(pos.x, pos.y - 1) + load_u(pos.x, pos.y + 1);
+    let diagonal = load_u(pos.x - 1, pos.y - 1) + load_u(pos.x + 1, pos.y - 1) + load_u(pos.x - 1, pos.y + 1) + load_u(pos.x + 1, pos.y + 1);
+    let far = load_u(pos.x - 2, pos.y) + load_u(pos.x + 2, pos.y) + load_u(pos.x, pos.y - 2) + load_u(pos.x, pos.y + 2);
+
+    let lap = near - 4.0 * u;
+    let bilap = 20.0 * u - 8.0 * near + 2.0 * diagonal + far;
+
+    let r = params.equation_a;
+    let q2 = params.equation_b * params.equation_b;
+    let g = params.equation_c;
+    let du_dt = (r - q2 * q2) * u - 2.0 * q2 * lap - bilap + g * u * u - u * u * u;
+    return clamp(u + du_dt * params.delta_time, -FIELD_LIMIT, FIELD_LIMIT);
+}
+
+// One Gray-Scott step of a cell with field uv and Laplacian lap
+fn gray_scott(pos: vec2<i32>, uv: vec2<f32>, lap: vec2<f32>) -> vec2<f32> {
     let u = uv.r;
     let v = uv.g;
-    
-    // Compute Laplacian
-    let lap = laplacian(pos);
     let laplacian_u = lap.r;
     let laplacian_v = lap.g;
     
-    // Calculate position-dependent parameters for map mode
-    var feed = params.feed_rate;
-    var kill = params.kill_rate;
-    
-    if params.map_mode != 0u {
-        // Map mode: F varies along Y axis, k varies along X axis
-        let norm_x = f32(pos.x) / f32(params.grid_width);
-        let norm_y = f32(pos.y) / f32(params.grid_height);
-        
-        kill = mix(0.045, 0.070, norm_x);  // k: 0.045 to 0.070 along X
-        feed = mix(0.010, 0.100, norm_y);  // F: 0.01 to 0.1 along Y
-    }
+    // Calculate position-dependent parameters
+    let rates = rates_at(pos);
+    let feed = rates.x;
+    let kill = rates.y;
     
     let uvv = u * v * v;
     
@@ -213,13 +284,292 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     
     // Add noise injection if enabled
     if params.noise_strength > 0.0 {
-        let noise = hash(vec2<u32>(pos)) * 2.0 - 1.0; // Range: -1 to 1
-        new_u += noise * params.noise_strength;
-        new_v += noise * params.noise_strength * 0.5; // Less noise on V
+        let noise = noise_at(pos);
+        new_u += noise.x;
+        new_v += noise.y;
     }
     
     // Clamp to valid range
-    let result = vec2<f32>(clamp(new_u, 0.0, 1.0), clamp(new_v, 0.0, 1.0));
+    return vec2<f32>(clamp(new_u, 0.0, 1.0), clamp(new_v, 0.0, 1.0));
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let pos = vec2<i32>(global_id.xy);
+    let dims = vec2<i32>(i32(params.grid_width), i32(params.grid_height));
+    
+    // Boundary check
+    if (pos.x >= dims.x || pos.y >= dims.y) {
+        return;
+    }
+
+    if params.equation == 1u {
+        textureStore(texture_dst, pos, vec4<f32>(ginzburg_landau(pos), 0.0, 1.0));
+        return;
+    }
+    if params.equation == 2u {
+        textureStore(texture_dst, pos, vec4<f32>(swift_hohenberg(pos), 0.0, 0.0, 1.0));
+        return;
+    }
+    
+    let uv = textureLoad(texture_src, pos, 0).rg;
+    textureStore(texture_dst, pos, vec4<f32>(gray_scott(pos, uv, laplacian(pos)), 0.0, 1.0));
+}
+
+
+// Workgroup-tiled variant of main (Simulation::tiled_kernel). Each workgroup
+// loads its 16x16 cells plus a 2-cell halo, enough for the 5x5 spiral kernel,
+// into workgroup memory once, so boundaries are applied once per loaded cell
+// and the Laplacian reads shared memory instead of up to 13 texture loads.
+// Only Gray-Scott uses the tile; the other equations run as in main.
+const TILE_SIZE: i32 = 16;
+const TILE_HALO: i32 = 2;
+const TILE_SIDE: i32 = 20; // TILE_SIZE + 2 * TILE_HALO
+
+var<workgroup> tile: array<vec2<f32>, 400>; // TILE_SIDE * TILE_SIDE
+
+// The tile value at offset (dx, dy) from the cell at `local` in its workgroup
+fn tile_at(local: vec2<i32>, dx: i32, dy: i32) -> vec2<f32> {
+    return tile[(local.y + TILE_HALO + dy) * TILE_SIDE + local.x + TILE_HALO + dx];
+}
+
+// laplacian() read from the tile, with the same weights and summation order
+fn tile_laplacian(local: vec2<i32>) -> vec2<f32> {
+    let center = tile_at(local, 0, 0);
+    
+    if params.kernel_type == 0u {
+        return tile_at(local, -1, 0) * 0.2 + tile_at(local, 1, 0) * 0.2 + tile_at(local, 0, -1) * 0.2 + tile_at(local, 0, 1) * 0.2 +
+               tile_at(local, -1, -1) * 0.05 + tile_at(local, 1, -1) * 0.05 + tile_at(local, -1, 1) * 0.05 + tile_at(local, 1, 1) * 0.05 +
+               center * -1.0;
+    } else if params.kernel_type == 1u {
+        return tile_at(local, -1, 0) * 0.2 + tile_at(local, 1, 0) * 0.2 + tile_at(local, 0, -1) * 0.2 + tile_at(local, 0, 1) * 0.2 + center * -0.8;
+    } else if params.kernel_type == 2u {
+        return tile_at(local, -1, -1) * 0.2 + tile_at(local, 1, -1) * 0.2 + tile_at(local, -1, 1) * 0.2 + tile_at(local, 1, 1) * 0.2 + center * -0.8;
+    } else if params.kernel_type == 3u {
+        var sum = vec2<f32>(0.0);
+        sum += tile_at(local, -2, -2) * (4.0/60.0);
+        sum += tile_at(local, -1, -2) * (2.0/60.0);
+        sum += tile_at(local, -2, -1) * (6.0/60.0);
+        sum += tile_at(local, -2, 0) * (8.0/60.0);
+        sum += tile_at(local, -1, 0) * (10.0/60.0);
+        sum += center * (-60.0/60.0);
+        sum += tile_at(local, 1, 0) * (10.0/60.0);
+        sum += tile_at(local, 2, 0) * (8.0/60.0);
+        sum += tile_at(local, 2, 1) * (6.0/60.0);
+        sum += tile_at(local, 1, 2) * (2.0/60.0);
+        sum += tile_at(local, 2, 2) * (4.0/60.0);
+        return sum;
+    } else {
+        var sum = vec2<f32>(0.0);
+        sum += tile_at(local, -1, -1) * 0.15;
+        sum += tile_at(local, 0, -1) * 0.10;
+        sum += tile_at(local, 1, -1) * 0.05;
+        sum += tile_at(local, -1, 0) * 0.20;
+        sum += center * -0.80;
+        sum += tile_at(local, 1, 0) * 0.15;
+        sum += tile_at(local, -1, 1) * 0.05;
+        sum += tile_at(local, 0, 1) * 0.05;
+        sum += tile_at(local, 1, 1) * 0.05;
+        return sum;
+    }
+}
+
+@compute @workgroup_size(16, 16)
+fn main_tiled(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+) {
+    let pos = vec2<i32>(global_id.xy);
+    let local = vec2<i32>(local_id.xy);
+    let dims = vec2<i32>(i32(params.grid_width), i32(params.grid_height));
+
+    // Every invocation helps load the tile, including those past the grid edge,
+    // and all of them reach the barrier
+    if params.equation == 0u {
+        let origin = vec2<i32>(workgroup_id.xy) * TILE_SIZE - TILE_HALO;
+        for (var i = i32(local_index); i < TILE_SIDE * TILE_SIDE; i += TILE_SIZE * TILE_SIZE) {
+            let coord = vec2<i32>(
+                apply_boundary(origin.x + i % TILE_SIDE, dims.x, params.boundary_mode),
+                apply_boundary(origin.y + i / TILE_SIDE, dims.y, params.boundary_mode),
+            );
+            tile[i] = textureLoad(texture_src, coord, 0).rg;
+        }
+    }
+    workgroupBarrier();
+
+    if (pos.x >= dims.x || pos.y >= dims.y) {
+        return;
+    }
+
+    if params.equation == 1u {
+        textureStore(texture_dst, pos, vec4<f32>(ginzburg_landau(pos), 0.0, 1.0));
+        return;
+    }
+    if params.equation == 2u {
+        textureStore(texture_dst, pos, vec4<f32>(swift_hohenberg(pos), 0.0, 0.0, 1.0));
+        return;
+    }
+
+    let uv = tile_at(local, 0, 0);
+    textureStore(texture_dst, pos, vec4<f32>(gray_scott(pos, uv, tile_laplacian(local)), 0.0, 1.0));
+}
+
+// Temporal blocking (Simulation::block_steps): main_blocked advances a 16x16
+// block of cells by block_steps Gray-Scott steps in one dispatch. The workgroup
+// loads the block with a halo of block_steps times the kernel radius and steps
+// it in workgroup memory, each step leaving one radius less of the halo valid,
+// then writes back only the block itself.
+//
+// Wrapped halo cells are stepped like any other, with their wrapped positions.
+// Clamp and reflect reads of cells outside the grid go to the cell they map to,
+// as laplacian() does, so those halo cells are never read.
+const BLOCK_SIZE: i32 = 16;
+const MAX_BLOCK_HALO: i32 = 8;  // 4 steps of the radius-2 spiral kernel
+const BLOCK_STRIDE: i32 = 32;   // BLOCK_SIZE + 2 * MAX_BLOCK_HALO
+const BLOCK_CELLS: i32 = 1024;  // BLOCK_STRIDE * BLOCK_STRIDE
+
+// Two halves, source and written in turn
+var<workgroup> blocked_tile: array<vec2<f32>, 2048>;
+
+var<private> block_origin: vec2<i32>;  // grid position of the tile's first cell
+var<private> block_side: i32;          // tile cells per side in use
+
+fn kernel_radius() -> i32 {
+    return select(1, 2, params.kernel_type == 3u);
+}
+
+// Grid position of tile cell `cell`, with wrapped coordinates reduced into the grid
+fn block_position(cell: vec2<i32>) -> vec2<i32> {
+    let coord = block_origin + cell;
+    let dims = vec2<i32>(i32(params.grid_width), i32(params.grid_height));
+    if params.boundary_mode == 0u {
+        // Shifted by whole periods first, as % keeps the sign of negative coordinates
+        let periods = MAX_BLOCK_HALO / dims + 1;
+        return (coord + dims * periods) % dims;
+    }
+    return vec2<i32>(
+        apply_boundary(coord.x, dims.x, params.boundary_mode),
+        apply_boundary(coord.y, dims.y, params.boundary_mode),
+    );
+}
+
+// The value in half `tile_half` (0 or 1) at offset (dx, dy) from tile cell `cell`
+fn block_at(tile_half: i32, cell: vec2<i32>, dx: i32, dy: i32) -> vec2<f32> {
+    var source = cell + vec2<i32>(dx, dy);
+    let coord = block_origin + source;
+    let outside = coord.x < 0 || coord.y < 0 || coord.x >= i32(params.grid_width) || coord.y >= i32(params.grid_height);
+    if params.boundary_mode != 0u && outside {
+        source = clamp(block_position(source) - block_origin, vec2<i32>(0), vec2<i32>(block_side - 1));
+    }
+    return blocked_tile[tile_half * BLOCK_CELLS + source.y * BLOCK_STRIDE + source.x];
+}
+
+// laplacian() source from one tile_half of the tile, with the same weights and summation order
+fn block_laplacian(tile_half: i32, cell: vec2<i32>) -> vec2<f32> {
+    let center = block_at(tile_half, cell, 0, 0);
     
-    textureStore(texture_dst, pos, vec4<f32>(result, 0.0, 1.0));
+    if params.kernel_type == 0u {
+        return block_at(tile_half, cell, -1, 0) * 0.2 + block_at(tile_half, cell, 1, 0) * 0.2 + block_at(tile_half, cell, 0, -1) * 0.2 + block_at(tile_half, cell, 0, 1) * 0.2 +
+               block_at(tile_half, cell, -1, -1) * 0.05 + block_at(tile_half, cell, 1, -1) * 0.05 + block_at(tile_half, cell, -1, 1) * 0.05 + block_at(tile_half, cell, 1, 1) * 0.05 +
+               center * -1.0;
+    } else if params.kernel_type == 1u {
+        return block_at(tile_half, cell, -1, 0) * 0.2 + block_at(tile_half, cell, 1, 0) * 0.2 + block_at(tile_half, cell, 0, -1) * 0.2 + block_at(tile_half, cell, 0, 1) * 0.2 + center * -0.8;
+    } else if params.kernel_type == 2u {
+        return block_at(tile_half, cell, -1, -1) * 0.2 + block_at(tile_half, cell, 1, -1) * 0.2 + block_at(tile_half, cell, -1, 1) * 0.2 + block_at(tile_half, cell, 1, 1) * 0.2 + center * -0.8;
+    } else if params.kernel_type == 3u {
+        var sum = vec2<f32>(0.0);
+        sum += block_at(tile_half, cell, -2, -2) * (4.0/60.0);
+        sum += block_at(tile_half, cell, -1, -2) * (2.0/60.0);
+        sum += block_at(tile_half, cell, -2, -1) * (6.0/60.0);
+        sum += block_at(tile_half, cell, -2, 0) * (8.0/60.0);
+        sum += block_at(tile_half, cell, -1, 0) * (10.0/60.0);
+        sum += center * (-60.0/60.0);
+        sum += block_at(tile_half, cell, 1, 0) * (10.0/60.0);
+        sum += block_at(tile_half, cell, 2, 0) * (8.0/60.0);
+        sum += block_at(tile_half, cell, 2, 1) * (6.0/60.0);
+        sum += block_at(tile_half, cell, 1, 2) * (2.0/60.0);
+        sum += block_at(tile_half, cell, 2, 2) * (4.0/60.0);
+        return sum;
+    } else {
+        var sum = vec2<f32>(0.0);
+        sum += block_at(tile_half, cell, -1, -1) * 0.15;
+        sum += block_at(tile_half, cell, 0, -1) * 0.10;
+        sum += block_at(tile_half, cell, 1, -1) * 0.05;
+        sum += block_at(tile_half, cell, -1, 0) * 0.20;
+        sum += center * -0.80;
+        sum += block_at(tile_half, cell, 1, 0) * 0.15;
+        sum += block_at(tile_half, cell, -1, 1) * 0.05;
+        sum += block_at(tile_half, cell, 0, 1) * 0.05;
+        sum += block_at(tile_half, cell, 1, 1) * 0.05;
+        return sum;
+    }
+}
+
+@compute @workgroup_size(16, 16)
+fn main_blocked(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+) {
+    let radius = kernel_radius();
+    let halo = i32(params.block_steps) * radius;
+    block_origin = vec2<i32>(workgroup_id.xy) * BLOCK_SIZE - halo;
+    block_side = BLOCK_SIZE + 2 * halo;
+    let threads = BLOCK_SIZE * BLOCK_SIZE;
+
+    for (var i = i32(local_index); i < block_side * block_side; i += threads) {
+        let cell = vec2<i32>(i % block_side, i / block_side);
+        let value = textureLoad(texture_src, block_position(cell), 0).rg;
+        blocked_tile[cell.y * BLOCK_STRIDE + cell.x] = value;
+    }
+    workgroupBarrier();
+
+    // Step s leaves the cells at least s radii from the tile's edge valid
+    for (var block_step = 1; block_step <= i32(params.block_steps); block_step++) {
+        let read_half = (block_step - 1) % 2;
+        let inset = block_step * radius;
+        let side = block_side - 2 * inset;
+        for (var i = i32(local_index); i < side * side; i += threads) {
+            let cell = vec2<i32>(i % side, i / side) + inset;
+            let uv = block_at(read_half, cell, 0, 0);
+            let stepped = gray_scott(block_position(cell), uv, block_laplacian(read_half, cell));
+            blocked_tile[(1 - read_half) * BLOCK_CELLS + cell.y * BLOCK_STRIDE + cell.x] = stepped;
+        }
+        workgroupBarrier();
+    }
+
+    let pos = vec2<i32>(global_id.xy);
+    if pos.x < i32(params.grid_width) && pos.y < i32(params.grid_height) {
+        let cell = vec2<i32>(local_id.xy) + halo;
+        let tile_half = i32(params.block_steps) % 2;
+        let uv = blocked_tile[tile_half * BLOCK_CELLS + cell.y * BLOCK_STRIDE + cell.x];
+        textureStore(texture_dst, pos, vec4<f32>(uv, 0.0, 1.0));
+    }
+}
+
+// Split-step solvers (spectral.rs, implicit.rs): the explicit reaction half of a
+// step. Writes the reacted field, before diffusion, as (U, V) pairs; the
+// spectral solver reads them as U + iV for the FFT.
+@group(1) @binding(0) var<storage, read_write> reacted_field: array<vec2<f32>>;
+
+@compute @workgroup_size(8, 8)
+fn reaction_step(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let pos = vec2<i32>(global_id.xy);
+    if global_id.x >= params.grid_width || global_id.y >= params.grid_height {
+        return;
+    }
+
+    let uv = textureLoad(texture_src, pos, 0).rg;
+    let rates = rates_at(pos);
+    let uvv = uv.x * uv.y * uv.y;
+    let reaction = vec2<f32>(-uvv + rates.x * (1.0 - uv.x), uvv - (rates.x + rates.y) * uv.y);
+
+    var reacted = uv + reaction * params.delta_time;
+    if params.noise_strength > 0.0 {
+        reacted += noise_at(pos);
+    }
+    reacted_field[global_id.y * params.grid_width + global_id.x] = reacted;
 }
@@ -0,0 +1,82 @@
+// Binary state dumps of the field, for saving and resuming headless runs.
+//
+// Layout (little-endian):
+//   magic "RDSF", format version (u32), width (u32), height (u32), step (u64),
+//   then width * height interleaved (U, V) pairs as f32, row-major.
+
+const MAGIC: &[u8; 4] = b"RDSF";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 24;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateDump {
+    pub width: u32,
+    pub height: u32,
+    pub step: u64,
+    pub field: Vec<f32>, // interleaved (U, V) pairs
+}
+
+impl StateDump {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.field.len() * 4);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&self.step.to_le_bytes());
+        for value in &self.field {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+            return Err("Not a state dump".to_string());
+        }
+        let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        let version = u32_at(4);
+        if version != VERSION {
+            return Err(format!("Unsupported state dump version {}", version));
+        }
+
+        let (width, height) = (u32_at(8), u32_at(12));
+        let step = u64::from_le_bytes(bytes[16..24].try_into().unwrap());
+        let expected = HEADER_LEN + width as usize * height as usize * 8;
+        if bytes.len() != expected {
+            return Err(format!(
+                "State dump for a {}x{} grid should be {} bytes, got {}",
+                width, height, expected, bytes.len()
+            ));
+        }
+
+        let field = bytes[HEADER_LEN..]
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+
+        Ok(Self { width, height, step, field })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_decode_round_trips() {
+        let dump = StateDump {
+            width: 3,
+            height: 2,
+            step: 1234,
+            field: (0..12).map(|i| i as f32 * 0.25).collect(),
+        };
+        let bytes = dump.encode();
+        assert_eq!(bytes.len(), 24 + 48);
+        assert_eq!(StateDump::decode(&bytes).unwrap(), dump);
+
+        assert!(StateDump::decode(&bytes[..bytes.len() - 4]).is_err());
+        assert!(StateDump::decode(b"nope").is_err());
+    }
+}
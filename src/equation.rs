@@ -0,0 +1,109 @@
+// The PDE the compute shader integrates. Gray-Scott is the default; the other
+// two reuse the same ping-pong field and boundary handling:
+//
+// - Complex Ginzburg-Landau, with the complex amplitude A = U + iV:
+//     dA/dt = A + (1 + i alpha) D lap(A) - (1 + i beta) |A|^2 A
+//   D is the U diffusion rate and the Laplacian is the selected kernel.
+// - Swift-Hohenberg on U alone (V is unused):
+//     du/dt = r u - (q0^2 + lap)^2 u + g u^2 - u^3
+//   with the 5-point Laplacian and 13-point biharmonic, so the pattern
+//   wavelength is 2 pi / q0 cells. Explicit steps need delta_time below ~0.03.
+//
+// Both are seeded with small noise (InitRecipe::Noise) and shown through
+// 0.5 + 0.5 * U instead of V.
+
+use serde::{Deserialize, Serialize};
+
+pub const EQUATION_GRAY_SCOTT: u32 = 0;
+pub const EQUATION_GINZBURG_LANDAU: u32 = 1;
+pub const EQUATION_SWIFT_HOHENBERG: u32 = 2;
+
+// How the renderer reads a cell (field_view in render.wgsl)
+// Bound on |U| and |V| for the signed equations, as FIELD_LIMIT in compute.wgsl
+pub const FIELD_LIMIT: f32 = 10.0;
+
+pub const FIELD_VIEW_V: u32 = 0;
+pub const FIELD_VIEW_SIGNED_U: u32 = 1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Equation {
+    #[default]
+    GrayScott,
+    GinzburgLandau { alpha: f32, beta: f32 },
+    SwiftHohenberg { r: f32, q0: f32, g: f32 },
+}
+
+impl Equation {
+    pub fn is_gray_scott(&self) -> bool {
+        *self == Equation::GrayScott
+    }
+
+    // Range the explicit kernel clamps U and V to: concentrations for
+    // Gray-Scott, signed amplitudes for the others
+    pub fn field_range(&self) -> (f32, f32) {
+        if self.is_gray_scott() {
+            (0.0, 1.0)
+        } else {
+            (-FIELD_LIMIT, FIELD_LIMIT)
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let values: &[f32] = match self {
+            Equation::GrayScott => &[],
+            Equation::GinzburgLandau { alpha, beta } => &[*alpha, *beta],
+            Equation::SwiftHohenberg { r, q0, g } => {
+                if *q0 <= 0.0 {
+                    return Err("Swift-Hohenberg q0 must be positive".to_string());
+                }
+                &[*r, *q0, *g]
+            }
+        };
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err("Equation parameters must be finite numbers".to_string());
+        }
+        Ok(())
+    }
+
+    // (equation, parameters) as laid out in SimParams
+    pub(crate) fn uniform(&self) -> (u32, [f32; 3]) {
+        match *self {
+            Equation::GrayScott => (EQUATION_GRAY_SCOTT, [0.0; 3]),
+            Equation::GinzburgLandau { alpha, beta } => (EQUATION_GINZBURG_LANDAU, [alpha, beta, 0.0]),
+            Equation::SwiftHohenberg { r, q0, g } => (EQUATION_SWIFT_HOHENBERG, [r, q0, g]),
+        }
+    }
+
+    pub fn field_view(&self) -> u32 {
+        match self {
+            Equation::GrayScott => FIELD_VIEW_V,
+            _ => FIELD_VIEW_SIGNED_U,
+        }
+    }
+
+    // The value the renderer maps through the gradient for a cell
+    pub fn display_value(&self, u: f32, v: f32) -> f32 {
+        match self.field_view() {
+            FIELD_VIEW_V => v,
+            _ => 0.5 + 0.5 * u,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equations_parse_and_validate() {
+        let equation: Equation = serde_json::from_str(r#"{ "type": "swift_hohenberg", "r": 0.3, "q0": 0.75, "g": 0.0 }"#).unwrap();
+        assert_eq!(equation.uniform(), (EQUATION_SWIFT_HOHENBERG, [0.3, 0.75, 0.0]));
+        assert_eq!(equation.display_value(-1.0, 0.7), 0.0);
+        assert_eq!(Equation::GrayScott.display_value(-1.0, 0.7), 0.7);
+
+        assert!(Equation::SwiftHohenberg { r: 0.3, q0: 0.0, g: 0.0 }.validate().is_err());
+        assert!(Equation::GinzburgLandau { alpha: f32::INFINITY, beta: 0.0 }.validate().is_err());
+        assert!(serde_json::from_str::<Equation>(r#"{ "type": "ginzburg_landau", "alpha": 1.0 }"#).is_err());
+    }
+}
@@ -0,0 +1,447 @@
+// Parameter expressions: small formulas of position and time such as
+// "0.03 + 0.02 * r" or "kill + 0.005 * sin(theta + t / 2000)" that set F or k
+// per cell. They're parsed here and compiled into the compute shader, so only
+// the grammar below can reach the GPU.
+//
+//   expr   := term (('+' | '-') term)*
+//   term   := unary (('*' | '/') unary)*
+//   unary  := '-' unary | power
+//   power  := atom ('^' unary)?
+//   atom   := number | variable | function '(' expr (',' expr)* ')' | '(' expr ')'
+//
+// Variables: x, y (0..1 across the grid), t (steps since the field was seeded),
+// r (distance from the centre), theta (angle around the centre), feed and kill
+// (the current F and k settings), pi. Reaction terms of the multi-species model
+// (see species.rs) use c0, c1, ... for the concentrations instead of feed and kill.
+
+const MAX_DEPTH: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Var {
+    X,
+    Y,
+    T,
+    R,
+    Theta,
+    Feed,
+    Kill,
+    Species(usize),
+}
+
+const SPECIES_WGSL: [&str; 8] = ["c[0]", "c[1]", "c[2]", "c[3]", "c[4]", "c[5]", "c[6]", "c[7]"];
+
+impl Var {
+    // `species` is None for F/k expressions, or the species count for reaction terms
+    fn from_name(name: &str, species: Option<usize>) -> Option<Self> {
+        Some(match (name, species) {
+            ("x", _) => Var::X,
+            ("y", _) => Var::Y,
+            ("t", _) => Var::T,
+            ("r", _) => Var::R,
+            ("theta", _) => Var::Theta,
+            ("feed", None) => Var::Feed,
+            ("kill", None) => Var::Kill,
+            (_, Some(count)) => {
+                let index: usize = name.strip_prefix('c')?.parse().ok()?;
+                if index >= count || name != format!("c{}", index) {
+                    return None;
+                }
+                Var::Species(index)
+            }
+            _ => return None,
+        })
+    }
+
+    // Parameter names of the generated WGSL function
+    fn wgsl(self) -> &'static str {
+        match self {
+            Var::X => "x",
+            Var::Y => "y",
+            Var::T => "t",
+            Var::R => "r",
+            Var::Theta => "theta",
+            Var::Feed => "feed",
+            Var::Kill => "kill",
+            Var::Species(i) => SPECIES_WGSL[i],
+        }
+    }
+}
+
+// Values of the variables at one cell and step
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vars {
+    pub x: f32,
+    pub y: f32,
+    pub t: f32,
+    pub feed: f32,
+    pub kill: f32,
+    pub species: [f32; 8],
+}
+
+impl Vars {
+    fn get(&self, var: Var) -> f32 {
+        match var {
+            Var::X => self.x,
+            Var::Y => self.y,
+            Var::T => self.t,
+            Var::R => (self.x - 0.5).hypot(self.y - 0.5),
+            Var::Theta => (self.y - 0.5).atan2(self.x - 0.5),
+            Var::Feed => self.feed,
+            Var::Kill => self.kill,
+            Var::Species(i) => self.species[i],
+        }
+    }
+}
+
+// (name, argument count); the names are the same in WGSL
+const FUNCTIONS: [(&str, usize); 20] = [
+    ("sin", 1), ("cos", 1), ("tan", 1), ("asin", 1), ("acos", 1), ("atan", 1),
+    ("abs", 1), ("sqrt", 1), ("exp", 1), ("log", 1), ("floor", 1), ("fract", 1), ("sign", 1),
+    ("atan2", 2), ("pow", 2), ("min", 2), ("max", 2), ("step", 2),
+    ("clamp", 3), ("mix", 3),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(f32),
+    Var(Var),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>), // + - * / ^
+    Call(&'static str, Vec<Expr>),
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    depth: usize,
+    species: Option<usize>,
+}
+
+impl Parser<'_> {
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("{} at position {} in '{}'", message, self.pos + 1, self.text))
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.text[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> &str {
+        let start = self.pos;
+        let len = self.text[start..].find(|c| !accept(c)).unwrap_or(self.text.len() - start);
+        self.pos += len;
+        &self.text[start..self.pos]
+    }
+
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        if self.depth == MAX_DEPTH {
+            return self.error("Expression is nested too deeply");
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    // Position of the next token
+    fn start(&mut self) -> usize {
+        self.peek();
+        self.pos
+    }
+
+    // Replaces a node whose operands are all numbers by its value. The WGSL
+    // compiler folds such sub-expressions itself and fails shader creation if
+    // one isn't finite (e.g. "1/0" or "2^200"), so those are rejected here.
+    fn fold(&mut self, start: usize, expr: Expr) -> Result<Expr, String> {
+        let number = |e: &Expr| matches!(e, Expr::Number(_));
+        let constant = match &expr {
+            Expr::Neg(inner) => number(inner),
+            Expr::Binary(_, a, b) => number(a) && number(b),
+            Expr::Call(_, args) => args.iter().all(number),
+            _ => false,
+        };
+        if !constant {
+            return Ok(expr);
+        }
+        let value = expr.eval(&Vars::default());
+        if !value.is_finite() {
+            self.pos = start;
+            return self.error("Constant part isn't a finite number");
+        }
+        Ok(Expr::Number(value))
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let start = self.start();
+        let mut lhs = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            lhs = self.fold(start, Expr::Binary(op, Box::new(lhs), Box::new(rhs)))?;
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let start = self.start();
+        let mut lhs = self.unary()?;
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            lhs = self.fold(start, Expr::Binary(op, Box::new(lhs), Box::new(rhs)))?;
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        let start = self.start();
+        if self.eat('-') {
+            let inner = self.nested(|p| p.unary())?;
+            return self.fold(start, Expr::Neg(Box::new(inner)));
+        }
+        let base = self.atom()?;
+        if self.eat('^') {
+            let exponent = self.nested(|p| p.unary())?;
+            return self.fold(start, Expr::Binary('^', Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let start = self.start();
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.nested(|p| p.expr())?;
+                if !self.eat(')') {
+                    return self.error("Expected ')'");
+                }
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_').to_string();
+                if name == "pi" {
+                    return Ok(Expr::Number(std::f32::consts::PI));
+                }
+                if let Some(var) = Var::from_name(&name, self.species) {
+                    return Ok(Expr::Var(var));
+                }
+                let Some(&(function, arity)) = FUNCTIONS.iter().find(|(f, _)| *f == name) else {
+                    self.pos = start;
+                    return self.error(&format!("Unknown name '{}'", name));
+                };
+                if !self.eat('(') {
+                    return self.error(&format!("Expected '(' after {}", function));
+                }
+                let mut args = vec![self.nested(|p| p.expr())?];
+                while self.eat(',') {
+                    args.push(self.nested(|p| p.expr())?);
+                }
+                if !self.eat(')') {
+                    return self.error("Expected ')'");
+                }
+                if args.len() != arity {
+                    return self.error(&format!("{} takes {} argument(s), got {}", function, arity, args.len()));
+                }
+                self.fold(start, Expr::Call(function, args))
+            }
+            Some(_) => self.error("Unexpected character"),
+            None => self.error("Unexpected end of expression"),
+        }
+    }
+
+    fn number(&mut self) -> Result<Expr, String> {
+        let start = self.pos;
+        self.take_while(|c| c.is_ascii_digit() || c == '.');
+        // Optional exponent, e.g. 1e-3
+        if self.text[self.pos..].starts_with(['e', 'E']) {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if self.text[self.pos..].starts_with(['+', '-']) {
+                self.pos += 1;
+            }
+            if self.take_while(|c| c.is_ascii_digit()).is_empty() {
+                self.pos = mantissa_end;
+            }
+        }
+        match self.text[start..self.pos].parse::<f32>() {
+            Ok(value) if value.is_finite() => Ok(Expr::Number(value)),
+            _ => {
+                self.pos = start;
+                self.error("Invalid number")
+            }
+        }
+    }
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::parse_with(text, None)
+    }
+
+    // A reaction term over x, y, t, r, theta and the concentrations c0..c{species - 1}
+    pub fn parse_reaction(text: &str, species: usize) -> Result<Self, String> {
+        Self::parse_with(text, Some(species.min(SPECIES_WGSL.len())))
+    }
+
+    fn parse_with(text: &str, species: Option<usize>) -> Result<Self, String> {
+        let mut parser = Parser { text, pos: 0, depth: 0, species };
+        let expr = parser.expr()?;
+        if parser.peek().is_some() {
+            return parser.error("Unexpected character");
+        }
+        Ok(expr)
+    }
+
+    // CPU evaluation, matching the generated WGSL
+    pub fn eval(&self, vars: &Vars) -> f32 {
+        match self {
+            Expr::Number(value) => *value,
+            Expr::Var(var) => vars.get(*var),
+            Expr::Neg(inner) => -inner.eval(vars),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(vars), b.eval(vars));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    _ => a.powf(b),
+                }
+            }
+            Expr::Call(function, args) => {
+                let arg = |i: usize| args[i].eval(vars);
+                match *function {
+                    "sin" => arg(0).sin(),
+                    "cos" => arg(0).cos(),
+                    "tan" => arg(0).tan(),
+                    "asin" => arg(0).asin(),
+                    "acos" => arg(0).acos(),
+                    "atan" => arg(0).atan(),
+                    "abs" => arg(0).abs(),
+                    "sqrt" => arg(0).sqrt(),
+                    "exp" => arg(0).exp(),
+                    "log" => arg(0).ln(),
+                    "floor" => arg(0).floor(),
+                    "fract" => arg(0) - arg(0).floor(),
+                    "sign" => if arg(0) == 0.0 { 0.0 } else { arg(0).signum() },
+                    "atan2" => arg(0).atan2(arg(1)),
+                    "pow" => arg(0).powf(arg(1)),
+                    "min" => arg(0).min(arg(1)),
+                    "max" => arg(0).max(arg(1)),
+                    "step" => if arg(1) < arg(0) { 0.0 } else { 1.0 },
+                    "clamp" => arg(0).max(arg(1)).min(arg(2)),
+                    _ => arg(0) + (arg(1) - arg(0)) * arg(2), // mix
+                }
+            }
+        }
+    }
+
+    // A WGSL expression over f32 parameters named after the variables
+    pub fn to_wgsl(&self) -> String {
+        match self {
+            // Debug formatting always keeps a '.' or exponent, so the literal stays a float
+            Expr::Number(value) => format!("{:?}", value),
+            Expr::Var(var) => var.wgsl().to_string(),
+            Expr::Neg(inner) => format!("(-{})", inner.to_wgsl()),
+            Expr::Binary('^', a, b) => format!("pow({}, {})", a.to_wgsl(), b.to_wgsl()),
+            Expr::Binary(op, a, b) => format!("({} {} {})", a.to_wgsl(), op, b.to_wgsl()),
+            Expr::Call(function, args) => {
+                let args: Vec<String> = args.iter().map(Expr::to_wgsl).collect();
+                format!("{}({})", function, args.join(", "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(text: &str, vars: &Vars) -> f32 {
+        Expr::parse(text).unwrap().eval(vars)
+    }
+
+    #[test]
+    fn precedence_and_functions() {
+        let vars = Vars { x: 0.25, y: 0.5, t: 100.0, feed: 0.03, kill: 0.06, ..Default::default() };
+        assert_eq!(eval("1 + 2 * 3", &vars), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", &vars), 9.0);
+        assert_eq!(eval("-2 ^ 2", &vars), -4.0);
+        assert_eq!(eval("2 ^ 3 ^ 2", &vars), 512.0);
+        assert_eq!(eval("8 / 2 / 2", &vars), 2.0);
+        assert_eq!(eval("1e-3 * 2", &vars), 0.002);
+        assert_eq!(eval("x + y * 2", &vars), 1.25);
+        assert_eq!(eval("r", &vars), 0.25);
+        assert_eq!(eval("theta", &vars), std::f32::consts::PI);
+        assert_eq!(eval("feed + kill", &vars), 0.03 + 0.06);
+        assert_eq!(eval("clamp(t / 50, 0, 1)", &vars), 1.0);
+        assert!((eval("mix(0.02, 0.04, x)", &vars) - 0.025).abs() < 1e-7);
+        assert_eq!(eval("step(0.5, x) + max(x, y)", &vars), 0.5);
+        assert_eq!(eval("fract(-0.25)", &vars), 0.75);
+    }
+
+    #[test]
+    fn wgsl_keeps_structure_and_float_literals() {
+        let expr = Expr::parse("0.03 + 0.01 * sin(theta + t / 2000) ^ 2 - -x").unwrap();
+        assert_eq!(expr.to_wgsl(), "((0.03 + (0.01 * pow(sin((theta + (t / 2000.0))), 2.0))) - (-x))");
+        assert_eq!(Expr::parse("pi").unwrap().to_wgsl(), "3.1415927");
+        assert_eq!(Expr::parse("1e-7").unwrap().to_wgsl(), "1e-7");
+    }
+
+    #[test]
+    fn constant_parts_are_folded() {
+        assert_eq!(Expr::parse("2 * 3 + x").unwrap().to_wgsl(), "(6.0 + x)");
+        assert_eq!(Expr::parse("-(1 + 1)").unwrap(), Expr::Number(-2.0));
+        assert_eq!(Expr::parse("x * sqrt(4)").unwrap().to_wgsl(), "(x * 2.0)");
+        // Only operands that are numbers themselves fold, as in WGSL
+        assert_eq!(Expr::parse("x * 2 * 3").unwrap().to_wgsl(), "((x * 2.0) * 3.0)");
+    }
+
+    #[test]
+    fn reaction_terms_use_species() {
+        let vars = Vars { species: [0.5, 0.25, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0], ..Default::default() };
+        let term = Expr::parse_reaction("c0 * (1 - c1) - c2 * x", 3).unwrap();
+        assert_eq!(term.eval(&vars), 0.375);
+        assert_eq!(term.to_wgsl(), "((c[0] * (1.0 - c[1])) - (c[2] * x))");
+        for text in ["c3", "c01", "feed", "c"] {
+            assert!(Expr::parse_reaction(text, 3).unwrap_err().contains("Unknown name"), "{}", text);
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        for (text, message) in [
+            ("", "Unexpected end"),
+            ("x +", "Unexpected end"),
+            ("(x", "Expected ')'"),
+            ("x y", "Unexpected character"),
+            ("foo(x)", "Unknown name 'foo'"),
+            ("sin", "Expected '('"),
+            ("min(x)", "min takes 2 argument(s)"),
+            ("x; 1", "Unexpected character"),
+            ("1e40", "Invalid number"),
+            ("x + 1/0", "Constant part isn't a finite number at position 5"),
+            ("2^200 * x", "Constant part isn't a finite number at position 1"),
+            ("sin(x) + log(0)", "Constant part isn't a finite number at position 10"),
+        ] {
+            let error = Expr::parse(text).unwrap_err();
+            assert!(error.contains(message), "'{}' gave '{}'", text, error);
+        }
+        assert!(Expr::parse("c0").unwrap_err().contains("Unknown name 'c0'"));
+        assert!(Expr::parse(&"(".repeat(100)).unwrap_err().contains("nested too deeply"));
+        assert!(Expr::parse(&"-".repeat(100)).unwrap_err().contains("nested too deeply"));
+    }
+}
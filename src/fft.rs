@@ -0,0 +1,309 @@
+// 2D FFT on the GPU (fft.wgsl). Transforms a row-major complex buffer in place
+// of two ping-pong storage buffers; sizes must be powers of two.
+
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct FftParams {
+    width: u32,
+    height: u32,
+    axis: u32,
+    span: u32,
+    direction: f32,
+    scale: f32,
+    _padding1: u32,
+    _padding2: u32,
+}
+
+// Bytes per complex element (vec2<f32>)
+pub const COMPLEX_SIZE: u64 = 8;
+
+pub struct Fft2d {
+    width: u32,
+    height: u32,
+
+    pipeline: wgpu::ComputePipeline,
+    buffers: [wgpu::Buffer; 2],
+    // bind_groups[i] reads buffers[i] and writes the other one
+    bind_groups: [wgpu::BindGroup; 2],
+
+    // Uniform slots are one per stage, forward stages first, then the inverse ones
+    params_stride: u32,
+}
+
+impl Fft2d {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Result<Self, String> {
+        if !width.is_power_of_two() || !height.is_power_of_two() || width < 2 || height < 2 {
+            return Err(format!("FFT size must be a power of two of at least 2, got {}x{}", width, height));
+        }
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("FFT Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("fft.wgsl").into()),
+        });
+
+        let stage_params = |inverse: bool| {
+            let axes = [(0, width), (1, height)];
+            axes.into_iter().flat_map(move |(axis, n)| {
+                (0..n.trailing_zeros()).map(move |stage| FftParams {
+                    width,
+                    height,
+                    axis,
+                    span: 1 << stage,
+                    direction: if inverse { 1.0 } else { -1.0 },
+                    scale: if inverse { 0.5 } else { 1.0 },
+                    _padding1: 0,
+                    _padding2: 0,
+                })
+            })
+        };
+
+        // Each stage gets its own uniform slot so all of them can be recorded into
+        // one pass with dynamic offsets
+        let params_stride = (std::mem::size_of::<FftParams>() as u32)
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment);
+        let mut params_data = Vec::new();
+        for params in stage_params(false).chain(stage_params(true)) {
+            params_data.extend_from_slice(bytemuck::bytes_of(&params));
+            params_data.resize(params_data.len().next_multiple_of(params_stride as usize), 0);
+        }
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("FFT Params Buffer"),
+            contents: &params_data,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let create_buffer = |label| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: width as u64 * height as u64 * COMPLEX_SIZE,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let buffers = [create_buffer("FFT Buffer A"), create_buffer("FFT Buffer B")];
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("FFT Bind Group Layout"),
+            entries: &[
+                // binding 0: source buffer
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 1: destination buffer
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 2: per-stage params (dynamic offset)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<FftParams>() as u64),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("FFT Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("FFT Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("fft_stage"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let create_bind_group = |label, src: &wgpu::Buffer, dst: &wgpu::Buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: src.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: dst.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &params_buffer,
+                            offset: 0,
+                            size: wgpu::BufferSize::new(std::mem::size_of::<FftParams>() as u64),
+                        }),
+                    },
+                ],
+            })
+        };
+
+        let bind_groups = [
+            create_bind_group("FFT Bind Group A", &buffers[0], &buffers[1]),
+            create_bind_group("FFT Bind Group B", &buffers[1], &buffers[0]),
+        ];
+
+        Ok(Self {
+            width,
+            height,
+            pipeline,
+            buffers,
+            bind_groups,
+            params_stride,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn num_stages(&self) -> u32 {
+        self.width.trailing_zeros() + self.height.trailing_zeros()
+    }
+
+    // Buffer the transform reads from
+    pub fn input_buffer(&self) -> &wgpu::Buffer {
+        &self.buffers[0]
+    }
+
+    // Buffer holding the result once `encode` has run
+    pub fn output_buffer(&self) -> &wgpu::Buffer {
+        &self.buffers[(self.num_stages() % 2) as usize]
+    }
+
+    // Records the transform of the input buffer into the output buffer. The inverse
+    // transform is normalised, so forward followed by inverse is the identity.
+    pub fn encode(&self, pass: &mut wgpu::ComputePass, inverse: bool) {
+        pass.set_pipeline(&self.pipeline);
+
+        let stages = self.num_stages();
+        let first_slot = if inverse { stages } else { 0 };
+        let row_stages = self.width.trailing_zeros();
+
+        for stage in 0..stages {
+            let (half, lines) = if stage < row_stages {
+                (self.width / 2, self.height)
+            } else {
+                (self.height / 2, self.width)
+            };
+
+            let offset = (first_slot + stage) * self.params_stride;
+            pass.set_bind_group(0, &self.bind_groups[(stage % 2) as usize], &[offset]);
+            pass.dispatch_workgroups(half.div_ceil(8), lines.div_ceil(8), 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::readback;
+    use crate::simulation::request_headless_device;
+
+    fn run(device: &wgpu::Device, queue: &wgpu::Queue, fft: &Fft2d, input: &[[f32; 2]], inverse: bool) -> Vec<[f32; 2]> {
+        queue.write_buffer(fft.input_buffer(), 0, bytemuck::cast_slice(input));
+
+        let size = fft.output_buffer().size();
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("FFT Test Staging Buffer"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("FFT Test Encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("FFT Test Pass"),
+                timestamp_writes: None,
+            });
+            fft.encode(&mut pass, inverse);
+        }
+        encoder.copy_buffer_to_buffer(fft.output_buffer(), 0, &staging_buffer, 0, size);
+        queue.submit(Some(encoder.finish()));
+
+        pollster::block_on(readback::map_buffer(device, &staging_buffer)).unwrap();
+        let output = bytemuck::cast_slice(&staging_buffer.slice(..).get_mapped_range()).to_vec();
+        staging_buffer.unmap();
+        output
+    }
+
+    #[test]
+    fn cosine_transforms_to_two_peaks() {
+        let Ok((_, device, queue)) = pollster::block_on(request_headless_device()) else { return };
+        let (width, height) = (16, 8);
+        let fft = Fft2d::new(&device, width, height).unwrap();
+
+        // cos(2*pi*(3x/16 + 2y/8)) has all its energy at (3, 2) and (-3, -2)
+        let input: Vec<[f32; 2]> = (0..width * height)
+            .map(|i| {
+                let (x, y) = ((i % width) as f32, (i / width) as f32);
+                let phase = 2.0 * std::f32::consts::PI * (3.0 * x / 16.0 + 2.0 * y / 8.0);
+                [phase.cos(), 0.0]
+            })
+            .collect();
+
+        let output = run(&device, &queue, &fft, &input, false);
+        let expected_peak = (width * height) as f32 / 2.0;
+        for (i, value) in output.iter().enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            let peak = (x, y) == (3, 2) || (x, y) == (width - 3, height - 2);
+            let expected = if peak { expected_peak } else { 0.0 };
+            assert!((value[0] - expected).abs() < 1e-3, "({}, {}): {:?}", x, y, value);
+            assert!(value[1].abs() < 1e-3, "({}, {}): {:?}", x, y, value);
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_forward() {
+        let Ok((_, device, queue)) = pollster::block_on(request_headless_device()) else { return };
+        let fft = Fft2d::new(&device, 32, 4).unwrap();
+
+        let input: Vec<[f32; 2]> = (0..32 * 4).map(|i| [((i * 7) % 5) as f32, ((i * 3) % 4) as f32]).collect();
+        let spectrum = run(&device, &queue, &fft, &input, false);
+        let output = run(&device, &queue, &fft, &spectrum, true);
+
+        for (a, b) in input.iter().zip(&output) {
+            assert!((a[0] - b[0]).abs() < 1e-4 && (a[1] - b[1]).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn non_power_of_two_sizes_are_rejected() {
+        let Ok((_, device, _)) = pollster::block_on(request_headless_device()) else { return };
+        assert!(Fft2d::new(&device, 48, 32).is_err());
+        assert!(Fft2d::new(&device, 32, 1).is_err());
+    }
+}
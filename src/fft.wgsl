@@ -0,0 +1,63 @@
+// Radix-2 Stockham FFT over a complex (vec2<f32>) buffer laid out row-major.
+// Every dispatch performs one butterfly stage along one axis, reading `src` and
+// writing `dst`; the host ping-pongs the two buffers between stages. The Stockham
+// ordering keeps the output in natural order, so no bit-reversal pass is needed.
+
+struct FftParams {
+    width: u32,
+    height: u32,
+    axis: u32,          // 0=rows (along x), 1=columns (along y)
+    span: u32,          // size of the sub-transforms merged by this stage (1, 2, 4, ...)
+    direction: f32,     // -1=forward, 1=inverse
+    scale: f32,         // 1 for forward stages, 0.5 for inverse stages (normalises by 1/N)
+    _padding1: u32,
+    _padding2: u32,
+}
+
+@group(0) @binding(0) var<storage, read> src: array<vec2<f32>>;
+@group(0) @binding(1) var<storage, read_write> dst: array<vec2<f32>>;
+@group(0) @binding(2) var<uniform> params: FftParams;
+
+const PI: f32 = 3.14159265358979;
+
+fn complex_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+// global_id.x indexes the butterflies of a line (n/2 of them), global_id.y the line
+@compute @workgroup_size(8, 8)
+fn fft_stage(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    var n = params.width;
+    var lines = params.height;
+    if params.axis == 1u {
+        n = params.height;
+        lines = params.width;
+    }
+
+    let half = n / 2u;
+    let i = global_id.x;
+    let line = global_id.y;
+    if i >= half || line >= lines {
+        return;
+    }
+
+    // Element `e` of this line lives at base + e * stride
+    var base = line * params.width;
+    var stride = 1u;
+    if params.axis == 1u {
+        base = line;
+        stride = params.width;
+    }
+
+    let p = params.span;
+    let k = i & (p - 1u);
+    let a = src[base + i * stride];
+    let b = src[base + (i + half) * stride];
+
+    let angle = params.direction * PI * f32(k) / f32(p);
+    let twiddled = complex_mul(vec2<f32>(cos(angle), sin(angle)), b);
+
+    let j = ((i - k) << 1u) + k;
+    dst[base + j * stride] = (a + twiddled) * params.scale;
+    dst[base + (j + p) * stride] = (a - twiddled) * params.scale;
+}
@@ -0,0 +1,132 @@
+// Velocity fields that carry U and V around. When a flow is set, each step
+// first advects the field semi-Lagrangian style (every cell takes the value
+// found upstream, bilinearly interpolated) and then runs reaction-diffusion on
+// the result. Velocities are in cells per unit time and scale with delta_time.
+
+use serde::{Deserialize, Serialize};
+
+pub const FLOW_NONE: u32 = 0;
+pub const FLOW_DRIFT: u32 = 1;
+pub const FLOW_VORTEX: u32 = 2;
+pub const FLOW_SHEAR: u32 = 3;
+pub const FLOW_TEXTURE: u32 = 4;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Flow {
+    #[default]
+    None,
+    // The same velocity everywhere
+    Drift { vx: f32, vy: f32 },
+    // Swirl around (x, y) in grid fractions; `speed` is the angular velocity
+    // near the centre and fades out past `radius` (a fraction of the grid)
+    Vortex { x: f32, y: f32, speed: f32, radius: f32 },
+    // Horizontal flow alternating direction in `bands` bands down the grid
+    Shear { speed: f32, bands: f32 },
+    // Velocities from Simulation::set_flow_texture, multiplied by `scale`
+    Texture { scale: f32 },
+}
+
+// Uniform for advect.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct FlowUniform {
+    mode: u32,
+    boundary_mode: u32,
+    grid_width: u32,
+    grid_height: u32,
+    delta_time: f32,
+    speed: f32,
+    center_x: f32,
+    center_y: f32,
+    drift_x: f32,
+    drift_y: f32,
+    radius: f32,
+    bands: f32,
+    scale: f32,
+    _padding1: u32,
+    _padding2: u32,
+    _padding3: u32,
+}
+
+impl Flow {
+    pub fn is_none(&self) -> bool {
+        *self == Flow::None
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let values: &[f32] = match self {
+            Flow::None => &[],
+            Flow::Drift { vx, vy } => &[*vx, *vy],
+            Flow::Vortex { x, y, speed, radius } => {
+                if *radius <= 0.0 {
+                    return Err("Vortex radius must be positive".to_string());
+                }
+                &[*x, *y, *speed, *radius]
+            }
+            Flow::Shear { speed, bands } => {
+                if *bands <= 0.0 {
+                    return Err("Shear needs a positive number of bands".to_string());
+                }
+                &[*speed, *bands]
+            }
+            Flow::Texture { scale } => &[*scale],
+        };
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err("Flow settings must be finite numbers".to_string());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn uniform(&self, width: u32, height: u32, delta_time: f32, boundary_mode: u32) -> FlowUniform {
+        let mut uniform = FlowUniform {
+            boundary_mode,
+            grid_width: width,
+            grid_height: height,
+            delta_time,
+            ..Default::default()
+        };
+        match *self {
+            Flow::None => uniform.mode = FLOW_NONE,
+            Flow::Drift { vx, vy } => {
+                uniform.mode = FLOW_DRIFT;
+                uniform.drift_x = vx;
+                uniform.drift_y = vy;
+            }
+            Flow::Vortex { x, y, speed, radius } => {
+                uniform.mode = FLOW_VORTEX;
+                uniform.center_x = x;
+                uniform.center_y = y;
+                uniform.speed = speed;
+                uniform.radius = radius;
+            }
+            Flow::Shear { speed, bands } => {
+                uniform.mode = FLOW_SHEAR;
+                uniform.speed = speed;
+                uniform.bands = bands;
+            }
+            Flow::Texture { scale } => {
+                uniform.mode = FLOW_TEXTURE;
+                uniform.scale = scale;
+            }
+        }
+        uniform
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flows_parse_from_json_and_validate() {
+        let flow: Flow = serde_json::from_str(r#"{ "type": "vortex", "x": 0.5, "y": 0.5, "speed": 0.01, "radius": 0.2 }"#).unwrap();
+        assert_eq!(flow, Flow::Vortex { x: 0.5, y: 0.5, speed: 0.01, radius: 0.2 });
+        assert!(flow.validate().is_ok());
+        assert_eq!(flow.uniform(64, 32, 1.0, 0).mode, FLOW_VORTEX);
+
+        assert!(Flow::Shear { speed: 1.0, bands: 0.0 }.validate().is_err());
+        assert!(Flow::Drift { vx: f32::NAN, vy: 0.0 }.validate().is_err());
+        assert!(serde_json::from_str::<Flow>(r#"{ "type": "whirlpool" }"#).is_err());
+    }
+}
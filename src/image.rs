@@ -0,0 +1,92 @@
+// Host-side images of the field: V mapped through a colour gradient, encoded as
+// PNG. Used by the sweep contact sheets and the command-line tool.
+
+// The app's default gradient (see GrayScottApp::new), as (position, RGB)
+pub const DEFAULT_GRADIENT: [(f32, [f32; 3]); 6] = [
+    (0.0, [0.2, 0.0, 0.3]), // Dark purple
+    (0.2, [0.5, 0.0, 1.0]), // Purple
+    (0.4, [0.0, 0.5, 1.0]), // Blue
+    (0.6, [0.0, 1.0, 0.8]), // Cyan
+    (0.8, [1.0, 0.3, 0.0]), // Orange
+    (1.0, [1.0, 0.0, 0.0]), // Red
+];
+
+// Same lookup as sample_gradient in render.wgsl
+pub fn gradient_color(stops: &[(f32, [f32; 3])], t: f32) -> [f32; 3] {
+    let t = t.clamp(0.0, 1.0);
+    match stops {
+        [] => [1.0, 1.0, 1.0],
+        [only] => only.1,
+        _ => {
+            if t <= stops[0].0 {
+                return stops[0].1;
+            }
+            for pair in stops.windows(2) {
+                let ((p0, c0), (p1, c1)) = (pair[0], pair[1]);
+                if t >= p0 && t <= p1 {
+                    let range = p1 - p0;
+                    if range <= 0.0 {
+                        return c0;
+                    }
+                    let local = (t - p0) / range;
+                    return [0, 1, 2].map(|i| c0[i] + (c1[i] - c0[i]) * local);
+                }
+            }
+            stops[stops.len() - 1].1
+        }
+    }
+}
+
+// Maps V values (row-major) to packed RGB8 pixels
+pub fn colorize(v: &[f32], stops: &[(f32, [f32; 3])]) -> Vec<u8> {
+    v.iter()
+        .flat_map(|&value| gradient_color(stops, value).map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8))
+        .collect()
+}
+
+pub fn encode_png(width: u32, height: u32, rgb: &[u8]) -> Result<Vec<u8>, String> {
+    if rgb.len() != (width * height * 3) as usize {
+        return Err(format!("Expected {} bytes of RGB data, got {}", width * height * 3, rgb.len()));
+    }
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| format!("Failed to write PNG header: {}", e))?;
+        writer.write_image_data(rgb).map_err(|e| format!("Failed to write PNG data: {}", e))?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_interpolates_between_stops() {
+        let stops = [(0.0, [0.0, 0.0, 0.0]), (0.5, [1.0, 0.0, 0.0]), (1.0, [1.0, 1.0, 1.0])];
+        assert_eq!(gradient_color(&stops, -1.0), [0.0, 0.0, 0.0]);
+        assert_eq!(gradient_color(&stops, 0.25), [0.5, 0.0, 0.0]);
+        assert_eq!(gradient_color(&stops, 0.75), [1.0, 0.5, 0.5]);
+        assert_eq!(gradient_color(&stops, 2.0), [1.0, 1.0, 1.0]);
+
+        assert_eq!(colorize(&[0.5], &stops), vec![255, 0, 0]);
+    }
+
+    #[test]
+    fn png_round_trips() {
+        let rgb: Vec<u8> = (0..4 * 3 * 3).map(|i| i as u8 * 7).collect();
+        let bytes = encode_png(4, 3, &rgb).unwrap();
+
+        let decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+        let mut reader = decoder.read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut decoded).unwrap();
+        assert_eq!((info.width, info.height), (4, 3));
+        assert_eq!(&decoded[..info.buffer_size()], &rgb[..]);
+
+        assert!(encode_png(4, 4, &rgb).is_err());
+    }
+}
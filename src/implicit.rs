@@ -0,0 +1,542 @@
+// GPU resources for Solver::Implicit (see solver.rs). Each step is
+//
+//   1. reaction_step (compute.wgsl): u* = u + dt R(u) into level 0's residual buffer
+//   2. rhs (implicit.wgsl): (I + (1 - theta) dt D L) u*, with u* as the first guess
+//   3. `cycles` V-cycles on (I - theta dt D L) u' = rhs
+//   4. store_field (implicit.wgsl): clamp and write the next field texture
+//
+// A V-cycle damps the error with a few Jacobi sweeps, restricts the residual to
+// a grid half the size, solves for a correction there the same way, and
+// interpolates it back before sweeping again. Levels halve (rounding up) until
+// one side is COARSEST cells or less, where plain sweeps are enough. Jacobi
+// rather than red-black Gauss-Seidel, since the 9-point and 5x5 kernels couple
+// cells of the same colour.
+
+use crate::simulation::{field_shader, Simulation};
+use crate::solver::kernel_weights;
+
+const COARSEST: u32 = 4;
+// Jacobi sweeps before and after each coarse correction, and on the coarsest
+// level. Both even, so the solution ends up back in `x`.
+const SMOOTHING_SWEEPS: u32 = 2;
+const COARSE_SWEEPS: u32 = 16;
+
+// Uniform for implicit.wgsl, one slot per level
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct LevelUniform {
+    width: u32,
+    height: u32,
+    coarse_width: u32,
+    coarse_height: u32,
+    boundary_mode: u32,
+    _padding1: u32,
+    _padding2: u32,
+    _padding3: u32,
+    implicit_scale: [f32; 2],
+    explicit_scale: [f32; 2],
+    kernel: [f32; 28],
+}
+
+// Why the implicit solver can't step `sim` as it's set up, if it can't
+pub(crate) fn unsupported_reason(sim: &Simulation) -> Option<String> {
+    if !sim.equation.is_gray_scott() {
+        Some("the implicit solver only integrates Gray-Scott".to_string())
+    } else {
+        None
+    }
+}
+
+struct Level {
+    width: u32,
+    height: u32,
+    smooth_bind_groups: [wgpu::BindGroup; 2], // x, b -> x2 and x2, b -> x
+    residual_bind_group: wgpu::BindGroup,     // x, b -> r
+    // To and from the next level down, if there is one
+    restrict_bind_group: Option<wgpu::BindGroup>, // r -> next b, next x
+    prolong_bind_group: Option<wgpu::BindGroup>,  // next x -> x
+}
+
+impl Level {
+    fn workgroups(&self) -> (u32, u32) {
+        (self.width.div_ceil(8), self.height.div_ceil(8))
+    }
+}
+
+// The buffers of one level, each one (U, V) pair per cell
+struct LevelBuffers {
+    x: wgpu::Buffer,
+    x2: wgpu::Buffer,
+    b: wgpu::Buffer,
+    r: wgpu::Buffer,
+}
+
+pub(crate) struct ImplicitSolver {
+    levels: Vec<Level>,
+    params_buffer: wgpu::Buffer,
+    params_stride: u32,
+
+    reaction_pipeline: wgpu::ComputePipeline,
+    reaction_bind_group: wgpu::BindGroup, // group 1: level 0's r
+
+    rhs_pipeline: wgpu::ComputePipeline,
+    rhs_bind_group: wgpu::BindGroup, // r -> b, x
+    smooth_pipeline: wgpu::ComputePipeline,
+    residual_pipeline: wgpu::ComputePipeline,
+    restrict_pipeline: wgpu::ComputePipeline,
+    prolong_pipeline: wgpu::ComputePipeline,
+
+    store_pipeline: wgpu::ComputePipeline,
+    store_bind_group_a: wgpu::BindGroup, // group 1: -> A
+    store_bind_group_b: wgpu::BindGroup, // group 1: -> B
+}
+
+impl ImplicitSolver {
+    // Builds the levels for `sim`'s grid and the passes for its current compute
+    // shader. Fails if unsupported_reason does.
+    pub(crate) fn new(sim: &Simulation) -> Result<Self, String> {
+        if let Some(reason) = unsupported_reason(sim) {
+            return Err(reason);
+        }
+        let device = sim.device();
+
+        let mut sizes = vec![(sim.width(), sim.height())];
+        let (mut width, mut height) = sizes[0];
+        while width.min(height) > COARSEST {
+            (width, height) = (width.div_ceil(2), height.div_ceil(2));
+            sizes.push((width, height));
+        }
+
+        let buffers: Vec<LevelBuffers> = sizes
+            .iter()
+            .map(|&(width, height)| {
+                let create_buffer = |label| {
+                    device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(label),
+                        size: width as u64 * height as u64 * 8,
+                        usage: wgpu::BufferUsages::STORAGE,
+                        mapped_at_creation: false,
+                    })
+                };
+                LevelBuffers {
+                    x: create_buffer("Implicit Solution Buffer"),
+                    x2: create_buffer("Implicit Jacobi Buffer"),
+                    b: create_buffer("Implicit RHS Buffer"),
+                    r: create_buffer("Implicit Residual Buffer"),
+                }
+            })
+            .collect();
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        // Reaction: compute.wgsl's reaction_step entry point
+        let reaction_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Implicit Reaction Bind Group Layout"),
+            entries: &[
+                // binding 0: reacted_field
+                storage_entry(0, false),
+            ],
+        });
+
+        let reaction_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Implicit Reaction Pipeline Layout"),
+            bind_group_layouts: &[sim.pipelines().compute_bind_group_layout(), &reaction_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let reaction_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Implicit Reaction Pipeline"),
+            layout: Some(&reaction_pipeline_layout),
+            module: sim.compute_shader(),
+            entry_point: Some("reaction_step"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let reaction_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Implicit Reaction Bind Group"),
+            layout: &reaction_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffers[0].r.as_entire_binding(),
+            }],
+        });
+
+        // Multigrid passes: implicit.wgsl
+        let shader = field_shader(device, "Implicit Shader", include_str!("implicit.wgsl"), sim.format());
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Implicit Bind Group Layout"),
+            entries: &[
+                // binding 0: src_a
+                storage_entry(0, true),
+                // binding 1: src_b
+                storage_entry(1, true),
+                // binding 2: dst_a
+                storage_entry(2, false),
+                // binding 3: dst_b
+                storage_entry(3, false),
+                // binding 4: per-level params (dynamic offset)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<LevelUniform>() as u64),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let store_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Implicit Store Bind Group Layout"),
+            entries: &[
+                // binding 0: texture_dst
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: sim.format(),
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Implicit Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let store_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Implicit Store Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout, &store_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let create_pipeline = |label, layout, entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let rhs_pipeline = create_pipeline("Implicit RHS Pipeline", &pipeline_layout, "rhs");
+        let smooth_pipeline = create_pipeline("Implicit Smooth Pipeline", &pipeline_layout, "jacobi");
+        let residual_pipeline = create_pipeline("Implicit Residual Pipeline", &pipeline_layout, "residual");
+        let restrict_pipeline = create_pipeline("Implicit Restrict Pipeline", &pipeline_layout, "restrict_residual");
+        let prolong_pipeline = create_pipeline("Implicit Prolong Pipeline", &pipeline_layout, "add_correction");
+        let store_pipeline = create_pipeline("Implicit Store Pipeline", &store_pipeline_layout, "store_field");
+
+        let params_stride = (std::mem::size_of::<LevelUniform>() as u32)
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment);
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Implicit Params Buffer"),
+            size: params_stride as u64 * sizes.len() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // The bindings of each pass, in order src_a, src_b, dst_a, dst_b. Unused
+        // slots get some other buffer of the level, as a buffer can't be bound
+        // both read-only and writable in one group.
+        let create_bind_group = |label, [src_a, src_b, dst_a, dst_b]: [&wgpu::Buffer; 4]| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: src_a.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: src_b.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: dst_a.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: dst_b.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &params_buffer,
+                            offset: 0,
+                            size: wgpu::BufferSize::new(std::mem::size_of::<LevelUniform>() as u64),
+                        }),
+                    },
+                ],
+            })
+        };
+
+        let levels = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &(width, height))| {
+                let LevelBuffers { x, x2, b, r } = &buffers[i];
+                let next = buffers.get(i + 1);
+                Level {
+                    width,
+                    height,
+                    smooth_bind_groups: [
+                        create_bind_group("Implicit Smooth Bind Group x->x2", [x, b, x2, r]),
+                        create_bind_group("Implicit Smooth Bind Group x2->x", [x2, b, x, r]),
+                    ],
+                    residual_bind_group: create_bind_group("Implicit Residual Bind Group", [x, b, r, x2]),
+                    restrict_bind_group: next.map(|next| create_bind_group("Implicit Restrict Bind Group", [r, x, &next.b, &next.x])),
+                    prolong_bind_group: next.map(|next| create_bind_group("Implicit Prolong Bind Group", [&next.x, b, x, r])),
+                }
+            })
+            .collect();
+
+        let top = &buffers[0];
+        let rhs_bind_group = create_bind_group("Implicit RHS Bind Group", [&top.r, &top.x2, &top.b, &top.x]);
+
+        let create_store_bind_group = |label, dst: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &store_bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(dst),
+                }],
+            })
+        };
+        let store_bind_group_a = create_store_bind_group("Implicit Store Bind Group ->A", sim.texture_a_view());
+        let store_bind_group_b = create_store_bind_group("Implicit Store Bind Group ->B", sim.texture_b_view());
+
+        let solver = Self {
+            levels,
+            params_buffer,
+            params_stride,
+            reaction_pipeline,
+            reaction_bind_group,
+            rhs_pipeline,
+            rhs_bind_group,
+            smooth_pipeline,
+            residual_pipeline,
+            restrict_pipeline,
+            prolong_pipeline,
+            store_pipeline,
+            store_bind_group_a,
+            store_bind_group_b,
+        };
+        solver.update_params(sim, 1.0);
+        Ok(solver)
+    }
+
+    // Copies the diffusion rates, time step, boundary mode and kernel from the
+    // simulation, with `theta` the implicit weight
+    pub(crate) fn update_params(&self, sim: &Simulation, theta: f32) {
+        let mut kernel = [0.0; 28];
+        kernel[..25].copy_from_slice(&kernel_weights(sim.kernel_type));
+        let rates = [sim.diffuse_u * sim.delta_time, sim.diffuse_v * sim.delta_time];
+
+        let mut data = vec![0u8; self.params_stride as usize * self.levels.len()];
+        for (i, level) in self.levels.iter().enumerate() {
+            let next = self.levels.get(i + 1).unwrap_or(level);
+            // Each level down doubles the cell size, so L shrinks by 4
+            let scale = theta / 4f32.powi(i as i32);
+            let params = LevelUniform {
+                width: level.width,
+                height: level.height,
+                coarse_width: next.width,
+                coarse_height: next.height,
+                boundary_mode: sim.boundary_mode,
+                _padding1: 0,
+                _padding2: 0,
+                _padding3: 0,
+                implicit_scale: rates.map(|rate| rate * scale),
+                explicit_scale: rates.map(|rate| rate * (1.0 - theta)),
+                kernel,
+            };
+            let offset = i * self.params_stride as usize;
+            data[offset..offset + std::mem::size_of::<LevelUniform>()].copy_from_slice(bytemuck::bytes_of(&params));
+        }
+        sim.queue().write_buffer(&self.params_buffer, 0, &data);
+    }
+
+    // Records one step reading the field through `compute_bind_group` (the one
+    // the explicit solver would use) and writing texture A or B
+    pub(crate) fn encode(&self, encoder: &mut wgpu::CommandEncoder, compute_bind_group: &wgpu::BindGroup, to_a: bool, cycles: u32) {
+        let (groups_x, groups_y) = self.levels[0].workgroups();
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Implicit Diffusion Pass"),
+            timestamp_writes: None,
+        });
+
+        pass.set_pipeline(&self.reaction_pipeline);
+        pass.set_bind_group(0, compute_bind_group, &[]);
+        pass.set_bind_group(1, &self.reaction_bind_group, &[]);
+        pass.dispatch_workgroups(groups_x, groups_y, 1);
+
+        pass.set_pipeline(&self.rhs_pipeline);
+        pass.set_bind_group(0, &self.rhs_bind_group, &[0]);
+        pass.dispatch_workgroups(groups_x, groups_y, 1);
+
+        for _ in 0..cycles {
+            self.v_cycle(&mut pass, 0);
+        }
+
+        pass.set_pipeline(&self.store_pipeline);
+        pass.set_bind_group(0, &self.levels[0].smooth_bind_groups[0], &[0]);
+        pass.set_bind_group(1, if to_a { &self.store_bind_group_a } else { &self.store_bind_group_b }, &[]);
+        pass.dispatch_workgroups(groups_x, groups_y, 1);
+    }
+
+    fn v_cycle(&self, pass: &mut wgpu::ComputePass, index: usize) {
+        let level = &self.levels[index];
+        let offset = index as u32 * self.params_stride;
+        let (groups_x, groups_y) = level.workgroups();
+
+        let (Some(restrict_bind_group), Some(prolong_bind_group)) = (&level.restrict_bind_group, &level.prolong_bind_group) else {
+            self.smooth(pass, index, COARSE_SWEEPS);
+            return;
+        };
+
+        self.smooth(pass, index, SMOOTHING_SWEEPS);
+
+        pass.set_pipeline(&self.residual_pipeline);
+        pass.set_bind_group(0, &level.residual_bind_group, &[offset]);
+        pass.dispatch_workgroups(groups_x, groups_y, 1);
+
+        let (coarse_x, coarse_y) = self.levels[index + 1].workgroups();
+        pass.set_pipeline(&self.restrict_pipeline);
+        pass.set_bind_group(0, restrict_bind_group, &[offset]);
+        pass.dispatch_workgroups(coarse_x, coarse_y, 1);
+
+        self.v_cycle(pass, index + 1);
+
+        pass.set_pipeline(&self.prolong_pipeline);
+        pass.set_bind_group(0, prolong_bind_group, &[offset]);
+        pass.dispatch_workgroups(groups_x, groups_y, 1);
+
+        self.smooth(pass, index, SMOOTHING_SWEEPS);
+    }
+
+    fn smooth(&self, pass: &mut wgpu::ComputePass, index: usize, sweeps: u32) {
+        let level = &self.levels[index];
+        let offset = index as u32 * self.params_stride;
+        let (groups_x, groups_y) = level.workgroups();
+
+        pass.set_pipeline(&self.smooth_pipeline);
+        for sweep in 0..sweeps {
+            pass.set_bind_group(0, &level.smooth_bind_groups[sweep as usize % 2], &[offset]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::{headless_simulation, SplitMix64};
+    use crate::solver::{kernel_weights, Solver};
+
+    #[test]
+    fn small_steps_match_the_explicit_solver() {
+        let Some(mut sim) = headless_simulation(24, 20) else { return };
+        let mut rng = SplitMix64(5);
+        let start: Vec<f32> = (0..24 * 20 * 2).map(|_| rng.next_f32()).collect();
+        sim.delta_time = 0.002;
+
+        for (kernel_type, boundary_mode) in [(0, 1), (1, 2), (2, 0), (3, 1), (4, 2)] {
+            sim.kernel_type = kernel_type;
+            sim.boundary_mode = boundary_mode;
+            let mut run = |solver| {
+                sim.solver = solver;
+                sim.update_params();
+                sim.write_field(&start).unwrap();
+                sim.step(1);
+                pollster::block_on(sim.read_field()).unwrap()
+            };
+            let explicit = run(Solver::Explicit);
+            let implicit = run(Solver::Implicit { theta: 0.5, cycles: 2 });
+            for (i, (a, b)) in explicit.iter().zip(&implicit).enumerate() {
+                assert!((a - b).abs() < 1e-4, "kernel {}, value {}: {} vs {}", kernel_type, i, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn large_steps_solve_the_backward_euler_system() {
+        let (width, height) = (24, 20);
+        let Some(mut sim) = headless_simulation(width, height) else { return };
+        // No V and no feed, so U only diffuses
+        sim.feed_rate = 0.0;
+        sim.kill_rate = 0.0;
+        sim.delta_time = 20.0;
+        sim.boundary_mode = 2;
+        sim.solver = Solver::Implicit { theta: 1.0, cycles: 4 };
+        sim.update_params();
+
+        let mut rng = SplitMix64(8);
+        let start: Vec<f32> = (0..width * height).map(|_| 0.2 + 0.6 * rng.next_f32()).collect();
+        sim.write_field(&start.iter().flat_map(|&u| [u, 0.0]).collect::<Vec<_>>()).unwrap();
+        sim.step(1);
+        let field = pollster::block_on(sim.read_field()).unwrap();
+
+        // Reference: Jacobi on (I - dt Du L) u' = u until it has converged
+        let reflect = |c: i32, size: i32| {
+            let c = c.abs();
+            if c >= size { 2 * (size - 1) - c } else { c }
+        };
+        let weights = kernel_weights(sim.kernel_type);
+        let scale = sim.delta_time * sim.diffuse_u;
+        let (w, h) = (width as i32, height as i32);
+        let mut u = start.clone();
+        for _ in 0..2000 {
+            u = (0..w * h)
+                .map(|i| {
+                    let (x, y) = (i % w, i / w);
+                    let mut off_diagonal = 0.0;
+                    for (j, weight) in weights.iter().enumerate() {
+                        let (dx, dy) = (j as i32 % 5 - 2, j as i32 / 5 - 2);
+                        if *weight != 0.0 && (dx, dy) != (0, 0) {
+                            off_diagonal += weight * u[(reflect(y + dy, h) * w + reflect(x + dx, w)) as usize];
+                        }
+                    }
+                    (start[i as usize] + scale * off_diagonal) / (1.0 - scale * weights[12])
+                })
+                .collect();
+        }
+
+        for (i, uv) in field.chunks(2).enumerate() {
+            assert!((uv[0] - u[i]).abs() < 1e-3, "cell {}: {} vs {}", i, uv[0], u[i]);
+            assert!(uv[1].abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn unsupported_setups_fall_back() {
+        let Some(mut sim) = headless_simulation(30, 17) else { return };
+        sim.solver = Solver::Implicit { theta: 0.5, cycles: 1 };
+        sim.boundary_mode = 1;
+        assert!(sim.solver_fallback().is_none());
+        sim.equation = crate::equation::Equation::SwiftHohenberg { r: 0.3, q0: 0.5, g: 0.0 };
+        assert!(sim.solver_fallback().unwrap().contains("Gray-Scott"));
+        sim.update_params();
+        sim.step(2);
+        assert!(sim.current_is_a());
+    }
+}
@@ -0,0 +1,170 @@
+// Implicit diffusion for Solver::Implicit (implicit.rs). After the explicit
+// reaction u* = u + dt R(u), each step solves
+//   (I - theta dt D L) u' = (I + (1 - theta) dt D L) u*
+// for U and V at once with multigrid V-cycles. L is the selected Laplacian
+// kernel with the field's boundary mode; coarse levels reuse it scaled by 1/4
+// per level. All buffers hold one vec2 (U, V) per cell, row-major.
+
+struct LevelParams {
+    width: u32,
+    height: u32,
+    coarse_width: u32,    // size of the next level down
+    coarse_height: u32,
+    boundary_mode: u32,   // 0=wrap, 1=clamp, 2=reflect
+    _padding1: u32,
+    _padding2: u32,
+    _padding3: u32,
+    implicit_scale: vec2<f32>,  // theta dt D / 4^level, for U and V
+    explicit_scale: vec2<f32>,  // (1 - theta) dt D, only used on level 0
+    kernel: array<vec4<f32>, 7>, // 5x5 weights row-major from (-2, -2), see solver.rs
+}
+
+// Each entry point reads and writes a subset of these; see the bind groups in implicit.rs
+@group(0) @binding(0) var<storage, read> src_a: array<vec2<f32>>;
+@group(0) @binding(1) var<storage, read> src_b: array<vec2<f32>>;
+@group(0) @binding(2) var<storage, read_write> dst_a: array<vec2<f32>>;
+@group(0) @binding(3) var<storage, read_write> dst_b: array<vec2<f32>>;
+@group(0) @binding(4) var<uniform> level: LevelParams;
+@group(1) @binding(0) var texture_dst: texture_storage_2d<rg32float, write>;
+
+// Weighted Jacobi damping; 0.8 smooths the high frequencies of 9-point stencils well
+const JACOBI_WEIGHT: f32 = 0.8;
+
+// Same as apply_boundary in compute.wgsl
+fn apply_boundary(coord: i32, size: i32) -> i32 {
+    if level.boundary_mode == 0u {
+        return (coord + size * 2) % size;
+    } else if level.boundary_mode == 1u {
+        return clamp(coord, 0, size - 1);
+    } else {
+        var c = coord;
+        if c < 0 {
+            c = -c;
+        }
+        if c >= size {
+            c = 2 * (size - 1) - c;
+        }
+        if c < 0 {
+            c = -c;
+        }
+        return clamp(c, 0, size - 1);
+    }
+}
+
+fn kernel_weight(i: i32) -> f32 {
+    return level.kernel[i / 4][i % 4];
+}
+
+// L applied to src_a at `pos`
+fn laplacian(pos: vec2<i32>) -> vec2<f32> {
+    let width = i32(level.width);
+    let height = i32(level.height);
+    var sum = vec2<f32>(0.0);
+    for (var i = 0; i < 25; i++) {
+        let weight = kernel_weight(i);
+        if weight != 0.0 {
+            let x = apply_boundary(pos.x + i % 5 - 2, width);
+            let y = apply_boundary(pos.y + i / 5 - 2, height);
+            sum += src_a[y * width + x] * weight;
+        }
+    }
+    return sum;
+}
+
+fn cell(global_id: vec3<u32>) -> i32 {
+    if global_id.x >= level.width || global_id.y >= level.height {
+        return -1;
+    }
+    return i32(global_id.y * level.width + global_id.x);
+}
+
+// Level 0: right-hand side into dst_a and the initial guess u* into dst_b,
+// from the reacted field in src_a
+@compute @workgroup_size(8, 8)
+fn rhs(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = cell(global_id);
+    if i < 0 {
+        return;
+    }
+    let u = src_a[i];
+    dst_a[i] = u + level.explicit_scale * laplacian(vec2<i32>(global_id.xy));
+    dst_b[i] = u;
+}
+
+// One weighted Jacobi sweep on A x = b: x in src_a, b in src_b, new x into dst_a
+@compute @workgroup_size(8, 8)
+fn jacobi(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = cell(global_id);
+    if i < 0 {
+        return;
+    }
+    let x = src_a[i];
+    let ax = x - level.implicit_scale * laplacian(vec2<i32>(global_id.xy));
+    let diagonal = 1.0 - level.implicit_scale * kernel_weight(12);
+    dst_a[i] = x + JACOBI_WEIGHT * (src_b[i] - ax) / diagonal;
+}
+
+// b - A x into dst_a
+@compute @workgroup_size(8, 8)
+fn residual(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = cell(global_id);
+    if i < 0 {
+        return;
+    }
+    let x = src_a[i];
+    dst_a[i] = src_b[i] - (x - level.implicit_scale * laplacian(vec2<i32>(global_id.xy)));
+}
+
+// Dispatched over the coarse level: averages each 2x2 block of the residual in
+// src_a into the coarse right-hand side dst_a, and zeroes the coarse solution dst_b
+@compute @workgroup_size(8, 8)
+fn restrict_residual(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if global_id.x >= level.coarse_width || global_id.y >= level.coarse_height {
+        return;
+    }
+    var sum = vec2<f32>(0.0);
+    var count = 0.0;
+    for (var dy = 0u; dy < 2u; dy++) {
+        for (var dx = 0u; dx < 2u; dx++) {
+            let x = global_id.x * 2u + dx;
+            let y = global_id.y * 2u + dy;
+            if x < level.width && y < level.height {
+                sum += src_a[y * level.width + x];
+                count += 1.0;
+            }
+        }
+    }
+    let i = global_id.y * level.coarse_width + global_id.x;
+    dst_a[i] = sum / count;
+    dst_b[i] = vec2<f32>(0.0);
+}
+
+// Adds the coarse correction in src_a, bilinearly interpolated, to dst_a
+@compute @workgroup_size(8, 8)
+fn add_correction(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = cell(global_id);
+    if i < 0 {
+        return;
+    }
+    let coarse_size = vec2<i32>(i32(level.coarse_width), i32(level.coarse_height));
+    let coord = (vec2<f32>(global_id.xy) + 0.5) * 0.5 - 0.5;
+    let base = vec2<i32>(floor(coord));
+    let t = coord - vec2<f32>(base);
+
+    let c0 = clamp(base, vec2<i32>(0), coarse_size - 1);
+    let c1 = clamp(base + 1, vec2<i32>(0), coarse_size - 1);
+    let top = mix(src_a[c0.y * coarse_size.x + c0.x], src_a[c0.y * coarse_size.x + c1.x], t.x);
+    let bottom = mix(src_a[c1.y * coarse_size.x + c0.x], src_a[c1.y * coarse_size.x + c1.x], t.x);
+    dst_a[i] += mix(top, bottom, t.y);
+}
+
+// Writes the level 0 solution in src_a to the field texture
+@compute @workgroup_size(8, 8)
+fn store_field(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = cell(global_id);
+    if i < 0 {
+        return;
+    }
+    let result = clamp(src_a[i], vec2<f32>(0.0), vec2<f32>(1.0));
+    textureStore(texture_dst, vec2<i32>(global_id.xy), vec4<f32>(result, 0.0, 1.0));
+}
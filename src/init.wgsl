@@ -0,0 +1,79 @@
+// Initial-state compute shader
+// Writes a starting pattern straight into an RG32Float simulation texture,
+// so resets don't have to build and upload the whole grid from the CPU.
+
+const MAX_BLOBS: u32 = 32u;
+
+struct InitParams {
+    pattern: u32,        // 0=uniform (U=1, V=0), 1=center seed, 2=blobs, 3=noise, 4=soup
+    num_blobs: u32,
+    grid_width: u32,
+    grid_height: u32,
+    seed_radius: f32,    // radius of the center seed (pattern 1)
+    noise_amplitude: f32, // pattern 3
+    noise_seed: u32,
+    soup_size: u32,      // side of the centred noise square (pattern 4)
+    blobs: array<vec4<f32>, MAX_BLOBS>, // xy=center, z=radius (pattern 2)
+}
+
+@group(0) @binding(0) var texture_dst: texture_storage_2d<rg32float, write>;
+@group(0) @binding(1) var<uniform> init_params: InitParams;
+
+// Same hash as the compute shader's noise, in [0, 1]
+fn hash(p: vec2<u32>) -> f32 {
+    var h = p.x * 374761393u + p.y * 668265263u;
+    h = (h ^ (h >> 13u)) * 1274126177u;
+    return f32(h) / 4294967295.0;
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if (global_id.x >= init_params.grid_width || global_id.y >= init_params.grid_height) {
+        return;
+    }
+
+    let pos = vec2<f32>(f32(global_id.x), f32(global_id.y));
+
+    if init_params.pattern == 3u {
+        // Small random U and V around zero
+        let p = global_id.xy ^ vec2<u32>(init_params.noise_seed, init_params.noise_seed * 2654435761u);
+        let noise = vec2<f32>(hash(p), hash(p + vec2<u32>(7919u, 104729u))) * 2.0 - 1.0;
+        textureStore(texture_dst, vec2<i32>(global_id.xy), vec4<f32>(noise * init_params.noise_amplitude, 0.0, 1.0));
+        return;
+    }
+
+    if init_params.pattern == 4u {
+        // V uniform in 0..1 inside a centred square, U = 1 - V
+        let corner = (vec2<u32>(init_params.grid_width, init_params.grid_height) - init_params.soup_size) / 2u;
+        var v = 0.0;
+        if all(global_id.xy >= corner) && all(global_id.xy < corner + init_params.soup_size) {
+            v = hash(global_id.xy ^ vec2<u32>(init_params.noise_seed, init_params.noise_seed * 2654435761u));
+        }
+        textureStore(texture_dst, vec2<i32>(global_id.xy), vec4<f32>(1.0 - v, v, 0.0, 1.0));
+        return;
+    }
+
+    // Base state everywhere: U=1, V=0
+    var v = 0.0;
+
+    if init_params.pattern == 1u {
+        // Small disc of V in the middle of the grid
+        let center = vec2<f32>(f32(init_params.grid_width / 2u), f32(init_params.grid_height / 2u));
+        let d = pos - center;
+        if dot(d, d) < init_params.seed_radius * init_params.seed_radius {
+            v = 1.0;
+        }
+    } else if init_params.pattern == 2u {
+        // Discs of V at the blob centers chosen by the host
+        let count = min(init_params.num_blobs, MAX_BLOBS);
+        for (var i = 0u; i < count; i = i + 1u) {
+            let blob = init_params.blobs[i];
+            let d = pos - blob.xy;
+            if dot(d, d) <= blob.z * blob.z {
+                v = 1.0;
+            }
+        }
+    }
+
+    textureStore(texture_dst, vec2<i32>(global_id.xy), vec4<f32>(1.0, v, 0.0, 1.0));
+}
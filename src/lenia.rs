@@ -0,0 +1,459 @@
+// Lenia, a continuous cellular automaton (Chan 2019; SmoothLife is the
+// single-ring special case). Each cell holds a state A in 0..1 and follows
+//
+//   A' = clamp(A + dt * G(K * A), 0, 1)
+//   G(u) = 2 exp(-(u - mu)^2 / (2 sigma^2)) - 1
+//
+// where K is a normalised kernel of `radius` cells made of concentric rings,
+// ring i weighted by peaks[i] and shaped by the smooth bump exp(4 - 1/(r(1 - r))).
+// Lenia runs on the Simulation's own ping-pong field (A in V, 1 - A in U), so
+// the renderer, stats, spectrum and recording work on it unchanged.
+
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::{field_shader, InitParams, Simulation};
+
+pub const MAX_RADIUS: u32 = 24;
+pub const MAX_PEAKS: usize = 4;
+
+// The workgroup size of lenia.wgsl; the tile it caches is this plus the halo
+const BLOCK: u32 = 8;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LeniaModel {
+    pub radius: u32,     // kernel radius in cells, 1..=MAX_RADIUS
+    pub peaks: Vec<f32>, // ring heights from the centre out, 0..1
+    pub mu: f32,         // growth centre
+    pub sigma: f32,      // growth width
+    pub delta_time: f32,
+    // Seeding: uniform noise in a centred `soup` x `soup` square
+    pub soup: u32,
+}
+
+impl Default for LeniaModel {
+    fn default() -> Self {
+        Self {
+            radius: 13,
+            peaks: vec![1.0],
+            mu: 0.15,
+            sigma: 0.015,
+            delta_time: 0.1,
+            soup: 64,
+        }
+    }
+}
+
+// (name, label) of the built-in models, see lenia_preset
+pub const LENIA_PRESETS: [(&str, &str); 4] = [
+    ("orbium_worms", "Orbium Worms"),
+    ("hydrogeminium", "Hydrogeminium"),
+    ("smooth_life", "SmoothLife"),
+    ("amoeba", "Amoeba"),
+];
+
+pub fn lenia_preset(name: &str) -> Option<LeniaModel> {
+    let name = name.to_ascii_lowercase().replace('-', "_");
+    Some(match name.as_str() {
+        // The Orbium glider's rule; from a dense soup it fills the grid with
+        // writhing worms rather than settling into gliders
+        "orbium_worms" => LeniaModel::default(),
+        // Three rings; grows into a field of ragged, fuzzy colonies
+        "hydrogeminium" => LeniaModel {
+            radius: 18,
+            peaks: vec![0.5, 1.0, 0.667],
+            mu: 0.26,
+            sigma: 0.036,
+            delta_time: 0.1,
+            soup: 96,
+        },
+        // A wide growth window and short time step: a smooth, slowly settling maze
+        "smooth_life" => LeniaModel {
+            radius: 10,
+            peaks: vec![1.0],
+            mu: 0.31,
+            sigma: 0.049,
+            delta_time: 0.05,
+            soup: 128,
+        },
+        // Two rings with a weak centre; chains of beads
+        "amoeba" => LeniaModel {
+            radius: 15,
+            peaks: vec![0.25, 1.0],
+            mu: 0.2,
+            sigma: 0.031,
+            delta_time: 0.1,
+            soup: 96,
+        },
+        _ => return None,
+    })
+}
+
+// Uniform for lenia.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct LeniaUniform {
+    grid_width: u32,
+    grid_height: u32,
+    tap_count: u32,
+    _padding1: u32,
+    mu: f32,
+    sigma: f32,
+    delta_time: f32,
+    _padding2: f32,
+}
+
+impl LeniaModel {
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let model: Self = serde_json::from_str(text).map_err(|e| format!("Invalid Lenia model: {}", e))?;
+        model.validate()?;
+        Ok(model)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_RADIUS).contains(&self.radius) {
+            return Err(format!("radius must be between 1 and {}", MAX_RADIUS));
+        }
+        if self.peaks.is_empty() || self.peaks.len() > MAX_PEAKS {
+            return Err(format!("peaks needs 1 to {} values", MAX_PEAKS));
+        }
+        if self.peaks.iter().any(|p| !(0.0..=1.0).contains(p)) || self.peaks.iter().all(|&p| p == 0.0) {
+            return Err("peaks must be between 0 and 1, and not all 0".to_string());
+        }
+        if !(self.mu.is_finite() && self.mu > 0.0 && self.mu < 1.0) {
+            return Err("mu must be between 0 and 1".to_string());
+        }
+        for (name, value) in [("sigma", self.sigma), ("delta_time", self.delta_time)] {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(format!("{} must be positive", name));
+            }
+        }
+        Ok(())
+    }
+
+    // The kernel as (dx, dy, weight, 0) for every cell with a non-zero weight,
+    // normalised so the weights sum to 1
+    pub(crate) fn kernel_taps(&self) -> Vec<[f32; 4]> {
+        let radius = self.radius as i32;
+        let rings = self.peaks.len() as f32;
+        let mut taps = Vec::new();
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let r = ((dx * dx + dy * dy) as f32).sqrt() / self.radius as f32;
+                if r >= 1.0 {
+                    continue;
+                }
+                let ring = r * rings;
+                let x = ring.fract();
+                if x <= 0.0 {
+                    continue;
+                }
+                let weight = self.peaks[ring as usize] * (4.0 - 1.0 / (x * (1.0 - x))).exp();
+                if weight > 0.0 {
+                    taps.push([dx as f32, dy as f32, weight, 0.0]);
+                }
+            }
+        }
+        let total: f32 = taps.iter().map(|tap| tap[2]).sum();
+        for tap in &mut taps {
+            tap[2] /= total;
+        }
+        taps
+    }
+
+    // The growth function G
+    pub fn growth(&self, u: f32) -> f32 {
+        let d = u - self.mu;
+        2.0 * (-d * d / (2.0 * self.sigma * self.sigma)).exp() - 1.0
+    }
+}
+
+pub struct Lenia {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    width: u32,
+    height: u32,
+    model: LeniaModel,
+    step_count: u64,
+
+    pipeline: wgpu::ComputePipeline,
+    bind_group_a_to_b: wgpu::BindGroup,
+    bind_group_b_to_a: wgpu::BindGroup,
+}
+
+impl Lenia {
+    // Builds the pipeline for `model` over the simulation's field textures. The
+    // field itself is left alone until `randomize` or `step`.
+    pub fn new(sim: &Simulation, model: LeniaModel) -> Result<Self, String> {
+        model.validate()?;
+        let (width, height) = (sim.width(), sim.height());
+        if 2 * model.radius + 1 > width.min(height) {
+            return Err(format!("A kernel of radius {} doesn't fit a {}x{} grid", model.radius, width, height));
+        }
+        let device = sim.device();
+
+        let source = include_str!("lenia.wgsl").replace("const RADIUS: i32 = 13;", &format!("const RADIUS: i32 = {};", model.radius));
+        let shader = field_shader(device, "Lenia Shader", &source, sim.format());
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Lenia Bind Group Layout"),
+            entries: &[
+                // binding 0: texture_src
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // binding 1: texture_dst
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: sim.format(),
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // binding 2: params uniform buffer
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 3: kernel taps
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Lenia Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Lenia Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let taps = model.kernel_taps();
+        let params = LeniaUniform {
+            grid_width: width,
+            grid_height: height,
+            tap_count: taps.len() as u32,
+            _padding1: 0,
+            mu: model.mu,
+            sigma: model.sigma,
+            delta_time: model.delta_time,
+            _padding2: 0.0,
+        };
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lenia Params Buffer"),
+            size: std::mem::size_of::<LeniaUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        sim.queue().write_buffer(&params_buffer, 0, bytemuck::cast_slice(&[params]));
+
+        let taps_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lenia Kernel Buffer"),
+            size: std::mem::size_of_val(taps.as_slice()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        sim.queue().write_buffer(&taps_buffer, 0, bytemuck::cast_slice(&taps));
+
+        let create_bind_group = |label: &str, src: &wgpu::TextureView, dst: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(src),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(dst),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: taps_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let bind_group_a_to_b = create_bind_group("Lenia Bind Group A->B", sim.texture_a_view(), sim.texture_b_view());
+        let bind_group_b_to_a = create_bind_group("Lenia Bind Group B->A", sim.texture_b_view(), sim.texture_a_view());
+
+        Ok(Self {
+            device: device.clone(),
+            queue: sim.queue().clone(),
+            width,
+            height,
+            model,
+            step_count: 0,
+            pipeline,
+            bind_group_a_to_b,
+            bind_group_b_to_a,
+        })
+    }
+
+    pub fn model(&self) -> &LeniaModel {
+        &self.model
+    }
+
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    // Clears the field and fills the centred soup square with uniform noise,
+    // with the simulation's init pass
+    pub fn randomize(&mut self, sim: &mut Simulation, seed: u64) -> Result<(), String> {
+        if (sim.width(), sim.height()) != (self.width, self.height) {
+            return Err(format!("Simulation is {}x{}, Lenia grid is {}x{}", sim.width(), sim.height(), self.width, self.height));
+        }
+        let soup = self.model.soup.min(self.width).min(self.height);
+        sim.init_field(&InitParams::soup(seed, soup));
+        self.step_count = 0;
+        Ok(())
+    }
+
+    // Advances the simulation's field by `steps` Lenia steps
+    pub fn step(&mut self, sim: &mut Simulation, steps: u32) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Lenia Encoder"),
+        });
+        self.encode_steps(&mut encoder, sim, steps);
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    // Records `steps` Lenia steps into `encoder`, to be submitted with a frame's other passes
+    pub fn encode_steps(&mut self, encoder: &mut wgpu::CommandEncoder, sim: &mut Simulation, steps: u32) {
+        let (groups_x, groups_y) = (self.width.div_ceil(BLOCK), self.height.div_ceil(BLOCK));
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Lenia Pass"),
+                timestamp_writes: None,
+            });
+
+            compute_pass.set_pipeline(&self.pipeline);
+            for _ in 0..steps {
+                let bind_group = if sim.current_is_a() {
+                    &self.bind_group_a_to_b
+                } else {
+                    &self.bind_group_b_to_a
+                };
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                compute_pass.dispatch_workgroups(groups_x, groups_y, 1);
+                sim.swap();
+            }
+        }
+
+        self.step_count += steps as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::headless_simulation;
+
+    #[test]
+    fn models_validate() {
+        for (name, _) in LENIA_PRESETS {
+            lenia_preset(name).unwrap().validate().unwrap();
+        }
+        assert!(lenia_preset("Smooth-Life").is_some());
+
+        let model = LeniaModel::from_json(r#"{ "radius": 8, "peaks": [1, 0.5] }"#).unwrap();
+        let total: f32 = model.kernel_taps().iter().map(|tap| tap[2]).sum();
+        assert!((total - 1.0).abs() < 1e-5);
+
+        for bad in [
+            r#"{ "radius": 0 }"#,
+            r#"{ "radius": 25 }"#,
+            r#"{ "peaks": [] }"#,
+            r#"{ "peaks": [0, 0] }"#,
+            r#"{ "peaks": [1.5] }"#,
+            r#"{ "sigma": 0 }"#,
+            r#"{ "mu": 1.2 }"#,
+            r#"{ "gamma": 1 }"#,
+        ] {
+            assert!(LeniaModel::from_json(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn steps_match_a_cpu_convolution() {
+        let Some(mut sim) = headless_simulation(24, 20) else { return };
+        let model = LeniaModel {
+            radius: 5,
+            peaks: vec![0.5, 1.0],
+            mu: 0.2,
+            sigma: 0.05,
+            delta_time: 0.2,
+            soup: 16,
+        };
+        let mut lenia = Lenia::new(&sim, model.clone()).unwrap();
+        lenia.randomize(&mut sim, 7).unwrap();
+        let start: Vec<f32> = pollster::block_on(sim.read_field()).unwrap().chunks(2).map(|uv| uv[1]).collect();
+
+        // The soup fills the centred 16x16 square and nothing else
+        for (i, &v) in start.iter().enumerate() {
+            let inside = (4..20).contains(&(i % 24)) && (2..18).contains(&(i / 24));
+            assert!(if inside { (0.0..=1.0).contains(&v) } else { v == 0.0 }, "cell {}: {}", i, v);
+        }
+        assert!(start.iter().filter(|&&v| v > 0.0).count() > 200);
+        lenia.step(&mut sim, 1);
+
+        let (width, height) = (24i32, 20i32);
+        let taps = model.kernel_taps();
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        for y in 0..height {
+            for x in 0..width {
+                let potential: f32 = taps
+                    .iter()
+                    .map(|tap| {
+                        let (sx, sy) = ((x + tap[0] as i32).rem_euclid(width), (y + tap[1] as i32).rem_euclid(height));
+                        start[(sy * width + sx) as usize] * tap[2]
+                    })
+                    .sum();
+                let i = (y * width + x) as usize;
+                let expected = (start[i] + model.delta_time * model.growth(potential)).clamp(0.0, 1.0);
+                let (u, v) = (field[i * 2], field[i * 2 + 1]);
+                assert!((v - expected).abs() < 1e-4, "({}, {}): {} vs {}", x, y, v, expected);
+                assert!((u - (1.0 - v)).abs() < 1e-6);
+            }
+        }
+        assert!(Lenia::new(&sim, LeniaModel { radius: 10, ..model }).is_err());
+    }
+}
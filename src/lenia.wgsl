@@ -0,0 +1,70 @@
+// Lenia compute shader
+// One state A in 0..1 per cell, kept in the V channel of the simulation field
+// (U holds 1 - A so the field still reads as a two-species state):
+//   A' = clamp(A + dt * G(K * A), 0, 1),  G(u) = 2 exp(-(u - mu)^2 / (2 sigma^2)) - 1
+// K is a ring kernel of radius RADIUS, passed in as a list of non-zero taps.
+// Each workgroup first copies its block plus a RADIUS-cell halo into workgroup
+// memory, so the convolution reads the texture once per tile instead of once
+// per tap. The grid wraps at the edges.
+
+struct LeniaParams {
+    grid_width: u32,
+    grid_height: u32,
+    tap_count: u32,
+    _padding1: u32,
+    mu: f32,
+    sigma: f32,
+    delta_time: f32,
+    _padding2: f32,
+}
+
+@group(0) @binding(0) var texture_src: texture_2d<f32>;
+@group(0) @binding(1) var texture_dst: texture_storage_2d<rg32float, write>;
+@group(0) @binding(2) var<uniform> params: LeniaParams;
+@group(0) @binding(3) var<storage, read> taps: array<vec4<f32>>; // (dx, dy, weight, 0)
+
+// Replaced with the kernel radius when the pipeline is built
+const RADIUS: i32 = 13;
+const BLOCK: i32 = 8;
+const TILE: i32 = BLOCK + 2 * RADIUS;
+
+var<workgroup> tile: array<f32, TILE * TILE>;
+
+fn growth(u: f32) -> f32 {
+    let d = u - params.mu;
+    return 2.0 * exp(-d * d / (2.0 * params.sigma * params.sigma)) - 1.0;
+}
+
+@compute @workgroup_size(8, 8)
+fn main(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+    @builtin(workgroup_id) group_id: vec3<u32>,
+) {
+    let size = vec2<i32>(i32(params.grid_width), i32(params.grid_height));
+    let origin = vec2<i32>(group_id.xy) * BLOCK - vec2<i32>(RADIUS);
+
+    // Cooperative tile load, wrapping around the grid
+    let local_index = i32(local_id.y) * BLOCK + i32(local_id.x);
+    for (var i = local_index; i < TILE * TILE; i += BLOCK * BLOCK) {
+        let coord = (origin + vec2<i32>(i % TILE, i / TILE) + size * 2) % size;
+        tile[i] = textureLoad(texture_src, coord, 0).y;
+    }
+    workgroupBarrier();
+
+    if global_id.x >= params.grid_width || global_id.y >= params.grid_height {
+        return;
+    }
+
+    let center = vec2<i32>(local_id.xy) + vec2<i32>(RADIUS);
+    var potential = 0.0;
+    for (var i = 0u; i < params.tap_count; i++) {
+        let tap = taps[i];
+        let p = center + vec2<i32>(tap.xy);
+        potential += tile[p.y * TILE + p.x] * tap.z;
+    }
+
+    let a = tile[center.y * TILE + center.x];
+    let next = clamp(a + params.delta_time * growth(potential), 0.0, 1.0);
+    textureStore(texture_dst, vec2<i32>(global_id.xy), vec4<f32>(1.0 - next, next, 0.0, 1.0));
+}
@@ -4,10 +4,49 @@ use wgpu::util::DeviceExt;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+pub mod adaptive;
+pub mod classify;
+pub mod dump;
+pub mod equation;
+pub mod expr;
+pub mod fft;
+pub mod flow;
+pub mod image;
+mod implicit;
+pub mod lenia;
+pub mod monitor;
+pub mod perf;
+pub mod presets;
+mod readback;
+pub mod scene;
+pub mod simulation;
+pub mod solver;
+pub mod species;
+mod spectral;
+pub mod spectrum;
+pub mod stats;
+pub mod sweep;
+pub mod timeline;
+
+use monitor::StateMonitor;
+use equation::Equation;
+use lenia::{Lenia, LeniaModel};
+use scene::{ColorStop, InitRecipe, Scene, SceneFormat};
+use perf::{PerfMonitor, Section};
+use species::{MultiSpecies, SpeciesModel, SpeciesView};
+use simulation::{InitParams, Simulation};
+use spectrum::SpectrumAnalyzer;
+use stats::FieldStatsRecorder;
+use timeline::Timeline;
+
 const GRID_WIDTH: u32 = 2048;
 const GRID_HEIGHT: u32 = 2048;
 const MAX_GRADIENT_STOPS: usize = 8;
 
+// What the canvas shows
+const VIEW_FIELD: u32 = 0;
+const VIEW_SPECTRUM: u32 = 1;
+
 #[repr(C, align(16))]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct GradientStop {
@@ -28,62 +67,60 @@ struct GradientData {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct SimParams {
-    feed_rate: f32,
-    kill_rate: f32,
-    diffuse_u: f32,
-    diffuse_v: f32,
-    delta_time: f32,
-    noise_strength: f32,
-    grid_width: u32,
-    grid_height: u32,
-    kernel_type: u32,        // 0=default, 1=cross, 2=diagonal, 3=spiral
-    boundary_mode: u32,      // 0=wrap, 1=clamp, 2=reflect
-    map_mode: u32,
-    _padding: u32,
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct RenderParams {
+    color_palette: u32,
+    emboss_enabled: u32,
+    boundary_mode: u32,
+    field_view: u32,
+    zoom: f32,
+    pan_x: f32,
+    pan_y: f32,
+    perf_overlay: u32,
+    perf_ms: [f32; 4],  // steps, paint and render GPU time and the frame interval, for the overlay
 }
 
 struct GrayScottApp {
     device: wgpu::Device,
     queue: wgpu::Queue,
     surface: wgpu::Surface<'static>,
+    #[allow(dead_code)] // Kept so the surface can be reconfigured later
     surface_config: wgpu::SurfaceConfiguration,
-    
-    // Ping-pong textures
-    texture_a: wgpu::Texture,
-    texture_b: wgpu::Texture,
-    current_src: bool,
-    
-    // Compute pipeline
-    compute_pipeline: wgpu::ComputePipeline,
-    compute_bind_group_a_to_b: wgpu::BindGroup,
-    compute_bind_group_b_to_a: wgpu::BindGroup,
-    params_buffer: wgpu::Buffer,
-    
-    // Paint pipeline
-    paint_pipeline: wgpu::ComputePipeline,
-    paint_bind_group_a: wgpu::BindGroup,
-    paint_bind_group_b: wgpu::BindGroup,
-    paint_params_buffer: wgpu::Buffer,
-    
+
+    // Ping-pong textures and compute pipelines
+    sim: Simulation,
+
+    // Field statistics, sampled every `stats_interval` simulated frames (0 = off)
+    stats: FieldStatsRecorder,
+    stats_interval: u32,
+    frame_count: u64,
+
+    // Steady-state / extinction detection, fed from the stats samples. The epoch
+    // is bumped whenever the field is re-seeded so in-flight reads are dropped.
+    monitor: StateMonitor,
+    monitor_cursor: u64,
+    monitor_epoch: u32,
+    monitor_reading: bool,
+    time_step_reading: bool, // an adaptive controller read is in flight
+    perf: PerfMonitor,
+    perf_overlay: bool,
+    last_change: f32,  // RMS change per step of the newest stats sample, for classification
+    state_callback: Option<js_sys::Function>,
+
     // Render pipeline
     render_pipeline: wgpu::RenderPipeline,
     render_bind_group_a: wgpu::BindGroup,
     render_bind_group_b: wgpu::BindGroup,
     render_params_buffer: wgpu::Buffer,
     gradient_buffer: wgpu::Buffer,
+    render_bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+
+    // Power spectrum analysis and its render bind group, created on first use
+    spectrum: Option<(SpectrumAnalyzer, wgpu::BindGroup)>,
+    view_mode: u32,
     
     // Parameters
-    feed_rate: f32,
-    kill_rate: f32,
-    diffuse_u: f32,
-    diffuse_v: f32,
-    delta_time: f32,
-    noise_strength: f32,
-    kernel_type: u32,
-    boundary_mode: u32,
-    map_mode: bool,
     paused: bool,
     mouse_pos: Option<(f32, f32)>,
     prev_mouse_pos: Option<(f32, f32)>,
@@ -96,10 +133,26 @@ struct GrayScottApp {
     pan_x: f32,
     pan_y: f32,
     emboss_enabled: bool,
+
+    // Display gradient as last uploaded, and the seed and init recipe of the
+    // last loaded scene, so save_scene can write them back out
+    gradient: Vec<ColorStop>,
+    scene_seed: u64,
+    scene_init: InitRecipe,
+
+    // Keyframed parameters, evaluated at the current step every frame
+    timeline: Option<Timeline>,
+
+    // Multi-species model; while set it runs instead of `sim`, whose field
+    // only shows the projected species view
+    species: Option<MultiSpecies>,
+
+    // Lenia automaton; while set it steps `sim`'s field in place of the PDE
+    lenia: Option<Lenia>,
 }
 
 impl GrayScottApp {
-    async fn new(canvas: HtmlCanvasElement) -> Result<Self, JsValue> {
+    async fn new(canvas: HtmlCanvasElement, half_precision: bool) -> Result<Self, JsValue> {
         // Create wgpu instance with WebGPU backend (needed for compute shaders)
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::BROWSER_WEBGPU,
@@ -144,7 +197,15 @@ impl GrayScottApp {
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("Device"),
-                    required_features: wgpu::Features::empty(),
+                    // Native adapters only allow f16 storage textures through
+                    // their adapter-specific format features. Timestamps are
+                    // taken when offered, for perf_stats().
+                    required_features: if half_precision {
+                        adapter.features()
+                            & (wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES | wgpu::Features::TIMESTAMP_QUERY)
+                    } else {
+                        adapter.features() & wgpu::Features::TIMESTAMP_QUERY
+                    },
                     required_limits: wgpu::Limits::downlevel_webgl2_defaults()
                         .using_resolution(adapter.limits()),
                     memory_hints: Default::default(),
@@ -176,147 +237,17 @@ impl GrayScottApp {
         };
         surface.configure(&device, &surface_config);
 
-        // Create simulation textures
-        let texture_desc = wgpu::TextureDescriptor {
-            label: Some("Simulation Texture"),
-            size: wgpu::Extent3d {
-                width: GRID_WIDTH,
-                height: GRID_HEIGHT,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rg32Float,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        };
-
-        let texture_a = device.create_texture(&texture_desc);
-        let texture_b = device.create_texture(&texture_desc);
-
-        // Initialize texture A with pattern
-        let mut init_data = vec![0.0f32; (GRID_WIDTH * GRID_HEIGHT * 2) as usize];
-        for y in 0..GRID_HEIGHT {
-            for x in 0..GRID_WIDTH {
-                let idx = ((y * GRID_WIDTH + x) * 2) as usize;
-                init_data[idx] = 1.0; // U starts at 1
-                init_data[idx + 1] = 0.0; // V starts at 0
-                
-                // Add some initial disturbance in the center
-                let dx = x as i32 - GRID_WIDTH as i32 / 2;
-                let dy = y as i32 - GRID_HEIGHT as i32 / 2;
-                let dist_sq = dx * dx + dy * dy;
-                if dist_sq < 400 {
-                    init_data[idx + 1] = 1.0;
+        let field_format = match simulation::half_field_format(&adapter).filter(|_| half_precision) {
+            Some(format) => format,
+            None => {
+                if half_precision {
+                    log::warn!("No f16 storage texture format on this adapter; keeping the f32 field");
                 }
+                simulation::preferred_field_format(&adapter)
             }
-        }
-
-        queue.write_texture(
-            texture_a.as_image_copy(),
-            bytemuck::cast_slice(&init_data),
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(GRID_WIDTH * 8),
-                rows_per_image: Some(GRID_HEIGHT),
-            },
-            wgpu::Extent3d {
-                width: GRID_WIDTH,
-                height: GRID_HEIGHT,
-                depth_or_array_layers: 1,
-            },
-        );
-
-        // Create parameter buffer
-        let params = SimParams {
-            feed_rate: 0.055,
-            kill_rate: 0.062,
-            diffuse_u: 1.0,         // DA - standard value from tutorial
-            diffuse_v: 0.5,         // DB - standard value from tutorial
-            delta_time: 1.0,
-            noise_strength: 0.0,
-            grid_width: GRID_WIDTH,
-            grid_height: GRID_HEIGHT,
-            kernel_type: 0,         // Default kernel
-            boundary_mode: 0,       // Wrap (toroidal)
-            map_mode: 0,
-            _padding: 0,
         };
-
-        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Params Buffer"),
-            contents: bytemuck::cast_slice(&[params]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        // Create compute shader module
-        let compute_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Compute Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("compute.wgsl").into()),
-        });
-
-        // Create compute bind group layout (must match compute.wgsl bindings)
-        let compute_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Compute Bind Group Layout"),
-            entries: &[
-                // binding 0: texture_src
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                // binding 1: texture_dst
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::StorageTexture {
-                        access: wgpu::StorageTextureAccess::WriteOnly,
-                        format: wgpu::TextureFormat::Rg32Float,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                // binding 2: sampler
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
-                    count: None,
-                },
-                // binding 3: params uniform buffer
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-        let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Compute Pipeline Layout"),
-            bind_group_layouts: &[&compute_bind_group_layout],
-            push_constant_ranges: &[],
-        });
-
-        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Compute Pipeline"),
-            layout: Some(&compute_pipeline_layout),
-            module: &compute_shader,
-            entry_point: Some("main"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
+        let mut sim = Simulation::new(&device, &queue, GRID_WIDTH, GRID_HEIGHT, field_format);
+        sim.boundary_mode = 2;  // Reflect (Mirror)
 
         // Create sampler for texture reads - Nearest for Rg32Float (doesn't support filtering)
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -329,169 +260,6 @@ impl GrayScottApp {
             ..Default::default()
         });
 
-        // Create compute bind groups
-        let texture_a_view = texture_a.create_view(&wgpu::TextureViewDescriptor::default());
-        let texture_b_view = texture_b.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let compute_bind_group_a_to_b = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Compute Bind Group A->B"),
-            layout: &compute_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_a_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&texture_b_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: params_buffer.as_entire_binding(),
-                },
-            ],
-        });
-
-        let compute_bind_group_b_to_a = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Compute Bind Group B->A"),
-            layout: &compute_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_b_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&texture_a_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: params_buffer.as_entire_binding(),
-                },
-            ],
-        });
-
-        // Create paint shader and pipeline
-        let paint_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Paint Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("paint.wgsl").into()),
-        });
-
-        #[repr(C)]
-        #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-        struct PaintParams {
-            center_x: f32,
-            center_y: f32,
-        }
-
-        let paint_params = PaintParams {
-            center_x: 0.0,
-            center_y: 0.0,
-        };
-
-        let paint_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Paint Params Buffer"),
-            contents: bytemuck::cast_slice(&[paint_params]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let paint_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Paint Bind Group Layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::StorageTexture {
-                        access: wgpu::StorageTextureAccess::WriteOnly,
-                        format: wgpu::TextureFormat::Rg32Float,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-        let paint_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Paint Pipeline Layout"),
-            bind_group_layouts: &[&paint_bind_group_layout],
-            push_constant_ranges: &[],
-        });
-
-        let paint_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Paint Pipeline"),
-            layout: Some(&paint_pipeline_layout),
-            module: &paint_shader,
-            entry_point: Some("main"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
-
-        let paint_bind_group_a = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Paint Bind Group A"),
-            layout: &paint_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_a_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&texture_b_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: paint_params_buffer.as_entire_binding(),
-                },
-            ],
-        });
-
-        let paint_bind_group_b = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Paint Bind Group B"),
-            layout: &paint_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_b_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&texture_a_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: paint_params_buffer.as_entire_binding(),
-                },
-            ],
-        });
-
         // Create render shader module
         let render_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Render Shader"),
@@ -541,26 +309,16 @@ impl GrayScottApp {
             ],
         });
 
-        #[repr(C)]
-        #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-        struct RenderParams {
-            color_palette: u32,
-            emboss_enabled: u32,
-            boundary_mode: u32,
-            _padding: u32,
-            zoom: f32,
-            pan_x: f32,
-            pan_y: f32,
-        }
-
         let render_params = RenderParams {
             color_palette: 0,
             emboss_enabled: 1,  // Default to enabled
             boundary_mode: 0,
-            _padding: 0,
+            field_view: 0,
             zoom: 1.0,
             pan_x: 0.0,
             pan_y: 0.0,
+            perf_overlay: 0,
+            perf_ms: [0.0; 4],
         };
 
         let render_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -649,7 +407,7 @@ impl GrayScottApp {
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_a_view),
+                    resource: wgpu::BindingResource::TextureView(sim.texture_a_view()),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
@@ -672,7 +430,7 @@ impl GrayScottApp {
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_b_view),
+                    resource: wgpu::BindingResource::TextureView(sim.texture_b_view()),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
@@ -689,36 +447,36 @@ impl GrayScottApp {
             ],
         });
 
+        let stats = FieldStatsRecorder::new(&sim);
+        let perf = PerfMonitor::new(&device, &queue);
+
         Ok(Self {
             device,
             queue,
             surface,
             surface_config,
-            texture_a,
-            texture_b,
-            current_src: true,
-            compute_pipeline,
-            compute_bind_group_a_to_b,
-            compute_bind_group_b_to_a,
-            params_buffer,
-            paint_pipeline,
-            paint_bind_group_a,
-            paint_bind_group_b,
-            paint_params_buffer,
+            sim,
+            stats,
+            stats_interval: 30,
+            frame_count: 0,
+            monitor: StateMonitor::default(),
+            monitor_cursor: 0,
+            monitor_epoch: 0,
+            monitor_reading: false,
+            time_step_reading: false,
+            perf,
+            perf_overlay: false,
+            last_change: 0.0,
+            state_callback: None,
             render_pipeline,
             render_bind_group_a,
             render_bind_group_b,
             render_params_buffer,
             gradient_buffer,
-            feed_rate: 0.055,
-            kill_rate: 0.062,
-            diffuse_u: 1.0,         // DA - standard value from tutorial
-            diffuse_v: 0.5,         // DB - standard value from tutorial
-            delta_time: 1.0,
-            noise_strength: 0.0,
-            kernel_type: 0,         // Default kernel
-            boundary_mode: 2,       // Reflect (Mirror)
-            map_mode: false,
+            render_bind_group_layout,
+            sampler,
+            spectrum: None,
+            view_mode: VIEW_FIELD,
             paused: false,
             mouse_pos: None,
             prev_mouse_pos: None,
@@ -729,27 +487,15 @@ impl GrayScottApp {
             pan_x: 0.0,
             pan_y: 0.0,
             emboss_enabled: true,
+            gradient: Scene::default().gradient,
+            scene_seed: 0,
+            scene_init: InitRecipe::Center,
+            timeline: None,
+            species: None,
+            lenia: None,
         })
     }
 
-    fn update_params(&mut self) {
-        let params = SimParams {
-            feed_rate: self.feed_rate,
-            kill_rate: self.kill_rate,
-            diffuse_u: self.diffuse_u,
-            diffuse_v: self.diffuse_v,
-            delta_time: self.delta_time,
-            noise_strength: self.noise_strength,
-            grid_width: GRID_WIDTH,
-            grid_height: GRID_HEIGHT,
-            kernel_type: self.kernel_type,
-            boundary_mode: self.boundary_mode,
-            map_mode: if self.map_mode { 1 } else { 0 },
-            _padding: 0,
-        };
-        self.queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
-    }
-
     fn paint_at(&mut self, x: f32, y: f32) {
         // Apply the SAME transformation the shader uses to convert screen coords to texture coords
         // This is exactly what the shader does in fs_main:
@@ -764,91 +510,174 @@ impl GrayScottApp {
         
         let grid_x = (tx * GRID_WIDTH as f32) as u32;
         let grid_y = (ty * GRID_HEIGHT as f32) as u32;
-        
-        #[repr(C)]
-        #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-        struct PaintParams {
-            center_x: f32,
-            center_y: f32,
-        }
-
-        let paint_params = PaintParams {
-            center_x: grid_x as f32,
-            center_y: grid_y as f32,
-        };
-
-        self.queue.write_buffer(
-            &self.paint_params_buffer,
-            0,
-            bytemuck::cast_slice(&[paint_params]),
-        );
 
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Paint Encoder"),
         });
+        let span = self.perf.begin(&mut encoder, Section::Paint);
+        self.sim.encode_paint(&mut encoder, grid_x as f32, grid_y as f32);
+        self.perf.end(&mut encoder, span);
+        self.queue.submit(Some(encoder.finish()));
+    }
 
-        {
-            let mut paint_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Paint Pass"),
-                timestamp_writes: None,
+    // Creates the spectrum analyzer and its render bind group on first use
+    fn ensure_spectrum(&mut self) -> Result<(), String> {
+        if self.spectrum.is_none() {
+            let analyzer = SpectrumAnalyzer::new(&self.sim)?;
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Render Bind Group Spectrum"),
+                layout: &self.render_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(analyzer.view()),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: self.render_params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: self.gradient_buffer.as_entire_binding(),
+                    },
+                ],
             });
+            self.spectrum = Some((analyzer, bind_group));
+        }
+        Ok(())
+    }
 
-            paint_pass.set_pipeline(&self.paint_pipeline);
-            
-            let bind_group = if self.current_src {
-                &self.paint_bind_group_a
-            } else {
-                &self.paint_bind_group_b
-            };
-            
-            paint_pass.set_bind_group(0, bind_group, &[]);
-            paint_pass.dispatch_workgroups(GRID_WIDTH / 8, GRID_HEIGHT / 8, 1);
+    // Re-seeds the field and starts state detection over
+    fn init_field(&mut self, init: &InitParams) {
+        self.sim.init_field(init);
+        self.monitor.reset();
+        self.monitor_cursor = self.stats.samples_written();
+        self.monitor_epoch = self.monitor_epoch.wrapping_add(1);
+        self.last_change = 0.0;
+    }
+
+    fn to_scene(&self) -> Scene {
+        // The gradient picker hands stops over in the order they were added
+        let mut gradient = self.gradient.clone();
+        gradient.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Scene {
+            model: scene::ModelParams {
+                feed_rate: self.sim.feed_rate,
+                kill_rate: self.sim.kill_rate,
+                diffuse_u: self.sim.diffuse_u,
+                diffuse_v: self.sim.diffuse_v,
+                delta_time: self.sim.delta_time,
+                noise_strength: self.sim.noise_strength,
+                kernel_type: self.sim.kernel_type,
+                boundary_mode: self.sim.boundary_mode,
+                feed_expression: self.sim.feed_expression().map(str::to_string),
+                kill_expression: self.sim.kill_expression().map(str::to_string),
+                equation: self.sim.equation,
+                flow: self.sim.flow,
+                solver: self.sim.solver,
+                adaptive: self.sim.adaptive,
+            },
+            steps_per_frame: self.steps_per_frame,
+            gradient,
+            emboss: self.emboss_enabled,
+            view: scene::ViewParams { zoom: self.zoom, pan_x: self.pan_x, pan_y: self.pan_y },
+            seed: self.scene_seed,
+            init: self.scene_init.clone(),
+            ..Default::default()
         }
+    }
 
-        self.queue.submit(Some(encoder.finish()));
-        
-        // Toggle since we wrote to the opposite texture
-        self.current_src = !self.current_src;
+    // Takes over every setting of a validated scene and re-seeds the field from it
+    fn apply_scene(&mut self, scene: &Scene) -> Result<(), String> {
+        scene.apply(&mut self.sim)?;
+        self.steps_per_frame = scene.steps_per_frame;
+        write_color_stops(self, &scene.gradient);
+        self.emboss_enabled = scene.emboss;
+        self.zoom = scene.view.zoom;
+        self.pan_x = scene.view.pan_x;
+        self.pan_y = scene.view.pan_y;
+        update_render_params(self);
+
+        self.scene_seed = scene.seed;
+        self.scene_init = scene.init.clone();
+        let init = scene.init.init_params(scene.seed, self.sim.width(), self.sim.height());
+        self.init_field(&init);
+        Ok(())
     }
 
+    // Records the frame's steps, analysis passes and render pass into one
+    // encoder and submits them together
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        if !self.paused {
-            // Run multiple simulation steps per frame
-            for _ in 0..self.steps_per_frame {
-                let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Compute Encoder"),
-                });
-
-                {
-                    let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                        label: Some("Compute Pass"),
-                        timestamp_writes: None,
-                    });
-
-                    compute_pass.set_pipeline(&self.compute_pipeline);
-                    
-                    let bind_group = if self.current_src {
-                        &self.compute_bind_group_a_to_b
-                    } else {
-                        &self.compute_bind_group_b_to_a
-                    };
-                    
-                    compute_pass.set_bind_group(0, bind_group, &[]);
-                    compute_pass.dispatch_workgroups(GRID_WIDTH / 8, GRID_HEIGHT / 8, 1);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Frame Encoder"),
+        });
+        self.perf.frame(now_ms(), if self.paused { 0 } else { self.steps_per_frame });
+
+        if let Some(species) = &mut self.species {
+            if !self.paused {
+                let span = self.perf.begin(&mut encoder, Section::Steps);
+                species.encode_steps(&mut encoder, self.steps_per_frame);
+                self.perf.end(&mut encoder, span);
+                self.perf.add_steps(self.steps_per_frame);
+                self.frame_count += 1;
+            }
+            species.encode_project(&mut encoder, &self.sim).expect("species grid matches the simulation");
+        } else if let Some(lenia) = &mut self.lenia {
+            if !self.paused {
+                let span = self.perf.begin(&mut encoder, Section::Steps);
+                lenia.encode_steps(&mut encoder, &mut self.sim, self.steps_per_frame);
+                self.perf.end(&mut encoder, span);
+                self.perf.add_steps(self.steps_per_frame);
+                self.frame_count += 1;
+            }
+        } else if !self.paused {
+            if let Some(values) = self.timeline.as_ref().map(|t| t.evaluate(self.sim.step_count())) {
+                values.apply(&mut self.sim);
+                self.sim.update_params();
+                if let Some(gradient) = values.gradient.filter(|g| *g != self.gradient) {
+                    write_color_stops(self, &gradient);
                 }
+            }
 
-                self.queue.submit(Some(encoder.finish()));
-                self.current_src = !self.current_src;
+            // Run multiple simulation steps per frame
+            let span = self.perf.begin(&mut encoder, Section::Steps);
+            self.sim.encode_steps(&mut encoder, self.steps_per_frame);
+            self.perf.end(&mut encoder, span);
+            self.perf.add_steps(self.steps_per_frame);
+
+            self.frame_count += 1;
+            if self.stats_interval > 0 && self.frame_count.is_multiple_of(self.stats_interval as u64) {
+                self.stats.encode_record(&mut encoder, &self.sim);
             }
         }
 
-        let output = self.surface.get_current_texture()?;
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        if self.perf_overlay {
+            update_render_params(self);
+        }
 
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Render Encoder"),
-        });
+        let spectrum_bind_group = match (&self.spectrum, self.view_mode) {
+            (Some((analyzer, bind_group)), VIEW_SPECTRUM) => {
+                analyzer.encode(&mut encoder, &self.sim);
+                Some(bind_group)
+            }
+            _ => None,
+        };
+
+        let output = match self.surface.get_current_texture() {
+            Ok(output) => output,
+            Err(e) => {
+                // The steps are already counted as taken, so they still have to run
+                self.queue.submit(Some(encoder.finish()));
+                return Err(e);
+            }
+        };
+        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
+        let span = self.perf.begin(&mut encoder, Section::Render);
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
@@ -868,7 +697,9 @@ impl GrayScottApp {
 
             render_pass.set_pipeline(&self.render_pipeline);
             
-            let bind_group = if self.current_src {
+            let bind_group = if let Some(bind_group) = spectrum_bind_group {
+                bind_group
+            } else if self.sim.current_is_a() {
                 &self.render_bind_group_a
             } else {
                 &self.render_bind_group_b
@@ -877,6 +708,8 @@ impl GrayScottApp {
             render_pass.set_bind_group(0, bind_group, &[]);
             render_pass.draw(0..6, 0..1);
         }
+        self.perf.end(&mut encoder, span);
+        self.perf.resolve(&mut encoder);
 
         self.queue.submit(Some(encoder.finish()));
         output.present();
@@ -886,38 +719,196 @@ impl GrayScottApp {
 }
 
 thread_local! {
-    static APP: RefCell<Option<Rc<RefCell<GrayScottApp>>>> = RefCell::new(None);
-}
-
-#[wasm_bindgen(start)]
-pub fn start() {
-    console_error_panic_hook::set_once();
-    console_log::init_with_level(log::Level::Info).expect("Failed to initialize logger");
+    static APP: RefCell<Option<Rc<RefCell<GrayScottApp>>>> = const { RefCell::new(None) };
 }
 
-#[wasm_bindgen]
-pub async fn init_app(canvas_id: &str) -> Result<(), JsValue> {
-    let window = web_sys::window().ok_or("No window")?;
-    let document = window.document().ok_or("No document")?;
-    let canvas = document
-        .get_element_by_id(canvas_id)
-        .ok_or("Canvas not found")?
-        .dyn_into::<HtmlCanvasElement>()?;
+// Feeds new stats samples to the state monitor. The read finishes asynchronously;
+// on a state change the run is optionally paused and the JS callback is invoked
+// with (state, step) once the app is no longer borrowed.
+fn poll_state_monitor(app: &Rc<RefCell<GrayScottApp>>) {
+    let (read, epoch) = {
+        let mut app_mut = app.borrow_mut();
+        if app_mut.monitor_reading || app_mut.monitor_cursor == app_mut.stats.samples_written() {
+            return;
+        }
+        app_mut.monitor_reading = true;
 
-    let app = GrayScottApp::new(canvas).await?;
-    APP.with(|a| {
-        *a.borrow_mut() = Some(Rc::new(RefCell::new(app)));
-    });
+        let mut cursor = app_mut.monitor_cursor;
+        let read = app_mut.stats.read_since(&mut cursor);
+        app_mut.monitor_cursor = cursor;
+        (read, app_mut.monitor_epoch)
+    };
 
-    Ok(())
-}
+    let app = app.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        let samples = read.await;
 
-#[wasm_bindgen]
+        let (transitions, callback) = {
+            let mut app = app.borrow_mut();
+            app.monitor_reading = false;
+
+            let samples = match samples {
+                Ok(samples) if app.monitor_epoch == epoch => samples,
+                Ok(_) => return,
+                Err(e) => {
+                    log::warn!("State monitor read failed: {}", e);
+                    return;
+                }
+            };
+
+            let mut transitions = Vec::new();
+            if let Some(sample) = samples.last() {
+                app.last_change = sample.change;
+            }
+            for sample in &samples {
+                if let Some(state) = app.monitor.update(sample) {
+                    if state != monitor::RunState::Active && app.monitor.auto_pause {
+                        app.paused = true;
+                    }
+                    transitions.push((state, sample.step));
+                }
+            }
+            (transitions, app.state_callback.clone())
+        };
+
+        if let Some(callback) = callback {
+            for (state, step) in transitions {
+                let _ = callback.call2(&JsValue::NULL, &state.name().into(), &step.into());
+            }
+        }
+    });
+}
+
+// Refreshes the host's view of the adaptive controller (current_dt, sim_time),
+// one read at a time
+fn poll_time_step(app: &Rc<RefCell<GrayScottApp>>) {
+    let read = {
+        let mut app_mut = app.borrow_mut();
+        if app_mut.time_step_reading || app_mut.sim.adaptive.is_none() {
+            return;
+        }
+        app_mut.time_step_reading = true;
+        app_mut.sim.read_time_step()
+    };
+
+    let app = app.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        let state = read.await;
+        let mut app = app.borrow_mut();
+        app.time_step_reading = false;
+        match state {
+            Ok(Some(state)) => app.sim.sync_time_step(state),
+            Ok(None) => {}
+            Err(e) => log::warn!("Time step read failed: {}", e),
+        }
+    });
+}
+
+// Reads the GPU timestamps of the last timed frame, if any
+fn poll_perf(app: &Rc<RefCell<GrayScottApp>>) {
+    let read = {
+        let mut app_mut = app.borrow_mut();
+        let app_mut = &mut *app_mut;
+        match app_mut.perf.read(&app_mut.device, &app_mut.queue) {
+            Some(read) => read,
+            None => return,
+        }
+    };
+
+    let app = app.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        let sample = read.await;
+        let mut app = app.borrow_mut();
+        match sample {
+            Ok(sample) => app.perf.finish_read(sample),
+            Err(e) => {
+                app.perf.abandon_read();
+                log::warn!("Timestamp read failed: {}", e);
+            }
+        }
+    });
+}
+
+// Milliseconds on the page's high-resolution clock
+fn now_ms() -> f64 {
+    web_sys::window().and_then(|w| w.performance()).map_or(0.0, |p| p.now())
+}
+
+#[wasm_bindgen(start)]
+pub fn start() {
+    console_error_panic_hook::set_once();
+    console_log::init_with_level(log::Level::Info).expect("Failed to initialize logger");
+}
+
+// `half_precision` stores the field as f16 where the adapter allows it, which
+// halves its memory traffic but drifts from the f32 field over long runs
+// (see the precision example). field_format() reports what was chosen.
+#[wasm_bindgen]
+pub async fn init_app(canvas_id: &str, half_precision: Option<bool>) -> Result<(), JsValue> {
+    let window = web_sys::window().ok_or("No window")?;
+    let document = window.document().ok_or("No document")?;
+    let canvas = document
+        .get_element_by_id(canvas_id)
+        .ok_or("Canvas not found")?
+        .dyn_into::<HtmlCanvasElement>()?;
+
+    let app = GrayScottApp::new(canvas, half_precision.unwrap_or(false)).await?;
+    APP.with(|a| {
+        *a.borrow_mut() = Some(Rc::new(RefCell::new(app)));
+    });
+
+    Ok(())
+}
+
+// Smoothed frame timing as JSON: steps_per_second, ms_per_step, ms_per_frame,
+// gpu_timing and, with GPU timestamps, steps_ms, paint_ms and render_ms (GPU
+// time per frame of each). Without timestamps ms_per_step is the frame interval
+// over the steps taken in it, which includes waiting for vsync.
+#[wasm_bindgen]
+pub fn perf_stats() -> Result<String, JsValue> {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            Ok(serde_json::to_string(&app.borrow().perf.stats()).map_err(|e| e.to_string())?)
+        } else {
+            Err("App not initialized".into())
+        }
+    })
+}
+
+// Draws the perf_stats() times as bars in the corner of the canvas
+#[wasm_bindgen]
+pub fn set_perf_overlay(enabled: bool) {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            app.perf_overlay = enabled;
+            update_render_params(&mut app);
+        }
+    });
+}
+
+// Storage format of the field, e.g. "Rg32Float" or "Rg16Float"
+#[wasm_bindgen]
+pub fn field_format() -> Result<String, JsValue> {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            Ok(format!("{:?}", app.borrow().sim.format()))
+        } else {
+            Err("App not initialized".into())
+        }
+    })
+}
+
+#[wasm_bindgen]
 pub fn render_frame() -> Result<(), JsValue> {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
-            app.borrow_mut().render()
-                .map_err(|e| format!("Render error: {:?}", e).into())
+            let result = app.borrow_mut().render()
+                .map_err(|e| format!("Render error: {:?}", e).into());
+            poll_state_monitor(app);
+            poll_time_step(app);
+            poll_perf(app);
+            result
         } else {
             Err("App not initialized".into())
         }
@@ -929,8 +920,8 @@ pub fn set_feed_rate(value: f32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app = app.borrow_mut();
-            app.feed_rate = value;
-            app.update_params();
+            app.sim.feed_rate = value;
+            app.sim.update_params();
         }
     });
 }
@@ -940,8 +931,8 @@ pub fn set_kill_rate(value: f32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app = app.borrow_mut();
-            app.kill_rate = value;
-            app.update_params();
+            app.sim.kill_rate = value;
+            app.sim.update_params();
         }
     });
 }
@@ -951,8 +942,8 @@ pub fn set_diffuse_u(value: f32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app = app.borrow_mut();
-            app.diffuse_u = value;
-            app.update_params();
+            app.sim.diffuse_u = value;
+            app.sim.update_params();
         }
     });
 }
@@ -962,8 +953,8 @@ pub fn set_diffuse_v(value: f32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app = app.borrow_mut();
-            app.diffuse_v = value;
-            app.update_params();
+            app.sim.diffuse_v = value;
+            app.sim.update_params();
         }
     });
 }
@@ -981,43 +972,210 @@ pub fn set_paused(paused: bool) {
 pub fn reset() {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
-            let app = app.borrow_mut();
-        
-        let mut init_data = vec![0.0f32; (GRID_WIDTH * GRID_HEIGHT * 2) as usize];
-        for y in 0..GRID_HEIGHT {
-            for x in 0..GRID_WIDTH {
-                let idx = ((y * GRID_WIDTH + x) * 2) as usize;
-                init_data[idx] = 1.0;
-                init_data[idx + 1] = 0.0;
-                
-                let dx = x as i32 - GRID_WIDTH as i32 / 2;
-                let dy = y as i32 - GRID_HEIGHT as i32 / 2;
-                let dist_sq = dx * dx + dy * dy;
-                if dist_sq < 400 {
-                    init_data[idx + 1] = 1.0;
-                }
+            let mut app = app.borrow_mut();
+            // Species are reseeded in their own state, which each frame projects
+            // over the field
+            let seed = app.scene_seed;
+            if let Some(species) = &mut app.species {
+                species.randomize(seed);
+                return;
             }
+            // Lenia starts over from a new soup
+            if let Some(mut lenia) = app.lenia.take() {
+                lenia.randomize(&mut app.sim, seed).expect("soup matches the grid");
+                app.lenia = Some(lenia);
+                return;
+            }
+            // The other equations grow their patterns from noise
+            let init = if app.sim.equation.is_gray_scott() {
+                InitParams::center_seed()
+            } else {
+                InitParams::noise(app.scene_seed, 0.1)
+            };
+            app.init_field(&init);
         }
-        
-        let target = if app.current_src { &app.texture_a } else { &app.texture_b };
-            app.queue.write_texture(
-                target.as_image_copy(),
-                bytemuck::cast_slice(&init_data),
-                wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(GRID_WIDTH * 8),
-                    rows_per_image: Some(GRID_HEIGHT),
-                },
-                wgpu::Extent3d {
-                    width: GRID_WIDTH,
-                    height: GRID_HEIGHT,
-                    depth_or_array_layers: 1,
-                },
-            );
+    });
+}
+
+// Copies a block of the current field back from the GPU as interleaved (U, V) pairs
+#[wasm_bindgen]
+pub async fn read_region(x: u32, y: u32, width: u32, height: u32) -> Result<js_sys::Float32Array, JsValue> {
+    let read = APP
+        .with(|a| a.borrow().as_ref().map(|app| app.borrow().sim.read_region(x, y, width, height)))
+        .ok_or("App not initialized")?;
+
+    let values = read.await?;
+    Ok(js_sys::Float32Array::from(values.as_slice()))
+}
+
+// Reads the (U, V) values of one cell as a two-element array
+#[wasm_bindgen]
+pub async fn read_cell(x: u32, y: u32) -> Result<js_sys::Float32Array, JsValue> {
+    read_region(x, y, 1, 1).await
+}
+
+// Sample field statistics every `frames` simulated frames (0 turns sampling off)
+#[wasm_bindgen]
+pub fn set_stats_interval(frames: u32) {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            app.borrow_mut().stats_interval = frames;
+        }
+    });
+}
+
+// Cells with V above this threshold count towards the coverage statistic
+#[wasm_bindgen]
+pub fn set_stats_threshold(threshold: f32) {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            app.borrow_mut().stats.v_threshold = threshold;
+        }
+    });
+}
+
+// Returns the statistics samples recorded since the last call, oldest first, flattened
+// into records of 11 values:
+// [step, mean_u, mean_v, var_u, var_v, min_u, min_v, max_u, max_v, coverage, change]
+#[wasm_bindgen]
+pub async fn read_stats() -> Result<js_sys::Float64Array, JsValue> {
+    let read = APP
+        .with(|a| a.borrow().as_ref().map(|app| app.borrow_mut().stats.read_new()))
+        .ok_or("App not initialized")?;
+
+    let samples = read.await?;
+    let records: Vec<f64> = samples.iter().flat_map(|sample| sample.to_record()).collect();
+    Ok(js_sys::Float64Array::from(records.as_slice()))
+}
+
+// Registers `callback(state, step)`, called when the run becomes "active", "steady"
+// or "extinct". Detection runs on the stats samples, so it needs a non-zero stats interval.
+#[wasm_bindgen]
+pub fn on_state_change(callback: js_sys::Function) {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            app.borrow_mut().state_callback = Some(callback);
+        }
+    });
+}
+
+#[wasm_bindgen]
+pub fn set_state_detection(steady_threshold: f32, extinction_threshold: f32, steady_samples: u32) {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            app.monitor.steady_threshold = steady_threshold;
+            app.monitor.extinction_threshold = extinction_threshold;
+            app.monitor.steady_samples = steady_samples;
         }
     });
 }
 
+// Pause automatically when the run becomes steady or extinct
+#[wasm_bindgen]
+pub fn set_auto_pause(enabled: bool) {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            app.borrow_mut().monitor.auto_pause = enabled;
+        }
+    });
+}
+
+#[wasm_bindgen]
+pub fn run_state() -> String {
+    APP.with(|a| {
+        a.borrow()
+            .as_ref()
+            .map(|app| app.borrow().monitor.state().name().to_string())
+            .unwrap_or_default()
+    })
+}
+
+// Reads the field back and labels it: "extinct", "uniform", "waves", "spots",
+// "stripes", "labyrinth" or "holes". Temporal change comes from the stats samples,
+// so waves are only recognised with a non-zero stats interval.
+#[wasm_bindgen]
+pub async fn classify_pattern() -> Result<String, JsValue> {
+    let (read, width, height, change, periodic) = APP
+        .with(|a| {
+            a.borrow().as_ref().map(|app| {
+                let app = app.borrow();
+                (app.sim.read_field(), app.sim.width(), app.sim.height(), app.last_change, app.sim.boundary_mode == 0)
+            })
+        })
+        .ok_or("App not initialized")?;
+
+    let v = classify::v_channel(&read.await?);
+    let classification = classify::classify(&v, width, height, change, periodic, &classify::ClassifierConfig::default());
+    Ok(classification.class.name().to_string())
+}
+
+// Runs a parameter sweep described by a JSON `SweepConfig` (missing fields take
+// their defaults) on the app's device. Resolves to { manifest, csv, png } where
+// manifest is JSON text and png the contact sheet as a Uint8Array.
+#[wasm_bindgen]
+pub async fn run_sweep(config_json: &str) -> Result<JsValue, JsValue> {
+    let config: sweep::SweepConfig =
+        serde_json::from_str(config_json).map_err(|e| format!("Invalid sweep config: {}", e))?;
+    let (device, queue, format) = APP
+        .with(|a| {
+            a.borrow().as_ref().map(|app| {
+                let app = app.borrow();
+                (app.device.clone(), app.queue.clone(), app.sim.format())
+            })
+        })
+        .ok_or("App not initialized")?;
+
+    let result = sweep::run_sweep(&device, &queue, format, &config).await?;
+    let png = result.contact_sheet_png(&image::DEFAULT_GRADIENT)?;
+
+    let output = js_sys::Object::new();
+    js_sys::Reflect::set(&output, &"manifest".into(), &result.manifest_json()?.into())?;
+    js_sys::Reflect::set(&output, &"csv".into(), &result.manifest_csv().into())?;
+    js_sys::Reflect::set(&output, &"png".into(), &js_sys::Uint8Array::from(png.as_slice()).into())?;
+    Ok(output.into())
+}
+
+// 0 = field, 1 = centred log-magnitude power spectrum of V
+#[wasm_bindgen]
+pub fn set_view_mode(mode: u32) -> Result<(), JsValue> {
+    if mode > VIEW_SPECTRUM {
+        return Err(format!("Unknown view mode {}", mode).into());
+    }
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            if mode == VIEW_SPECTRUM {
+                app.ensure_spectrum()?;
+            }
+            app.view_mode = mode;
+        }
+        Ok(())
+    })
+}
+
+// Radially averaged power spectrum of V, as [dominant_wavelength, power_0, power_1, ...]
+// where power_r is the mean power at r cycles per N cells (N = 2 * (len - 2)) and the
+// wavelength is in cells, or NaN for a featureless field.
+#[wasm_bindgen]
+pub async fn analyze_spectrum() -> Result<js_sys::Float32Array, JsValue> {
+    let analyze = APP
+        .with(|a| {
+            a.borrow().as_ref().map(|app| {
+                let mut app = app.borrow_mut();
+                app.ensure_spectrum()?;
+                let (analyzer, _) = app.spectrum.as_ref().unwrap();
+                Ok::<_, String>(analyzer.analyze(&app.sim))
+            })
+        })
+        .ok_or("App not initialized")??;
+
+    let spectrum = analyze.await?;
+    let mut values = vec![spectrum.dominant_wavelength().unwrap_or(f32::NAN)];
+    values.extend_from_slice(&spectrum.radial_power);
+    Ok(js_sys::Float32Array::from(values.as_slice()))
+}
+
 #[wasm_bindgen]
 pub fn handle_mouse_down(x: f32, y: f32) {
     APP.with(|a| {
@@ -1057,7 +1215,7 @@ pub fn handle_mouse_move(x: f32, y: f32) {
                     let dist = (dx * dx + dy * dy).sqrt();
                     
                     // Paint points along the line - about 1 point per 0.01 normalized distance
-                    let steps = (dist * 50.0).ceil().max(1.0).min(20.0) as i32;
+                    let steps = (dist * 50.0).ceil().clamp(1.0, 20.0) as i32;
                     
                     for i in 1..=steps {
                         let t = i as f32 / steps as f32;
@@ -1075,18 +1233,59 @@ pub fn handle_mouse_move(x: f32, y: f32) {
     });
 }
 
+// Sets only F and k. index.js uses load_preset now; this stays for pages
+// written against the old API.
 #[wasm_bindgen]
 pub fn apply_preset(feed: f32, kill: f32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app = app.borrow_mut();
-            app.feed_rate = feed;
-            app.kill_rate = kill;
-            app.update_params();
+            app.sim.feed_rate = feed;
+            app.sim.kill_rate = kill;
+            app.sim.update_params();
         }
     });
 }
 
+// The built-in presets as a JSON array of { name, label, feed, kill, kernel_type,
+// delta_time, seed, blobs, palette, equation }, where equation is tagged by
+// type as in scene files, e.g. { "type": "gray_scott" }
+#[wasm_bindgen]
+pub fn list_presets() -> Result<String, JsValue> {
+    Ok(serde_json::to_string(presets::list_presets()).map_err(|e| e.to_string())?)
+}
+
+// One preset as JSON, or an error for an unknown name
+#[wasm_bindgen]
+pub fn get_preset(name: &str) -> Result<String, JsValue> {
+    let preset = presets::get_preset(name).ok_or_else(|| format!("Unknown preset '{}'", name))?;
+    Ok(serde_json::to_string(preset).map_err(|e| e.to_string())?)
+}
+
+// Switches to a preset with its recommended kernel, time step, palette and seed,
+// and re-seeds the field
+#[wasm_bindgen]
+pub fn load_preset(name: &str) -> Result<(), JsValue> {
+    let preset = presets::get_preset(name).ok_or_else(|| format!("Unknown preset '{}'", name))?;
+    let scene = preset.scene();
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            app.borrow_mut().apply_scene(&scene)?;
+        }
+        Ok(())
+    })
+}
+
+// The gradient palettes as a JSON array of { name, label, stops: [{ position, color }] }
+#[wasm_bindgen]
+pub fn list_palettes() -> Result<String, JsValue> {
+    let palettes: Vec<_> = presets::list_palettes()
+        .iter()
+        .map(|p| serde_json::json!({ "name": p.name, "label": p.label, "stops": p.color_stops() }))
+        .collect();
+    Ok(serde_json::to_string(&palettes).map_err(|e| e.to_string())?)
+}
+
 #[wasm_bindgen]
 pub fn set_color_palette(palette: u32) {
     APP.with(|a| {
@@ -1104,8 +1303,8 @@ pub fn set_kernel(kernel: u32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app_mut = app.borrow_mut();
-            app_mut.kernel_type = kernel;
-            app_mut.update_params();
+            app_mut.sim.kernel_type = kernel;
+            app_mut.sim.update_params();
         }
     });
 }
@@ -1115,8 +1314,19 @@ pub fn set_boundary(boundary: u32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app_mut = app.borrow_mut();
-            app_mut.boundary_mode = boundary;
-            app_mut.update_params();
+            app_mut.sim.boundary_mode = boundary;
+            app_mut.sim.update_params();
+        }
+    });
+}
+
+// Switches explicit steps between the per-texel and the workgroup-tiled
+// compute kernel (see examples/bench.rs for timings)
+#[wasm_bindgen]
+pub fn set_tiled_kernel(enabled: bool) {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            app.borrow_mut().sim.tiled_kernel = enabled;
         }
     });
 }
@@ -1126,8 +1336,8 @@ pub fn set_noise(strength: f32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app_mut = app.borrow_mut();
-            app_mut.noise_strength = strength;
-            app_mut.update_params();
+            app_mut.sim.noise_strength = strength;
+            app_mut.sim.update_params();
         }
     });
 }
@@ -1136,53 +1346,419 @@ pub fn set_noise(strength: f32) {
 pub fn set_gradient(positions: &[f32], colors: &[f32]) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
-            let app_mut = app.borrow_mut();
+            let mut app_mut = app.borrow_mut();
             
             // Validate input: positions and colors must be aligned
             let num_stops = positions.len().min(colors.len() / 4).min(MAX_GRADIENT_STOPS);
-            
-            let mut gradient_stops = [GradientStop {
-                position: 0.0,
-                _padding1: 0.0,
-                _padding2: 0.0,
-                _padding3: 0.0,
-                color: [0.0, 0.0, 0.0, 1.0],
-            }; MAX_GRADIENT_STOPS];
-            
-            for i in 0..num_stops {
-                gradient_stops[i] = GradientStop {
-                    position: positions[i],
-                    _padding1: 0.0,
-                    _padding2: 0.0,
-                    _padding3: 0.0,
-                    color: [
-                        colors[i * 4],
-                        colors[i * 4 + 1],
-                        colors[i * 4 + 2],
-                        colors[i * 4 + 3],
-                    ],
-                };
+            let stops: Vec<(f32, [f32; 4])> = (0..num_stops)
+                .map(|i| (positions[i], [colors[i * 4], colors[i * 4 + 1], colors[i * 4 + 2], colors[i * 4 + 3]]))
+                .collect();
+            write_gradient(&mut app_mut, &stops);
+        }
+    });
+}
+
+// Uploads up to MAX_GRADIENT_STOPS (position, RGBA) stops and remembers them for save_scene
+fn write_gradient(app: &mut GrayScottApp, stops: &[(f32, [f32; 4])]) {
+    let mut gradient_stops = [GradientStop {
+        position: 0.0,
+        _padding1: 0.0,
+        _padding2: 0.0,
+        _padding3: 0.0,
+        color: [0.0, 0.0, 0.0, 1.0],
+    }; MAX_GRADIENT_STOPS];
+    
+    let num_stops = stops.len().min(MAX_GRADIENT_STOPS);
+    for (slot, &(position, color)) in gradient_stops.iter_mut().zip(&stops[..num_stops]) {
+        *slot = GradientStop {
+            position,
+            _padding1: 0.0,
+            _padding2: 0.0,
+            _padding3: 0.0,
+            color,
+        };
+    }
+    
+    let gradient_data = GradientData {
+        stops: gradient_stops,
+        num_stops: num_stops as u32,
+        _padding: [0, 0, 0],
+        _final_padding: [0.0, 0.0, 0.0, 0.0],
+    };
+    
+    app.queue.write_buffer(&app.gradient_buffer, 0, bytemuck::cast_slice(&[gradient_data]));
+    app.gradient = stops[..num_stops]
+        .iter()
+        .map(|&(position, [r, g, b, _])| ColorStop { position, color: [r, g, b] })
+        .collect();
+}
+
+fn write_color_stops(app: &mut GrayScottApp, stops: &[ColorStop]) {
+    let stops: Vec<(f32, [f32; 4])> = stops
+        .iter()
+        .map(|s| (s.position, [s.color[0], s.color[1], s.color[2], 1.0]))
+        .collect();
+    write_gradient(app, &stops);
+}
+
+// Animates feed, kill, diffusion, noise and gradient stops from keyframes keyed by
+// step count (counted from the last re-seed). The JSON is a `Timeline`:
+// { "repeat": false, "keyframes": [{ "step": 0, "easing": "ease_in_out", "feed_rate": 0.03, ... }] }
+#[wasm_bindgen]
+pub fn set_timeline(json: &str) -> Result<(), JsValue> {
+    let timeline = Timeline::from_json(json)?;
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            app.borrow_mut().timeline = Some(timeline);
+        }
+    });
+    Ok(())
+}
+
+// Stops the animation, leaving the parameters where it last put them
+#[wasm_bindgen]
+pub fn clear_timeline() {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            app.borrow_mut().timeline = None;
+        }
+    });
+}
+
+// Switches to the multi-species model described by the JSON (a `SpeciesModel`):
+// { "species": 3, "diffusion": [0.2, 0.2, 0.2], "matrix": [[...], ...], "terms": [null, "c0 * c1", null] }
+// and seeds it from `seed`. Species 0 is shown until set_species_view says otherwise.
+#[wasm_bindgen]
+pub fn set_species_model(json: &str, seed: u32) -> Result<(), JsValue> {
+    let model = SpeciesModel::from_json(json)?;
+    start_species(model, seed)
+}
+
+fn start_species(model: SpeciesModel, seed: u32) -> Result<(), JsValue> {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            let sim = &app.sim;
+            let mut species = MultiSpecies::new(sim.device(), sim.queue(), sim.width(), sim.height(), model, sim.format())?;
+            species.randomize(seed as u64);
+            app.species = Some(species);
+            app.lenia = None;
+            update_render_params(&mut app);
+        }
+        Ok(())
+    })
+}
+
+// The built-in multi-species models as a JSON array of { name, label }
+#[wasm_bindgen]
+pub fn list_species_presets() -> Result<String, JsValue> {
+    let presets: Vec<_> = species::SPECIES_PRESETS
+        .iter()
+        .map(|(name, label)| serde_json::json!({ "name": name, "label": label }))
+        .collect();
+    Ok(serde_json::to_string(&presets).map_err(|e| e.to_string())?)
+}
+
+#[wasm_bindgen]
+pub fn load_species_preset(name: &str, seed: u32) -> Result<(), JsValue> {
+    let model = species::species_preset(name).ok_or_else(|| format!("Unknown species preset '{}'", name))?;
+    start_species(model, seed)
+}
+
+// What the renderer shows of the species, as JSON: { "type": "species", "index": 1 },
+// { "type": "weighted", "weights": [1, -1, 0] } or { "type": "dominant" }
+#[wasm_bindgen]
+pub fn set_species_view(json: &str) -> Result<(), JsValue> {
+    let view: SpeciesView = serde_json::from_str(json).map_err(|e| format!("Invalid species view: {}", e))?;
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            let species = app.species.as_mut().ok_or("No species model is running")?;
+            species.set_view(view)?;
+        }
+        Ok(())
+    })
+}
+
+// Goes back to the two-species model with a freshly seeded field
+#[wasm_bindgen]
+pub fn clear_species_model() {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            if app.species.take().is_some() {
+                app.init_field(&InitParams::center_seed());
+                update_render_params(&mut app);
             }
-            
-            let gradient_data = GradientData {
-                stops: gradient_stops,
-                num_stops: num_stops as u32,
-                _padding: [0, 0, 0],
-                _final_padding: [0.0, 0.0, 0.0, 0.0],
-            };
-            
-            app_mut.queue.write_buffer(&app_mut.gradient_buffer, 0, bytemuck::cast_slice(&[gradient_data]));
         }
     });
 }
 
+// Switches to the Lenia automaton described by the JSON (a `LeniaModel`):
+// { "radius": 13, "peaks": [1], "mu": 0.15, "sigma": 0.015, "delta_time": 0.1, "soup": 64 }
+// and seeds its soup from `seed`
+#[wasm_bindgen]
+pub fn set_lenia_model(json: &str, seed: u32) -> Result<(), JsValue> {
+    let model = LeniaModel::from_json(json)?;
+    start_lenia(model, seed)
+}
+
+fn start_lenia(model: LeniaModel, seed: u32) -> Result<(), JsValue> {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            let mut lenia = Lenia::new(&app.sim, model)?;
+            lenia.randomize(&mut app.sim, seed as u64)?;
+            app.lenia = Some(lenia);
+            app.species = None;
+            update_render_params(&mut app);
+        }
+        Ok(())
+    })
+}
+
+// The built-in Lenia models as a JSON array of { name, label }
+#[wasm_bindgen]
+pub fn list_lenia_presets() -> Result<String, JsValue> {
+    let presets: Vec<_> = lenia::LENIA_PRESETS
+        .iter()
+        .map(|(name, label)| serde_json::json!({ "name": name, "label": label }))
+        .collect();
+    Ok(serde_json::to_string(&presets).map_err(|e| e.to_string())?)
+}
+
+#[wasm_bindgen]
+pub fn load_lenia_preset(name: &str, seed: u32) -> Result<(), JsValue> {
+    let model = lenia::lenia_preset(name).ok_or_else(|| format!("Unknown Lenia preset '{}'", name))?;
+    start_lenia(model, seed)
+}
+
+// Goes back to the reaction-diffusion model with a freshly seeded field
+#[wasm_bindgen]
+pub fn clear_lenia_model() {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            if app.lenia.take().is_some() {
+                app.init_field(&InitParams::center_seed());
+                update_render_params(&mut app);
+            }
+        }
+    });
+}
+
+// Loads a JSON or TOML scene (older versions are migrated), applies all of its
+// settings and re-seeds the field from its init recipe
+#[wasm_bindgen]
+pub fn load_scene(text: &str) -> Result<(), JsValue> {
+    let scene = scene::load_scene(text, SceneFormat::detect(text))?;
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            app.borrow_mut().apply_scene(&scene)?;
+        }
+        Ok(())
+    })
+}
+
+// Writes the current settings as a scene; `format` is "json" or "toml"
+#[wasm_bindgen]
+pub fn save_scene(format: &str) -> Result<String, JsValue> {
+    let format = SceneFormat::from_extension(format).ok_or_else(|| format!("Unknown scene format '{}'", format))?;
+    let scene = APP
+        .with(|a| a.borrow().as_ref().map(|app| app.borrow().to_scene()))
+        .ok_or("App not initialized")?;
+    Ok(scene::save_scene(&scene, format)?)
+}
+
+// Sets F and k to expressions of x, y (0..1 across the grid), t (steps since
+// seeding), r, theta, feed and kill, e.g. "0.03 + 0.02 * r". An empty string
+// goes back to the uniform slider value.
+#[wasm_bindgen]
+pub fn set_expressions(feed: &str, kill: &str) -> Result<(), JsValue> {
+    let expression = |text: &str| Some(text.trim()).filter(|t| !t.is_empty()).map(str::to_string);
+    let (feed, kill) = (expression(feed), expression(kill));
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            app.sim.set_expressions(feed.as_deref(), kill.as_deref())?;
+            app.sim.update_params();
+        }
+        Ok(())
+    })
+}
+
+// Sets the velocity field from JSON, e.g. {"type": "vortex", "x": 0.5, "y": 0.5,
+// "speed": 0.01, "radius": 0.2}, or {"type": "none"} to stop advecting
+#[wasm_bindgen]
+pub fn set_flow(json: &str) -> Result<(), JsValue> {
+    let flow: flow::Flow = serde_json::from_str(json).map_err(|e| format!("Invalid flow: {}", e))?;
+    flow.validate()?;
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            app.sim.flow = flow;
+            app.sim.update_params();
+        }
+        Ok(())
+    })
+}
+
+// Gray-Scott steps per dispatch, 1 to MAX_BLOCK_STEPS. Above 1, each workgroup
+// takes that many steps on its own block of cells before writing them back. Needs
+// the explicit solver with a fixed time step and no flow or F/k expressions;
+// otherwise the setting is refused.
+#[wasm_bindgen]
+pub fn set_block_steps(steps: u32) -> Result<(), JsValue> {
+    if !(1..=simulation::MAX_BLOCK_STEPS).contains(&steps) {
+        return Err(format!("Block steps must be 1 to {}", simulation::MAX_BLOCK_STEPS).into());
+    }
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            let previous = app.sim.block_steps;
+            app.sim.block_steps = steps;
+            if let Some(reason) = app.sim.blocking_fallback() {
+                app.sim.block_steps = previous;
+                return Err(JsValue::from(reason));
+            }
+            app.sim.update_params();
+        }
+        Ok(())
+    })
+}
+
+// How diffusion is integrated, as JSON: { "type": "explicit" }, { "type": "spectral" }
+// or { "type": "implicit", "theta": 0.5, "cycles": 2 }. The spectral solver needs
+// wrap boundaries, a power-of-two grid and Gray-Scott, the implicit one Gray-Scott;
+// if the simulation doesn't allow it the setting is refused.
+#[wasm_bindgen]
+pub fn set_solver(json: &str) -> Result<(), JsValue> {
+    let solver: solver::Solver = serde_json::from_str(json).map_err(|e| format!("Invalid solver: {}", e))?;
+    solver.validate()?;
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            let previous = app.sim.solver;
+            app.sim.solver = solver;
+            if let Some(reason) = app.sim.solver_fallback() {
+                app.sim.solver = previous;
+                return Err(JsValue::from(reason));
+            }
+            app.sim.update_params();
+        }
+        Ok(())
+    })
+}
+
+// Switches to error-controlled step sizes, as JSON:
+// { "tolerance": 0.002, "min_dt": 0.01, "max_dt": 2 }, all optional. Each step
+// then starts from the controller's dt instead of delta_time. Needs the explicit
+// solver and no flow; otherwise the setting is refused.
+#[wasm_bindgen]
+pub fn set_adaptive_time_step(json: &str) -> Result<(), JsValue> {
+    let adaptive = adaptive::AdaptiveTimeStep::from_json(json)?;
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            let previous = app.sim.adaptive;
+            app.sim.adaptive = Some(adaptive);
+            if let Some(reason) = app.sim.adaptive_fallback() {
+                app.sim.adaptive = previous;
+                return Err(JsValue::from(reason));
+            }
+            app.sim.update_params();
+        }
+        Ok(())
+    })
+}
+
+// Back to fixed steps of delta_time
+#[wasm_bindgen]
+pub fn clear_adaptive_time_step() {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            app.sim.adaptive = None;
+            app.sim.update_params();
+        }
+    });
+}
+
+// Size of the next step. With adaptive steps this is the controller's dt as of
+// its last read, a frame or so behind the GPU.
+#[wasm_bindgen]
+pub fn current_dt() -> f32 {
+    APP.with(|a| {
+        a.borrow()
+            .as_ref()
+            .map(|app| app.borrow().sim.current_dt())
+            .unwrap_or_default()
+    })
+}
+
+// Simulated time since the field was last replaced: the sum of the step sizes,
+// which with a fixed delta_time is just steps * delta_time
+#[wasm_bindgen]
+pub fn sim_time() -> f64 {
+    APP.with(|a| {
+        a.borrow()
+            .as_ref()
+            .map(|app| app.borrow().sim.sim_time())
+            .unwrap_or_default()
+    })
+}
+
+// Velocities for the "texture" flow: width * height interleaved (vx, vy) pairs
+#[wasm_bindgen]
+pub fn set_flow_texture(width: u32, height: u32, data: &[f32]) -> Result<(), JsValue> {
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            app.borrow_mut().sim.set_flow_texture(width, height, data)?;
+        }
+        Ok(())
+    })
+}
+
+fn set_equation(equation: Equation) -> Result<(), JsValue> {
+    equation.validate()?;
+    APP.with(|a| {
+        if let Some(app) = a.borrow().as_ref() {
+            let mut app = app.borrow_mut();
+            app.sim.equation = equation;
+            app.sim.update_params();
+            update_render_params(&mut app);
+        }
+    });
+    Ok(())
+}
+
+// Back to Gray-Scott, keeping the current field
+#[wasm_bindgen]
+pub fn set_gray_scott() -> Result<(), JsValue> {
+    set_equation(Equation::GrayScott)
+}
+
+// Switches to the complex Ginzburg-Landau equation on A = U + iV. The U
+// diffusion rate sets the coupling D.
+#[wasm_bindgen]
+pub fn set_ginzburg_landau(alpha: f32, beta: f32) -> Result<(), JsValue> {
+    set_equation(Equation::GinzburgLandau { alpha, beta })
+}
+
+// Switches to the Swift-Hohenberg equation on U. It needs delta_time below
+// about 0.03; reset() seeds it with noise.
+#[wasm_bindgen]
+pub fn set_swift_hohenberg(r: f32, q0: f32, g: f32) -> Result<(), JsValue> {
+    set_equation(Equation::SwiftHohenberg { r, q0, g })
+}
+
 #[wasm_bindgen]
 pub fn set_map_mode(enabled: bool) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app_mut = app.borrow_mut();
-            app_mut.map_mode = enabled;
-            app_mut.update_params();
+            app_mut.sim.map_mode = enabled;
+            app_mut.sim.update_params();
         }
     });
 }
@@ -1199,26 +1775,27 @@ pub fn set_emboss(enabled: bool) {
 }
 
 fn update_render_params(app: &mut GrayScottApp) {
-    #[repr(C)]
-    #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-    struct RenderParams {
-        color_palette: u32,
-        emboss_enabled: u32,
-        boundary_mode: u32,
-        _padding: u32,
-        zoom: f32,
-        pan_x: f32,
-        pan_y: f32,
-    }
-    
+    let perf = app.perf.stats();
     let render_params = RenderParams {
         color_palette: app.color_palette,
         emboss_enabled: if app.emboss_enabled { 1 } else { 0 },
-        boundary_mode: app.boundary_mode,
-        _padding: 0,
+        boundary_mode: app.sim.boundary_mode,
+        // Species views and Lenia states are both written to V
+        field_view: if app.species.is_some() || app.lenia.is_some() {
+            equation::FIELD_VIEW_V
+        } else {
+            app.sim.equation.field_view()
+        },
         zoom: app.zoom,
         pan_x: app.pan_x,
         pan_y: app.pan_y,
+        perf_overlay: app.perf_overlay as u32,
+        perf_ms: [
+            perf.steps_ms.unwrap_or(0.0) as f32,
+            perf.paint_ms.unwrap_or(0.0) as f32,
+            perf.render_ms.unwrap_or(0.0) as f32,
+            perf.ms_per_frame as f32,
+        ],
     };
     
     app.queue.write_buffer(
@@ -1245,8 +1822,8 @@ pub fn set_pan(x: f32, y: f32) {
         if let Some(app) = a.borrow().as_ref() {
             let mut app_mut = app.borrow_mut();
             // Clamp pan to reasonable range
-            app_mut.pan_x = x.max(-1.0).min(1.0);
-            app_mut.pan_y = y.max(-1.0).min(1.0);
+            app_mut.pan_x = x.clamp(-1.0, 1.0);
+            app_mut.pan_y = y.clamp(-1.0, 1.0);
             update_render_params(&mut app_mut);
         }
     });
@@ -1266,8 +1843,8 @@ pub fn set_delta_time(value: f32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app = app.borrow_mut();
-            app.delta_time = value;
-            app.update_params();
+            app.sim.delta_time = value;
+            app.sim.update_params();
         }
     });
 }
@@ -1277,8 +1854,8 @@ pub fn set_diffusion_u(value: f32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app = app.borrow_mut();
-            app.diffuse_u = value;
-            app.update_params();
+            app.sim.diffuse_u = value;
+            app.sim.update_params();
         }
     });
 }
@@ -1288,8 +1865,8 @@ pub fn set_diffusion_v(value: f32) {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app = app.borrow_mut();
-            app.diffuse_v = value;
-            app.update_params();
+            app.sim.diffuse_v = value;
+            app.sim.update_params();
         }
     });
 }
@@ -1312,27 +1889,11 @@ pub fn clear_canvas() {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
             let mut app = app.borrow_mut();
-            let mut init_data = vec![0.0f32; (GRID_WIDTH * GRID_HEIGHT * 2) as usize];
-            for i in (0..init_data.len()).step_by(2) {
-                init_data[i] = 1.0;  // U = 1
-                init_data[i + 1] = 0.0;  // V = 0
+            if let Some(species) = &mut app.species {
+                species.clear();
+                return;
             }
-            
-            app.queue.write_texture(
-                app.texture_a.as_image_copy(),
-                bytemuck::cast_slice(&init_data),
-                wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(GRID_WIDTH * 8),
-                    rows_per_image: Some(GRID_HEIGHT),
-                },
-                wgpu::Extent3d {
-                    width: GRID_WIDTH,
-                    height: GRID_HEIGHT,
-                    depth_or_array_layers: 1,
-                },
-            );
-            app.current_src = true;
+            app.init_field(&InitParams::uniform());
         }
     });
 }
@@ -1341,52 +1902,23 @@ pub fn clear_canvas() {
 pub fn add_random_blobs() {
     APP.with(|a| {
         if let Some(app) = a.borrow().as_ref() {
-            let mut app = app.borrow_mut();
-            let mut init_data = vec![0.0f32; (GRID_WIDTH * GRID_HEIGHT * 2) as usize];
-            
-            // Fill with base state
-            for i in (0..init_data.len()).step_by(2) {
-                init_data[i] = 1.0;  // U = 1
-                init_data[i + 1] = 0.0;  // V = 0
-            }
-            
-            // Add random blobs
-            for _ in 0..15 {
-                let cx = (js_sys::Math::random() * GRID_WIDTH as f64) as u32;
-                let cy = (js_sys::Math::random() * GRID_HEIGHT as f64) as u32;
-                let radius = (js_sys::Math::random() * 30.0 + 10.0) as i32;
-                
-                for dy in -radius..=radius {
-                    for dx in -radius..=radius {
-                        let px = cx as i32 + dx;
-                        let py = cy as i32 + dy;
-                        
-                        if px >= 0 && px < GRID_WIDTH as i32 && py >= 0 && py < GRID_HEIGHT as i32 {
-                            let dist_sq = dx * dx + dy * dy;
-                            if dist_sq <= radius * radius {
-                                let idx = ((py as u32 * GRID_WIDTH + px as u32) * 2) as usize;
-                                init_data[idx + 1] = 1.0;  // V = 1 in blob
-                            }
-                        }
-                    }
-                }
+            // Species have no blobs, so they get a fresh random start instead
+            if let Some(species) = &mut app.borrow_mut().species {
+                species.randomize((js_sys::Math::random() * u32::MAX as f64) as u64);
+                return;
             }
-            
-            app.queue.write_texture(
-                app.texture_a.as_image_copy(),
-                bytemuck::cast_slice(&init_data),
-                wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(GRID_WIDTH * 8),
-                    rows_per_image: Some(GRID_HEIGHT),
-                },
-                wgpu::Extent3d {
-                    width: GRID_WIDTH,
-                    height: GRID_HEIGHT,
-                    depth_or_array_layers: 1,
-                },
-            );
-            app.current_src = true;
+
+            // Pick the blobs here; the GPU only needs their centers and radii
+            let blobs: Vec<(f32, f32, f32)> = (0..15)
+                .map(|_| {
+                    let cx = (js_sys::Math::random() * GRID_WIDTH as f64) as u32;
+                    let cy = (js_sys::Math::random() * GRID_HEIGHT as f64) as u32;
+                    let radius = (js_sys::Math::random() * 30.0 + 10.0) as i32;
+                    (cx as f32, cy as f32, radius as f32)
+                })
+                .collect();
+
+            app.borrow_mut().init_field(&InitParams::blobs(&blobs));
         }
     });
 }
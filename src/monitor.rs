@@ -0,0 +1,122 @@
+// Steady-state and extinction detection. The monitor watches the stats time
+// series (see stats.rs) and reports when the run settles down or dies out, so
+// the UI can react without reading the field back every frame.
+
+use crate::stats::FieldStats;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RunState {
+    Active,  // the field is still changing
+    Steady,  // the RMS change per step has stayed below the steady threshold
+    Extinct, // V has died out everywhere
+}
+
+impl RunState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RunState::Active => "active",
+            RunState::Steady => "steady",
+            RunState::Extinct => "extinct",
+        }
+    }
+}
+
+pub struct StateMonitor {
+    state: RunState,
+    quiet_samples: u32,
+
+    pub steady_threshold: f32,     // RMS change per cell per step
+    pub extinction_threshold: f32, // total V mass divided by the number of cells
+    pub steady_samples: u32,       // consecutive quiet samples before reporting Steady
+    pub auto_pause: bool,
+}
+
+impl Default for StateMonitor {
+    fn default() -> Self {
+        Self {
+            state: RunState::Active,
+            quiet_samples: 0,
+            steady_threshold: 1e-5,
+            extinction_threshold: 1e-4,
+            steady_samples: 3,
+            auto_pause: false,
+        }
+    }
+}
+
+impl StateMonitor {
+    pub fn state(&self) -> RunState {
+        self.state
+    }
+
+    // Forgets the history, e.g. after the field was re-seeded
+    pub fn reset(&mut self) {
+        self.state = RunState::Active;
+        self.quiet_samples = 0;
+    }
+
+    // Feeds one stats sample; returns the new state if it changed
+    pub fn update(&mut self, sample: &FieldStats) -> Option<RunState> {
+        if sample.change < self.steady_threshold {
+            self.quiet_samples = self.quiet_samples.saturating_add(1);
+        } else {
+            self.quiet_samples = 0;
+        }
+
+        let state = if sample.mean[1] < self.extinction_threshold {
+            RunState::Extinct
+        } else if self.quiet_samples >= self.steady_samples.max(1) {
+            RunState::Steady
+        } else {
+            RunState::Active
+        };
+
+        if state == self.state {
+            return None;
+        }
+        self.state = state;
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(mean_v: f32, change: f32) -> FieldStats {
+        let mut stats = FieldStats::default();
+        stats.mean = [0.5, mean_v];
+        stats.change = change;
+        stats
+    }
+
+    #[test]
+    fn steady_needs_consecutive_quiet_samples() {
+        let mut monitor = StateMonitor::default();
+
+        assert_eq!(monitor.update(&sample(0.2, 0.0)), None);
+        assert_eq!(monitor.update(&sample(0.2, 0.0)), None);
+        // A burst of activity restarts the count
+        assert_eq!(monitor.update(&sample(0.2, 1e-2)), None);
+        assert_eq!(monitor.update(&sample(0.2, 0.0)), None);
+        assert_eq!(monitor.update(&sample(0.2, 0.0)), None);
+        assert_eq!(monitor.update(&sample(0.2, 0.0)), Some(RunState::Steady));
+        assert_eq!(monitor.update(&sample(0.2, 0.0)), None);
+
+        assert_eq!(monitor.update(&sample(0.2, 1e-2)), Some(RunState::Active));
+    }
+
+    #[test]
+    fn extinction_wins_over_steady() {
+        let mut monitor = StateMonitor::default();
+
+        assert_eq!(monitor.update(&sample(1e-3, 1e-2)), None);
+        assert_eq!(monitor.update(&sample(0.0, 1e-3)), Some(RunState::Extinct));
+        for _ in 0..5 {
+            assert_eq!(monitor.update(&sample(0.0, 0.0)), None);
+        }
+
+        monitor.reset();
+        assert_eq!(monitor.state(), RunState::Active);
+    }
+}
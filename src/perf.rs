@@ -0,0 +1,339 @@
+// Frame timing for perf_stats() and the performance overlay. On devices with
+// TIMESTAMP_QUERY the steps, paint and render work is timed on the GPU with
+// timestamps written by empty marker passes around each section; elsewhere
+// only the CPU-side interval between frames is measured, which includes any
+// wait for vsync.
+
+use std::future::Future;
+
+use serde::Serialize;
+
+use crate::readback;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Section {
+    Steps = 0,
+    Paint = 1,
+    Render = 2,
+}
+
+pub const SECTION_COUNT: usize = 3;
+
+// Timestamps per batch: a begin and an end for each timed section, with room
+// for a few paints between frames
+const QUERY_COUNT: u32 = 32;
+
+// Weight of the newest frame in the smoothed values
+const SMOOTHING: f64 = 0.1;
+
+// A section that has written its begin timestamp
+#[derive(Copy, Clone, Debug)]
+pub struct Span {
+    section: Section,
+    begin: u32,
+}
+
+// Times of one batch of sections, as read back from the GPU
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GpuSample {
+    pub ms: [f64; SECTION_COUNT],
+    pub steps: u32,
+}
+
+struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    period_ns: f64,
+    // Slots written since the last resolve, and the sections they belong to
+    used: u32,
+    spans: Vec<(Section, u32)>,
+    steps: u32,
+    // Sections and steps of a resolved batch that hasn't been read yet
+    resolved: Option<(Vec<(Section, u32)>, u32)>,
+    reading: bool,
+}
+
+impl GpuTimer {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Perf Query Set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Perf Resolve Buffer"),
+            size: QUERY_COUNT as u64 * 8,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        GpuTimer {
+            query_set,
+            resolve_buffer,
+            period_ns: queue.get_timestamp_period() as f64,
+            used: 0,
+            spans: Vec::new(),
+            steps: 0,
+            resolved: None,
+            reading: false,
+        }
+    }
+
+    // Writes a timestamp into `slot` from an empty compute pass
+    fn mark(&self, encoder: &mut wgpu::CommandEncoder, slot: u32) {
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Timestamp Pass"),
+            timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: Some(slot),
+                end_of_pass_write_index: None,
+            }),
+        });
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PerfStats {
+    pub steps_per_second: f64,
+    pub ms_per_step: f64,       // GPU time of the steps, or the frame interval over its steps without timestamps
+    pub ms_per_frame: f64,      // interval between frames
+    pub gpu_timing: bool,
+    pub steps_ms: Option<f64>,  // GPU time per frame of each section, with timestamps
+    pub paint_ms: Option<f64>,
+    pub render_ms: Option<f64>,
+}
+
+pub struct PerfMonitor {
+    timer: Option<GpuTimer>,
+    last_frame_ms: Option<f64>,
+    frame_ms: Option<f64>,
+    steps_per_frame: f64,
+    gpu_ms: Option<[f64; SECTION_COUNT]>,
+    gpu_ms_per_step: Option<f64>,
+}
+
+impl PerfMonitor {
+    // Times sections on the GPU if the device was created with TIMESTAMP_QUERY
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let timer = device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+            .then(|| GpuTimer::new(device, queue));
+        PerfMonitor {
+            timer,
+            last_frame_ms: None,
+            frame_ms: None,
+            steps_per_frame: 0.0,
+            gpu_ms: None,
+            gpu_ms_per_step: None,
+        }
+    }
+
+    pub fn gpu_timing(&self) -> bool {
+        self.timer.is_some()
+    }
+
+    // Starts timing `section` in `encoder`. Returns None without timestamps,
+    // while the previous batch is being read or once the batch is full.
+    pub fn begin(&mut self, encoder: &mut wgpu::CommandEncoder, section: Section) -> Option<Span> {
+        let timer = self.timer.as_mut().filter(|t| !t.reading && t.resolved.is_none())?;
+        if timer.used + 2 > QUERY_COUNT {
+            return None;
+        }
+        let begin = timer.used;
+        timer.used += 2;
+        timer.mark(encoder, begin);
+        Some(Span { section, begin })
+    }
+
+    pub fn end(&mut self, encoder: &mut wgpu::CommandEncoder, span: Option<Span>) {
+        if let (Some(timer), Some(span)) = (self.timer.as_mut(), span) {
+            timer.mark(encoder, span.begin + 1);
+            timer.spans.push((span.section, span.begin));
+        }
+    }
+
+    // Counts the steps timed in the current batch, for the time per step
+    pub fn add_steps(&mut self, steps: u32) {
+        if let Some(timer) = &mut self.timer {
+            if !timer.reading && timer.resolved.is_none() {
+                timer.steps += steps;
+            }
+        }
+    }
+
+    // Ends the batch: resolves its timestamps at the end of `encoder`, to be
+    // read after it is submitted
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(timer) = self.timer.as_mut().filter(|t| t.used > 0 && !t.reading && t.resolved.is_none()) else {
+            return;
+        };
+        encoder.resolve_query_set(&timer.query_set, 0..timer.used, &timer.resolve_buffer, 0);
+        timer.resolved = Some((std::mem::take(&mut timer.spans), timer.steps));
+        timer.used = 0;
+        timer.steps = 0;
+    }
+
+    // Reads the resolved batch, if there is one. Pass the result to `finish_read`.
+    pub fn read(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Option<impl Future<Output = Result<GpuSample, String>> + 'static> {
+        let timer = self.timer.as_mut().filter(|t| !t.reading)?;
+        let (spans, steps) = timer.resolved.take()?;
+        timer.reading = true;
+
+        let device = device.clone();
+        let queue = queue.clone();
+        let resolve_buffer = timer.resolve_buffer.clone();
+        let period_ns = timer.period_ns;
+
+        Some(async move {
+            let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Perf Staging Buffer"),
+                size: resolve_buffer.size(),
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Perf Readback Encoder"),
+            });
+            encoder.copy_buffer_to_buffer(&resolve_buffer, 0, &staging_buffer, 0, resolve_buffer.size());
+            queue.submit(Some(encoder.finish()));
+
+            readback::map_buffer(&device, &staging_buffer).await?;
+
+            let mut sample = GpuSample { steps, ..Default::default() };
+            {
+                let mapped = staging_buffer.slice(..).get_mapped_range();
+                let timestamps: &[u64] = bytemuck::cast_slice(&mapped);
+                for (section, begin) in spans {
+                    let ticks = timestamps[begin as usize + 1].saturating_sub(timestamps[begin as usize]);
+                    sample.ms[section as usize] += ticks as f64 * period_ns / 1e6;
+                }
+            }
+            staging_buffer.unmap();
+
+            Ok(sample)
+        })
+    }
+
+    // Takes in a batch read by `read` and lets the next batch start
+    pub fn finish_read(&mut self, sample: GpuSample) {
+        if let Some(timer) = &mut self.timer {
+            timer.reading = false;
+        }
+        self.gpu_ms = Some(match self.gpu_ms {
+            Some(ms) => std::array::from_fn(|i| smooth(ms[i], sample.ms[i])),
+            None => sample.ms,
+        });
+        if sample.steps > 0 {
+            let ms_per_step = sample.ms[Section::Steps as usize] / sample.steps as f64;
+            self.gpu_ms_per_step = Some(self.gpu_ms_per_step.map_or(ms_per_step, |ms| smooth(ms, ms_per_step)));
+        }
+    }
+
+    // Drops a batch whose read failed
+    pub fn abandon_read(&mut self) {
+        if let Some(timer) = &mut self.timer {
+            timer.reading = false;
+        }
+    }
+
+    // Records a frame that took `steps` steps, at `now_ms` on a millisecond clock
+    pub fn frame(&mut self, now_ms: f64, steps: u32) {
+        if let Some(last) = self.last_frame_ms.replace(now_ms) {
+            let interval = now_ms - last;
+            self.frame_ms = Some(self.frame_ms.map_or(interval, |ms| smooth(ms, interval)));
+            self.steps_per_frame = smooth(self.steps_per_frame, steps as f64);
+        } else {
+            self.steps_per_frame = steps as f64;
+        }
+    }
+
+    // Forgets the frame interval, e.g. after a pause in rendering
+    pub fn restart(&mut self) {
+        self.last_frame_ms = None;
+    }
+
+    pub fn stats(&self) -> PerfStats {
+        let frame_ms = self.frame_ms.unwrap_or(0.0);
+        let steps_per_second = if frame_ms > 0.0 { self.steps_per_frame * 1000.0 / frame_ms } else { 0.0 };
+        let cpu_ms_per_step = if self.steps_per_frame > 0.0 { frame_ms / self.steps_per_frame } else { 0.0 };
+        let section = |section: Section| self.gpu_ms.map(|ms| ms[section as usize]);
+        PerfStats {
+            steps_per_second,
+            ms_per_step: self.gpu_ms_per_step.unwrap_or(cpu_ms_per_step),
+            ms_per_frame: frame_ms,
+            gpu_timing: self.gpu_timing(),
+            steps_ms: section(Section::Steps),
+            paint_ms: section(Section::Paint),
+            render_ms: section(Section::Render),
+        }
+    }
+}
+
+fn smooth(average: f64, value: f64) -> f64 {
+    average + (value - average) * SMOOTHING
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation;
+
+    #[test]
+    fn frame_intervals_give_cpu_stats() {
+        let Ok((_, device, queue)) = pollster::block_on(simulation::request_headless_device()) else { return };
+        let mut perf = PerfMonitor::new(&device, &queue);
+        perf.timer = None;
+
+        assert_eq!(perf.stats().steps_per_second, 0.0);
+        for frame in 0..50 {
+            perf.frame(frame as f64 * 20.0, 8);
+        }
+        let stats = perf.stats();
+        assert!((stats.ms_per_frame - 20.0).abs() < 1e-9);
+        assert!((stats.ms_per_step - 2.5).abs() < 1e-9);
+        assert!((stats.steps_per_second - 400.0).abs() < 1e-6);
+        assert!(!stats.gpu_timing);
+        assert_eq!(stats.steps_ms, None);
+    }
+
+    #[test]
+    fn timestamps_time_each_section() {
+        let Some(mut sim) = simulation::headless_simulation(128, 128) else { return };
+        if !sim.device().features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            eprintln!("Skipping timestamp test: no TIMESTAMP_QUERY");
+            return;
+        }
+        let mut perf = PerfMonitor::new(sim.device(), sim.queue());
+        let device = sim.device().clone();
+        let queue = sim.queue().clone();
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        let span = perf.begin(&mut encoder, Section::Steps);
+        sim.encode_steps(&mut encoder, 20);
+        perf.end(&mut encoder, span);
+        perf.add_steps(20);
+        perf.resolve(&mut encoder);
+        // The batch is closed until it has been read
+        assert!(perf.begin(&mut encoder, Section::Render).is_none());
+        queue.submit(Some(encoder.finish()));
+
+        let sample = pollster::block_on(perf.read(&device, &queue).expect("a resolved batch")).unwrap();
+        assert_eq!(sample.steps, 20);
+        assert!(sample.ms[Section::Steps as usize] > 0.0, "{:?}", sample);
+        assert_eq!(sample.ms[Section::Paint as usize], 0.0);
+        assert!(perf.read(&device, &queue).is_none());
+
+        perf.finish_read(sample);
+        let stats = perf.stats();
+        assert!(stats.gpu_timing);
+        assert_eq!(stats.steps_ms, Some(sample.ms[0]));
+        assert!((stats.ms_per_step - sample.ms[0] / 20.0).abs() < 1e-12);
+        let mut encoder = device.create_command_encoder(&Default::default());
+        assert!(perf.begin(&mut encoder, Section::Paint).is_some());
+    }
+}
@@ -0,0 +1,212 @@
+// Built-in library of Gray-Scott presets and gradient palettes, shared by the
+// web UI, rd-sim and the tests so the tables only live here.
+
+use serde::Serialize;
+
+use crate::equation::Equation;
+use crate::scene::{ColorStop, InitRecipe, ModelParams, Scene};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Preset {
+    pub name: &'static str,  // lookup key
+    pub label: &'static str, // display name
+    pub feed: f32,
+    pub kill: f32,
+    // Recommended settings that bring out the pattern
+    pub kernel_type: u32,
+    pub delta_time: f32,
+    pub seed: u64,
+    pub blobs: u32, // random starting blobs; 0 seeds the centre square (Gray-Scott only)
+    pub palette: &'static str,
+    pub equation: Equation,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub stops: &'static [(f32, [f32; 3])],
+}
+
+// 0xRRGGBB to linear 0..1 channels, as the gradient editor does
+const fn hex(rgb: u32) -> [f32; 3] {
+    [
+        ((rgb >> 16) & 0xff) as f32 / 255.0,
+        ((rgb >> 8) & 0xff) as f32 / 255.0,
+        (rgb & 0xff) as f32 / 255.0,
+    ]
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset { name: "coral", label: "Coral", feed: 0.0545, kill: 0.062, kernel_type: 0, delta_time: 1.0, seed: 1, blobs: 0, palette: "rainbow", equation: Equation::GrayScott },
+    Preset { name: "bacteria", label: "Bacteria", feed: 0.014, kill: 0.054, kernel_type: 0, delta_time: 1.0, seed: 2, blobs: 12, palette: "olivegarden", equation: Equation::GrayScott },
+    Preset { name: "mitosis", label: "Mitosis", feed: 0.0367, kill: 0.0649, kernel_type: 0, delta_time: 1.0, seed: 3, blobs: 8, palette: "globs", equation: Equation::GrayScott },
+    Preset { name: "fingerprint", label: "Fingerprint", feed: 0.037, kill: 0.060, kernel_type: 0, delta_time: 1.0, seed: 4, blobs: 16, palette: "ink", equation: Equation::GrayScott },
+    Preset { name: "spirals", label: "Spirals", feed: 0.014, kill: 0.047, kernel_type: 3, delta_time: 1.0, seed: 5, blobs: 10, palette: "rainbow", equation: Equation::GrayScott },
+    Preset { name: "unstable", label: "Unstable", feed: 0.026, kill: 0.051, kernel_type: 0, delta_time: 1.0, seed: 6, blobs: 12, palette: "pastel", equation: Equation::GrayScott },
+    Preset { name: "waves", label: "Waves", feed: 0.006, kill: 0.031, kernel_type: 0, delta_time: 1.0, seed: 7, blobs: 6, palette: "ink", equation: Equation::GrayScott },
+    Preset { name: "worms", label: "Worms", feed: 0.078, kill: 0.061, kernel_type: 0, delta_time: 1.0, seed: 8, blobs: 16, palette: "earth", equation: Equation::GrayScott },
+    Preset { name: "zebrafish", label: "Zebrafish", feed: 0.016, kill: 0.055, kernel_type: 0, delta_time: 1.0, seed: 9, blobs: 12, palette: "threshold", equation: Equation::GrayScott },
+    Preset { name: "solitons", label: "Solitons", feed: 0.030, kill: 0.060, kernel_type: 0, delta_time: 1.0, seed: 10, blobs: 8, palette: "globs", equation: Equation::GrayScott },
+    Preset { name: "maze", label: "Maze", feed: 0.029, kill: 0.057, kernel_type: 0, delta_time: 1.0, seed: 11, blobs: 16, palette: "threshold", equation: Equation::GrayScott },
+    // Gliders that need a smaller step to keep their shape
+    Preset { name: "u_skate", label: "U-Skate", feed: 0.062, kill: 0.0609, kernel_type: 0, delta_time: 0.5, seed: 12, blobs: 4, palette: "ink", equation: Equation::GrayScott },
+    // Other equations, grown from noise; feed and kill are unused
+    Preset { name: "cgl_spirals", label: "Ginzburg-Landau Spirals", feed: 0.0, kill: 0.0, kernel_type: 0, delta_time: 0.1, seed: 13, blobs: 0, palette: "rainbow", equation: Equation::GinzburgLandau { alpha: 0.0, beta: 1.0 } },
+    Preset { name: "cgl_turbulence", label: "Ginzburg-Landau Turbulence", feed: 0.0, kill: 0.0, kernel_type: 0, delta_time: 0.1, seed: 14, blobs: 0, palette: "pastel", equation: Equation::GinzburgLandau { alpha: 2.0, beta: -1.0 } },
+    Preset { name: "sh_stripes", label: "Swift-Hohenberg Stripes", feed: 0.0, kill: 0.0, kernel_type: 0, delta_time: 0.02, seed: 15, blobs: 0, palette: "ink", equation: Equation::SwiftHohenberg { r: 0.3, q0: 0.75, g: 0.0 } },
+    Preset { name: "sh_hexagons", label: "Swift-Hohenberg Hexagons", feed: 0.0, kill: 0.0, kernel_type: 0, delta_time: 0.02, seed: 16, blobs: 0, palette: "globs", equation: Equation::SwiftHohenberg { r: 0.05, q0: 0.75, g: 1.0 } },
+];
+
+pub const PALETTES: &[Palette] = &[
+    Palette {
+        name: "rainbow",
+        label: "Rainbow",
+        stops: &[
+            (0.0, hex(0x330033)),
+            (0.1, hex(0x8000ff)),
+            (0.2, hex(0x0080ff)),
+            (0.3, hex(0x00ffcc)),
+            (0.45, hex(0xff4d00)),
+            (0.7, hex(0xff0000)),
+        ],
+    },
+    Palette {
+        name: "pastel",
+        label: "Pastel",
+        stops: &[
+            (0.0, hex(0xff9a9e)),
+            (0.1, hex(0xfad0c4)),
+            (0.3, hex(0xfbc2eb)),
+            (0.45, hex(0xa18cd1)),
+            (0.6, hex(0xfbc2eb)),
+        ],
+    },
+    Palette {
+        name: "ink",
+        label: "Ink",
+        stops: &[
+            (0.0, hex(0xf6eee3)),
+            (0.2, hex(0x0082ce)),
+            (0.4, hex(0x0e0955)),
+            (0.8, hex(0x000000)),
+        ],
+    },
+    Palette {
+        name: "threshold",
+        label: "Threshold",
+        stops: &[(0.2, hex(0xffffff)), (0.23, hex(0x000000))],
+    },
+    Palette {
+        name: "olivegarden",
+        label: "Olive Garden",
+        stops: &[
+            (0.0, hex(0x606c38)),
+            (0.1, hex(0x6b8e23)),
+            (0.2, hex(0xfefae0)),
+            (0.4, hex(0xdda15e)),
+            (0.6, hex(0xbc6c25)),
+        ],
+    },
+    Palette {
+        name: "globs",
+        label: "Globs",
+        stops: &[
+            (0.0, hex(0x8ecae6)),
+            (0.2, hex(0x219ebc)),
+            (0.33, hex(0x023047)),
+            (0.4, hex(0xffb703)),
+            (0.44, hex(0x023047)),
+            (0.8, hex(0x09e2b3)),
+        ],
+    },
+    Palette {
+        name: "earth",
+        label: "Earth",
+        stops: &[
+            (0.0, hex(0x6c584c)),
+            (0.1, hex(0xa98467)),
+            (0.3, hex(0xadc178)),
+            (0.4, hex(0xdde5b6)),
+            (0.8, hex(0xf0ead2)),
+        ],
+    },
+];
+
+pub fn list_presets() -> &'static [Preset] {
+    PRESETS
+}
+
+// Names are matched case-insensitively, with '-' and '_' interchangeable
+pub fn get_preset(name: &str) -> Option<&'static Preset> {
+    let name = name.replace('-', "_");
+    PRESETS.iter().find(|p| p.name.eq_ignore_ascii_case(&name))
+}
+
+pub fn list_palettes() -> &'static [Palette] {
+    PALETTES
+}
+
+pub fn get_palette(name: &str) -> Option<&'static Palette> {
+    PALETTES.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+impl Palette {
+    pub fn color_stops(&self) -> Vec<ColorStop> {
+        self.stops.iter().map(|&(position, color)| ColorStop { position, color }).collect()
+    }
+}
+
+impl Preset {
+    // A complete scene with the recommended settings and palette
+    pub fn scene(&self) -> Scene {
+        let defaults = Scene::default();
+        let init = match self.blobs {
+            _ if !self.equation.is_gray_scott() => InitRecipe::Noise { amplitude: 0.1 },
+            0 => InitRecipe::Center,
+            count => InitRecipe::RandomBlobs { count, min_radius: 4.0, max_radius: 10.0 },
+        };
+        Scene {
+            model: ModelParams {
+                feed_rate: self.feed,
+                kill_rate: self.kill,
+                kernel_type: self.kernel_type,
+                delta_time: self.delta_time,
+                equation: self.equation,
+                ..Default::default()
+            },
+            gradient: get_palette(self.palette).map_or(defaults.gradient.clone(), Palette::color_stops),
+            seed: self.seed,
+            init,
+            ..defaults
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_make_valid_scenes() {
+        for (i, preset) in PRESETS.iter().enumerate() {
+            assert!(PRESETS[..i].iter().all(|p| p.name != preset.name), "duplicate preset {}", preset.name);
+            assert!(get_palette(preset.palette).is_some(), "{} uses unknown palette {}", preset.name, preset.palette);
+            let scene = preset.scene();
+            scene.validate().unwrap();
+            assert_eq!(scene.gradient, get_palette(preset.palette).unwrap().color_stops());
+        }
+        for (i, palette) in PALETTES.iter().enumerate() {
+            assert!(PALETTES[..i].iter().all(|p| p.name != palette.name), "duplicate palette {}", palette.name);
+        }
+    }
+
+    #[test]
+    fn lookup_ignores_case_and_separators() {
+        assert_eq!(get_preset("U-Skate").unwrap().name, "u_skate");
+        assert_eq!(get_preset("MITOSIS").unwrap().feed, 0.0367);
+        assert!(get_preset("nope").is_none());
+        assert_eq!(get_palette("Ink").unwrap().stops.len(), 4);
+        assert_eq!(hex(0xff8000), [1.0, 128.0 / 255.0, 0.0]);
+    }
+}
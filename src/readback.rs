@@ -0,0 +1,185 @@
+// Copying simulation textures back to the host through a staging buffer.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+// Bytes per texel of a simulation texture in the given format
+pub fn texel_size(format: wgpu::TextureFormat) -> u32 {
+    format.block_copy_size(None).expect("simulation formats are uncompressed")
+}
+
+// Whether the channels of `format` are f16 rather than f32
+pub fn is_half_float(format: wgpu::TextureFormat) -> bool {
+    matches!(format, wgpu::TextureFormat::Rg16Float | wgpu::TextureFormat::Rgba16Float)
+}
+
+// Channel values as stored in a texture of `format`
+pub fn encode_channels(format: wgpu::TextureFormat, values: &[f32]) -> Vec<u8> {
+    if is_half_float(format) {
+        let halves: Vec<u16> = values.iter().map(|&value| half::f16::from_f32(value).to_bits()).collect();
+        bytemuck::cast_slice(&halves).to_vec()
+    } else {
+        bytemuck::cast_slice(values).to_vec()
+    }
+}
+
+// Channel values of texels copied out of a texture of `format`
+pub fn decode_channels(format: wgpu::TextureFormat, bytes: &[u8]) -> Vec<f32> {
+    if is_half_float(format) {
+        bytes
+            .chunks_exact(2)
+            .map(|bits| half::f16::from_bits(u16::from_le_bytes([bits[0], bits[1]])).to_f32())
+            .collect()
+    } else {
+        bytemuck::pod_collect_to_vec(bytes)
+    }
+}
+
+// Row pitch for a texture-to-buffer copy, padded up to wgpu's required alignment
+pub fn padded_bytes_per_row(width: u32, format: wgpu::TextureFormat) -> u32 {
+    let unpadded = width * texel_size(format);
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(align) * align
+}
+
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+// Resolves once `map_async` has called back
+struct MapFuture(Arc<Mutex<MapState>>);
+
+impl Future for MapFuture {
+    type Output = Result<(), wgpu::BufferAsyncError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+// Maps a whole buffer for reading. In the browser the callback fires from the
+// event loop; natively we block on the device until the copy has finished.
+pub async fn map_buffer(device: &wgpu::Device, buffer: &wgpu::Buffer) -> Result<(), String> {
+    let state = Arc::new(Mutex::new(MapState::default()));
+    let callback_state = state.clone();
+
+    buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+        let mut state = callback_state.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+
+    #[cfg(not(target_arch = "wasm32"))]
+    device
+        .poll(wgpu::PollType::wait_indefinitely())
+        .map_err(|e| format!("Failed to poll device: {:?}", e))?;
+    #[cfg(target_arch = "wasm32")]
+    let _ = device;
+
+    MapFuture(state)
+        .await
+        .map_err(|e| format!("Failed to map readback buffer: {:?}", e))
+}
+
+// Reads a `width` x `height` block of texels starting at (`x`, `y`) as
+// interleaved (U, V) pairs in row-major order, with the row padding and any
+// extra channels of the texture format stripped
+pub async fn read_texture_region(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<Vec<f32>, String> {
+    read_texels(device, queue, texture, x, y, width, height, 2).await
+}
+
+// Reads a whole texture with every channel of its format, row-major
+pub async fn read_texture_texels(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> Result<Vec<f32>, String> {
+    let channels = texture.format().components() as usize;
+    read_texels(device, queue, texture, 0, 0, texture.width(), texture.height(), channels).await
+}
+
+// Copies a block of texels, keeping the first `keep` channels of each
+#[allow(clippy::too_many_arguments)]
+async fn read_texels(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    keep: usize,
+) -> Result<Vec<f32>, String> {
+    let format = texture.format();
+    let padded_row = padded_bytes_per_row(width, format);
+    let unpadded_row = (width * texel_size(format)) as usize;
+    let channels = format.components() as usize;
+
+    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Staging Buffer"),
+        size: padded_row as u64 * height as u64,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback Encoder"),
+    });
+
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x, y, z: 0 },
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &staging_buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+
+    queue.submit(Some(encoder.finish()));
+
+    map_buffer(device, &staging_buffer).await?;
+
+    let mut values = Vec::with_capacity(width as usize * height as usize * keep);
+    {
+        let mapped = staging_buffer.slice(..).get_mapped_range();
+        for row in mapped.chunks(padded_row as usize) {
+            let texels = decode_channels(format, &row[..unpadded_row]);
+            for texel in texels.chunks(channels) {
+                values.extend_from_slice(&texel[..keep]);
+            }
+        }
+    }
+    staging_buffer.unmap();
+
+    Ok(values)
+}
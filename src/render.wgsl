@@ -35,10 +35,12 @@ struct RenderParams {
     color_palette: u32,
     emboss_enabled: u32,
     boundary_mode: u32,
-    _padding: u32,
+    field_view: u32,     // 0=V, 1=0.5 + 0.5 * U (Ginzburg-Landau, Swift-Hohenberg)
     zoom: f32,
     pan_x: f32,
     pan_y: f32,
+    perf_overlay: u32,
+    perf_ms: vec4<f32>,  // steps, paint and render GPU time and the frame interval
 }
 
 @group(0) @binding(2) var<uniform> render_params: RenderParams;
@@ -97,12 +99,58 @@ fn sample_gradient(t: f32) -> vec3<f32> {
     return gradient.stops[0].color.rgb;
 }
 
+// The value shown for a cell
+fn field_value(coord: vec2<i32>) -> f32 {
+    let uv = textureLoad(reaction_texture, coord, 0).rg;
+    if render_params.field_view == 1u {
+        return 0.5 + 0.5 * uv.r;
+    }
+    return uv.g;
+}
+
 // Color mapping for V channel
 fn value_to_color(v: f32) -> vec3<f32> {
 
     return sample_gradient(v);
 }
 
+// Performance overlay: one bar per perf_ms entry in the top-left corner, full
+// width at OVERLAY_FULL_MS, with ticks every 60 Hz frame budget
+const OVERLAY_FULL_MS: f32 = 50.0;
+const OVERLAY_ORIGIN: vec2<f32> = vec2<f32>(0.02, 0.02);
+const OVERLAY_SIZE: vec2<f32> = vec2<f32>(0.3, 0.1);
+
+fn overlay_color(row: u32) -> vec3<f32> {
+    switch row {
+        case 0u: { return vec3<f32>(1.0, 0.55, 0.1); }  // steps
+        case 1u: { return vec3<f32>(0.3, 0.85, 0.3); }  // paint
+        case 2u: { return vec3<f32>(0.3, 0.6, 1.0); }   // render
+        default: { return vec3<f32>(0.9, 0.9, 0.9); }   // frame interval
+    }
+}
+
+fn perf_overlay(screen: vec2<f32>, color: vec3<f32>) -> vec3<f32> {
+    let local = (screen - OVERLAY_ORIGIN) / OVERLAY_SIZE;
+    if any(local < vec2<f32>(0.0, 0.0)) || any(local >= vec2<f32>(1.0, 1.0)) {
+        return color;
+    }
+
+    let row = u32(local.y * 4.0);
+    let within = fract(local.y * 4.0);
+    let ms = local.x * OVERLAY_FULL_MS;
+    var shaded = color * 0.3;
+    if within > 0.2 && within < 0.8 && ms < render_params.perf_ms[row] {
+        shaded = overlay_color(row);
+    }
+
+    let budget = 1000.0 / 60.0;
+    let tick_width = OVERLAY_FULL_MS * 0.004;
+    if abs(ms - round(ms / budget) * budget) < tick_width && ms > tick_width {
+        shaded = vec3<f32>(1.0, 1.0, 1.0);
+    }
+    return shaded;
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // Apply zoom and pan to texture coordinates
@@ -146,7 +194,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
             var sample_val = 0.0;
             // If we're not out of bounds, sample the texture; otherwise, we just show black.
             if !out_of_bounds {
-                sample_val = textureLoad(reaction_texture, final_coord, 0).g;
+                sample_val = field_value(final_coord);
             }
             sum += sample_val * weight;
             weight_sum += weight;
@@ -205,10 +253,10 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
                 coord_down = clamp(coord_center + vec2<i32>(0, -1), vec2<i32>(0, 0), vec2<i32>(i32(tex_size.x) - 1, i32(tex_size.y) - 1));
             }
             
-            let val_right = textureLoad(reaction_texture, coord_right, 0).g;
-            let val_left = textureLoad(reaction_texture, coord_left, 0).g;
-            let val_up = textureLoad(reaction_texture, coord_up, 0).g;
-            let val_down = textureLoad(reaction_texture, coord_down, 0).g;
+            let val_right = field_value(coord_right);
+            let val_left = field_value(coord_left);
+            let val_up = field_value(coord_up);
+            let val_down = field_value(coord_down);
             
             // Calculate gradients with strong height amplification for liquid appearance
             let dx = (val_right - val_left) * 0.5;
@@ -238,6 +286,10 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
             color = color * lighting;
         }
     }
+
+    if render_params.perf_overlay != 0u {
+        color = perf_overlay(in.tex_coords, color);
+    }
     
     return vec4<f32>(color, 1.0);
 }
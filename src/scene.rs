@@ -0,0 +1,508 @@
+// Scene files: everything needed to reproduce a run (model parameters, the
+// seeded starting field and how it is displayed), stored as JSON or TOML so
+// native tools and the browser can share them.
+//
+// Every file carries a `version`. Older versions are migrated forward when
+// loaded, so scenes saved by earlier builds keep working; a file without a
+// version is treated as version 1.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::equation::Equation;
+use crate::expr::Expr;
+use crate::flow::Flow;
+use crate::image::DEFAULT_GRADIENT;
+use crate::simulation::{InitParams, Simulation, MAX_INIT_BLOBS};
+use crate::adaptive::AdaptiveTimeStep;
+use crate::solver::Solver;
+
+pub const SCENE_VERSION: u32 = 2;
+
+// Upper bound for steps_per_frame, well past anything that stays interactive
+pub const MAX_STEPS_PER_FRAME: u32 = 1024;
+
+const KERNEL_COUNT: u32 = 4;   // 0=default, 1=cross, 2=diagonal, 3=spiral
+const BOUNDARY_COUNT: u32 = 3; // 0=wrap, 1=clamp, 2=reflect
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SceneFormat {
+    Json,
+    Toml,
+}
+
+impl SceneFormat {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(SceneFormat::Json),
+            "toml" => Some(SceneFormat::Toml),
+            _ => None,
+        }
+    }
+
+    // JSON scenes are always objects; anything else is read as TOML
+    pub fn detect(text: &str) -> Self {
+        if text.trim_start().starts_with('{') {
+            SceneFormat::Json
+        } else {
+            SceneFormat::Toml
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InitRecipe {
+    Uniform,
+    Center,
+    // Reproducible blobs from the scene seed
+    RandomBlobs { count: u32, min_radius: f32, max_radius: f32 },
+    // Explicit discs as [x, y, radius] in grid cells
+    Blobs { blobs: Vec<[f32; 3]> },
+    // Small random U and V from the scene seed, for the non-Gray-Scott equations
+    Noise { amplitude: f32 },
+}
+
+impl InitRecipe {
+    pub fn init_params(&self, seed: u64, width: u32, height: u32) -> InitParams {
+        match self {
+            InitRecipe::Uniform => InitParams::uniform(),
+            InitRecipe::Center => InitParams::center_seed(),
+            InitRecipe::RandomBlobs { count, min_radius, max_radius } => {
+                InitParams::random_blobs(seed, *count as usize, width, height, *min_radius, *max_radius)
+            }
+            InitRecipe::Blobs { blobs } => {
+                let blobs: Vec<(f32, f32, f32)> = blobs.iter().map(|&[x, y, r]| (x, y, r)).collect();
+                InitParams::blobs(&blobs)
+            }
+            InitRecipe::Noise { amplitude } => InitParams::noise(seed, *amplitude),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            InitRecipe::Uniform | InitRecipe::Center => Ok(()),
+            InitRecipe::RandomBlobs { count, min_radius, max_radius } => {
+                if *count as usize > MAX_INIT_BLOBS {
+                    return Err(format!("init.count must be at most {}", MAX_INIT_BLOBS));
+                }
+                if !(*min_radius > 0.0 && min_radius <= max_radius && max_radius.is_finite()) {
+                    return Err("init radii must satisfy 0 < min_radius <= max_radius".to_string());
+                }
+                Ok(())
+            }
+            InitRecipe::Blobs { blobs } => {
+                if blobs.len() > MAX_INIT_BLOBS {
+                    return Err(format!("init.blobs can hold at most {} discs", MAX_INIT_BLOBS));
+                }
+                if blobs.iter().flatten().any(|v| !v.is_finite()) || blobs.iter().any(|b| b[2] <= 0.0) {
+                    return Err("init.blobs must be finite [x, y, radius] with radius > 0".to_string());
+                }
+                Ok(())
+            }
+            InitRecipe::Noise { amplitude } => {
+                if !(amplitude.is_finite() && *amplitude >= 0.0) {
+                    return Err("init.amplitude must be a non-negative number".to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelParams {
+    pub feed_rate: f32,
+    pub kill_rate: f32,
+    pub diffuse_u: f32,
+    pub diffuse_v: f32,
+    pub delta_time: f32,
+    pub noise_strength: f32,
+    pub kernel_type: u32,
+    pub boundary_mode: u32,
+    // Optional expressions of (x, y, t) overriding F and k per cell (see expr.rs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed_expression: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kill_expression: Option<String>,
+    // The PDE being integrated (see equation.rs)
+    #[serde(skip_serializing_if = "Equation::is_gray_scott")]
+    pub equation: Equation,
+    // Velocity field advecting U and V before each step (see flow.rs)
+    #[serde(skip_serializing_if = "Flow::is_none")]
+    pub flow: Flow,
+    // How diffusion is integrated (see solver.rs)
+    #[serde(skip_serializing_if = "Solver::is_explicit")]
+    pub solver: Solver,
+    // Error-controlled step sizes instead of a fixed delta_time (see adaptive.rs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adaptive: Option<AdaptiveTimeStep>,
+}
+
+impl Default for ModelParams {
+    fn default() -> Self {
+        // Matches a freshly created Simulation
+        Self {
+            feed_rate: 0.055,
+            kill_rate: 0.062,
+            diffuse_u: 1.0,
+            diffuse_v: 0.5,
+            delta_time: 1.0,
+            noise_strength: 0.0,
+            kernel_type: 0,
+            boundary_mode: 0,
+            feed_expression: None,
+            kill_expression: None,
+            equation: Equation::GrayScott,
+            flow: Flow::None,
+            solver: Solver::Explicit,
+            adaptive: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColorStop {
+    pub position: f32,
+    pub color: [f32; 3],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewParams {
+    pub zoom: f32,
+    pub pan_x: f32,
+    pub pan_y: f32,
+}
+
+impl Default for ViewParams {
+    fn default() -> Self {
+        Self { zoom: 1.0, pan_x: 0.0, pan_y: 0.0 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scene {
+    pub version: u32,
+    pub model: ModelParams,
+    pub steps_per_frame: u32,
+    pub gradient: Vec<ColorStop>,
+    pub emboss: bool,
+    pub view: ViewParams,
+    pub seed: u64,
+    pub init: InitRecipe,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        // Matches the app's startup state
+        Self {
+            version: SCENE_VERSION,
+            model: ModelParams::default(),
+            steps_per_frame: 8,
+            gradient: DEFAULT_GRADIENT
+                .iter()
+                .map(|&(position, color)| ColorStop { position, color })
+                .collect(),
+            emboss: true,
+            view: ViewParams::default(),
+            seed: 0,
+            init: InitRecipe::Center,
+        }
+    }
+}
+
+// Shared with timelines, which animate gradients too
+pub(crate) fn validate_gradient(gradient: &[ColorStop]) -> Result<(), String> {
+    if gradient.is_empty() || gradient.len() > crate::MAX_GRADIENT_STOPS {
+        return Err(format!("gradient needs 1 to {} stops", crate::MAX_GRADIENT_STOPS));
+    }
+    if gradient.windows(2).any(|pair| pair[1].position < pair[0].position) {
+        return Err("gradient stops must be sorted by position".to_string());
+    }
+    for stop in gradient {
+        let in_unit = |v: f32| (0.0..=1.0).contains(&v);
+        if !in_unit(stop.position) || !stop.color.iter().all(|&c| in_unit(c)) {
+            return Err("gradient positions and colors must be within 0..1".to_string());
+        }
+    }
+    Ok(())
+}
+
+// Version 1 (the first rd-sim scenes) kept the model parameters at the top
+// level and had no display settings
+fn migrate_v1(scene: &mut Map<String, Value>) {
+    const MODEL_KEYS: [&str; 8] = [
+        "feed_rate", "kill_rate", "diffuse_u", "diffuse_v",
+        "delta_time", "noise_strength", "kernel_type", "boundary_mode",
+    ];
+    let mut model = match scene.remove("model") {
+        Some(Value::Object(model)) => model,
+        _ => Map::new(), // hand-written files may already use the new layout
+    };
+    for key in MODEL_KEYS {
+        if let Some(value) = scene.remove(key) {
+            model.insert(key.to_string(), value);
+        }
+    }
+    scene.insert("model".to_string(), Value::Object(model));
+}
+
+// Brings a parsed scene of any supported version up to SCENE_VERSION
+fn migrate(mut value: Value) -> Result<Value, String> {
+    let scene = value.as_object_mut().ok_or("Invalid scene: expected a table of settings")?;
+    let version = match scene.get("version") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or("Invalid scene: version must be a positive integer")?,
+    };
+    if version == 0 || version > SCENE_VERSION {
+        return Err(format!(
+            "Unsupported scene version {} (this build reads versions 1 to {})",
+            version, SCENE_VERSION
+        ));
+    }
+
+    if version < 2 {
+        migrate_v1(scene);
+    }
+    scene.insert("version".to_string(), Value::from(SCENE_VERSION));
+    Ok(value)
+}
+
+pub fn load_scene(text: &str, format: SceneFormat) -> Result<Scene, String> {
+    let value: Value = match format {
+        SceneFormat::Json => serde_json::from_str(text).map_err(|e| format!("Invalid scene: {}", e))?,
+        SceneFormat::Toml => toml::from_str(text).map_err(|e| format!("Invalid scene: {}", e))?,
+    };
+    let scene: Scene = serde_json::from_value(migrate(value)?).map_err(|e| format!("Invalid scene: {}", e))?;
+    scene.validate()?;
+    Ok(scene)
+}
+
+pub fn save_scene(scene: &Scene, format: SceneFormat) -> Result<String, String> {
+    scene.validate()?;
+    match format {
+        SceneFormat::Json => serde_json::to_string_pretty(scene).map_err(|e| format!("Failed to write scene: {}", e)),
+        SceneFormat::Toml => toml::to_string_pretty(scene).map_err(|e| format!("Failed to write scene: {}", e)),
+    }
+}
+
+// Reads a .json or .toml scene file
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_scene_file(path: &std::path::Path) -> Result<Scene, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let format = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(SceneFormat::from_extension)
+        .unwrap_or_else(|| SceneFormat::detect(&text));
+    load_scene(&text, format).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+impl Scene {
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        load_scene(text, SceneFormat::Json)
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        save_scene(self, SceneFormat::Json)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        load_scene(text, SceneFormat::Toml)
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        save_scene(self, SceneFormat::Toml)
+    }
+
+    // Checks that every setting is in the range the simulation and renderer accept
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != SCENE_VERSION {
+            return Err(format!("Scene version must be {}", SCENE_VERSION));
+        }
+
+        let m = &self.model;
+        let rates = [
+            ("feed_rate", m.feed_rate),
+            ("kill_rate", m.kill_rate),
+            ("diffuse_u", m.diffuse_u),
+            ("diffuse_v", m.diffuse_v),
+            ("noise_strength", m.noise_strength),
+        ];
+        for (name, value) in rates {
+            if !(value.is_finite() && value >= 0.0) {
+                return Err(format!("model.{} must be a non-negative number", name));
+            }
+        }
+        if !(m.delta_time.is_finite() && m.delta_time > 0.0) {
+            return Err("model.delta_time must be positive".to_string());
+        }
+        if m.kernel_type >= KERNEL_COUNT {
+            return Err(format!("model.kernel_type must be below {}", KERNEL_COUNT));
+        }
+        if m.boundary_mode >= BOUNDARY_COUNT {
+            return Err(format!("model.boundary_mode must be below {}", BOUNDARY_COUNT));
+        }
+        for (name, expression) in [("feed_expression", &m.feed_expression), ("kill_expression", &m.kill_expression)] {
+            if let Some(expression) = expression {
+                Expr::parse(expression).map_err(|e| format!("model.{}: {}", name, e))?;
+            }
+        }
+        m.equation.validate().map_err(|e| format!("model.equation: {}", e))?;
+        m.flow.validate().map_err(|e| format!("model.flow: {}", e))?;
+        m.solver.validate().map_err(|e| format!("model.solver: {}", e))?;
+        if let Some(adaptive) = &m.adaptive {
+            adaptive.validate().map_err(|e| format!("model.adaptive: {}", e))?;
+        }
+
+        if self.steps_per_frame == 0 || self.steps_per_frame > MAX_STEPS_PER_FRAME {
+            return Err(format!("steps_per_frame must be between 1 and {}", MAX_STEPS_PER_FRAME));
+        }
+
+        validate_gradient(&self.gradient)?;
+
+        let v = &self.view;
+        if !(v.zoom.is_finite() && v.zoom >= 1.0) {
+            return Err("view.zoom must be at least 1".to_string());
+        }
+        if !(-1.0..=1.0).contains(&v.pan_x) || !(-1.0..=1.0).contains(&v.pan_y) {
+            return Err("view.pan_x and view.pan_y must be within -1..1".to_string());
+        }
+
+        self.init.validate()
+    }
+
+    // Gradient stops in the form the image helpers take
+    pub fn gradient_stops(&self) -> Vec<(f32, [f32; 3])> {
+        self.gradient.iter().map(|s| (s.position, s.color)).collect()
+    }
+
+    // Copies the model parameters into the simulation. Only fails for
+    // expressions that don't parse, which validate() rules out.
+    pub fn apply(&self, sim: &mut Simulation) -> Result<(), String> {
+        let m = &self.model;
+        sim.feed_rate = m.feed_rate;
+        sim.kill_rate = m.kill_rate;
+        sim.diffuse_u = m.diffuse_u;
+        sim.diffuse_v = m.diffuse_v;
+        sim.delta_time = m.delta_time;
+        sim.noise_strength = m.noise_strength;
+        sim.kernel_type = m.kernel_type;
+        sim.boundary_mode = m.boundary_mode;
+        sim.equation = m.equation;
+        sim.flow = m.flow;
+        sim.solver = m.solver;
+        sim.adaptive = m.adaptive;
+        sim.set_expressions(m.feed_expression.as_deref(), m.kill_expression.as_deref())?;
+        sim.update_params();
+        Ok(())
+    }
+
+    // Applies the parameters and replaces the field with the scene's starting state
+    pub fn start(&self, sim: &mut Simulation) -> Result<(), String> {
+        self.apply(sim)?;
+        sim.init_field(&self.init.init_params(self.seed, sim.width(), sim.height()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_round_trips_and_fills_defaults() {
+        let scene = Scene {
+            model: ModelParams { feed_rate: 0.03, ..Default::default() },
+            init: InitRecipe::RandomBlobs { count: 5, min_radius: 2.0, max_radius: 4.0 },
+            ..Default::default()
+        };
+        assert_eq!(Scene::from_json(&scene.to_json().unwrap()).unwrap(), scene);
+
+        let partial = Scene::from_json(r#"{ "version": 2, "model": { "kill_rate": 0.06 }, "init": { "type": "uniform" } }"#).unwrap();
+        assert_eq!(partial.model.kill_rate, 0.06);
+        assert_eq!(partial.model.feed_rate, Scene::default().model.feed_rate);
+        assert_eq!(partial.init, InitRecipe::Uniform);
+
+        assert!(Scene::from_json(r#"{ "init": { "type": "spiral" } }"#).is_err());
+    }
+
+    #[test]
+    fn toml_round_trips() {
+        let scene = Scene {
+            model: ModelParams {
+                kernel_type: 3,
+                boundary_mode: 2,
+                kill_expression: Some("kill + 0.002 * sin(theta + t / 1000)".to_string()),
+                solver: Solver::Spectral,
+                adaptive: Some(AdaptiveTimeStep::default()),
+                ..Default::default()
+            },
+            gradient: vec![
+                ColorStop { position: 0.0, color: [1.0, 1.0, 1.0] },
+                ColorStop { position: 0.25, color: [0.0, 0.0, 0.0] },
+            ],
+            emboss: false,
+            view: ViewParams { zoom: 2.0, pan_x: 0.25, pan_y: -0.5 },
+            seed: 42,
+            init: InitRecipe::Blobs { blobs: vec![[10.0, 12.0, 4.0]] },
+            ..Default::default()
+        };
+        let text = scene.to_toml().unwrap();
+        assert_eq!(Scene::from_toml(&text).unwrap(), scene);
+        assert_eq!(SceneFormat::detect(&text), SceneFormat::Toml);
+        assert_eq!(load_scene(&text, SceneFormat::detect(&text)).unwrap(), scene);
+    }
+
+    #[test]
+    fn version_1_scenes_migrate_forward() {
+        // As written by the first rd-sim release
+        let v1 = r#"{ "feed_rate": 0.03, "kill_rate": 0.06, "kernel_type": 1, "seed": 7, "init": { "type": "uniform" } }"#;
+        let scene = Scene::from_json(v1).unwrap();
+        assert_eq!(scene.version, SCENE_VERSION);
+        assert_eq!(scene.model.feed_rate, 0.03);
+        assert_eq!(scene.model.kill_rate, 0.06);
+        assert_eq!(scene.model.kernel_type, 1);
+        assert_eq!(scene.model.diffuse_u, ModelParams::default().diffuse_u);
+        assert_eq!(scene.seed, 7);
+        assert_eq!(scene.steps_per_frame, Scene::default().steps_per_frame);
+
+        let v1_toml = "feed_rate = 0.04\n[init]\ntype = \"center\"\n";
+        assert_eq!(Scene::from_toml(v1_toml).unwrap().model.feed_rate, 0.04);
+
+        assert!(Scene::from_json(r#"{ "version": 99 }"#).is_err());
+        assert!(Scene::from_json(r#"{ "version": 0 }"#).is_err());
+    }
+
+    #[test]
+    fn out_of_range_settings_are_rejected() {
+        let invalid = [
+            r#"{ "version": 2, "model": { "delta_time": 0.0 } }"#,
+            r#"{ "version": 2, "model": { "kernel_type": 4 } }"#,
+            r#"{ "version": 2, "model": { "feed_rate": -0.1 } }"#,
+            r#"{ "version": 2, "model": { "feed_rat": 0.03 } }"#,
+            r#"{ "version": 2, "model": { "feed_expression": "0.03 +" } }"#,
+            r#"{ "version": 2, "model": { "kill_expression": "kill + 1/0" } }"#,
+            r#"{ "version": 2, "steps_per_frame": 0 }"#,
+            r#"{ "version": 2, "gradient": [] }"#,
+            r#"{ "version": 2, "gradient": [{ "position": 0.5, "color": [0, 0, 0] }, { "position": 0.2, "color": [1, 1, 1] }] }"#,
+            r#"{ "version": 2, "gradient": [{ "position": 0.0, "color": [2, 0, 0] }] }"#,
+            r#"{ "version": 2, "view": { "zoom": 0.5 } }"#,
+            r#"{ "version": 2, "init": { "type": "random_blobs", "count": 4, "min_radius": 5.0, "max_radius": 2.0 } }"#,
+            r#"{ "version": 2, "init": { "type": "random_blobs", "count": 64, "min_radius": 1.0, "max_radius": 2.0 } }"#,
+        ];
+        for text in invalid {
+            assert!(Scene::from_json(text).is_err(), "accepted {}", text);
+        }
+
+        let scene = Scene { steps_per_frame: 0, ..Default::default() };
+        assert!(scene.to_json().is_err());
+    }
+}
@@ -0,0 +1,2147 @@
+// Headless Gray-Scott simulation state: the ping-pong textures and the compute
+// pipelines that read and write them. Nothing in here knows about the canvas,
+// so the same code runs in the browser, in tests and on native adapters.
+//
+// State transitions are explicit:
+// - `current_texture()` always holds the latest field.
+// - Passes that produce a new field (`step`, `paint`) write into `next_texture()`
+//   and then `swap()`.
+// - Passes that replace the field outright (`init_field`, `write_field`) write
+//   into `current_texture()` and leave the ping-pong order alone.
+
+use std::future::Future;
+
+use wgpu::util::DeviceExt;
+
+use crate::equation::Equation;
+use crate::expr::Expr;
+use crate::flow::{Flow, FlowUniform};
+use crate::readback;
+use crate::solver::Solver;
+use crate::adaptive::{AdaptiveStepper, AdaptiveTimeStep, TimeStepState};
+use crate::implicit::{self, ImplicitSolver};
+use crate::spectral::{self, SpectralSolver};
+
+pub const MAX_INIT_BLOBS: usize = 32;
+
+// Most steps per dispatch with temporal blocking; compute.wgsl's tile fits the
+// halo of this many steps of the radius-2 spiral kernel
+pub const MAX_BLOCK_STEPS: u32 = 4;
+
+// Initial patterns understood by init.wgsl
+pub const INIT_PATTERN_UNIFORM: u32 = 0;
+pub const INIT_PATTERN_CENTER: u32 = 1;
+pub const INIT_PATTERN_BLOBS: u32 = 2;
+pub const INIT_PATTERN_NOISE: u32 = 3;
+pub const INIT_PATTERN_SOUP: u32 = 4;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SimParams {
+    feed_rate: f32,
+    kill_rate: f32,
+    diffuse_u: f32,
+    diffuse_v: f32,
+    delta_time: f32,
+    noise_strength: f32,
+    grid_width: u32,
+    grid_height: u32,
+    kernel_type: u32,        // 0=default, 1=cross, 2=diagonal, 3=spiral
+    boundary_mode: u32,      // 0=wrap, 1=clamp, 2=reflect
+    map_mode: u32,
+    time: f32,  // step_count, read by F/k expressions
+    equation: u32,
+    equation_params: [f32; 3],
+    block_steps: u32,
+    _padding1: u32,
+    _padding2: u32,
+    _padding3: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InitParams {
+    pub pattern: u32,
+    pub num_blobs: u32,
+    grid_width: u32,   // Filled in by `Simulation::init_field`
+    grid_height: u32,
+    pub seed_radius: f32,
+    pub noise_amplitude: f32,  // pattern 3
+    pub noise_seed: u32,
+    pub soup_size: u32,  // pattern 4
+    pub blobs: [[f32; 4]; MAX_INIT_BLOBS],  // xy=center, z=radius
+}
+
+impl InitParams {
+    pub fn new(pattern: u32) -> Self {
+        Self {
+            pattern,
+            num_blobs: 0,
+            grid_width: 0,
+            grid_height: 0,
+            seed_radius: 20.0,
+            noise_amplitude: 0.0,
+            noise_seed: 0,
+            soup_size: 0,
+            blobs: [[0.0; 4]; MAX_INIT_BLOBS],
+        }
+    }
+
+    // U=1, V=0 everywhere
+    pub fn uniform() -> Self {
+        Self::new(INIT_PATTERN_UNIFORM)
+    }
+
+    // Uniform state plus a disc of V in the middle of the grid
+    pub fn center_seed() -> Self {
+        Self::new(INIT_PATTERN_CENTER)
+    }
+
+    // U and V uniform in -amplitude..amplitude, for the equations that grow
+    // patterns from small perturbations (see equation.rs)
+    pub fn noise(seed: u64, amplitude: f32) -> Self {
+        let mut params = Self::new(INIT_PATTERN_NOISE);
+        params.noise_amplitude = amplitude;
+        params.noise_seed = SplitMix64(seed).next_u64() as u32;
+        params
+    }
+
+    // V uniform in 0..1 inside a centred `size` x `size` square and 0 outside,
+    // with U = 1 - V, as the Lenia soup (see lenia.rs)
+    pub fn soup(seed: u64, size: u32) -> Self {
+        let mut params = Self::new(INIT_PATTERN_SOUP);
+        params.noise_seed = SplitMix64(seed).next_u64() as u32;
+        params.soup_size = size;
+        params
+    }
+
+    // Uniform state plus discs of V, given as (x, y, radius) in grid cells
+    pub fn blobs(blobs: &[(f32, f32, f32)]) -> Self {
+        let mut params = Self::new(INIT_PATTERN_BLOBS);
+        let count = blobs.len().min(MAX_INIT_BLOBS);
+        for (slot, &(x, y, radius)) in params.blobs.iter_mut().zip(&blobs[..count]) {
+            *slot = [x, y, radius, 0.0];
+        }
+        params.num_blobs = count as u32;
+        params
+    }
+
+    // `count` discs at reproducible positions: the same seed always gives the same
+    // blobs for a given grid size. Radii are drawn from `min_radius..max_radius`.
+    pub fn random_blobs(seed: u64, count: usize, width: u32, height: u32, min_radius: f32, max_radius: f32) -> Self {
+        let mut rng = SplitMix64(seed);
+        let blobs: Vec<(f32, f32, f32)> = (0..count.min(MAX_INIT_BLOBS))
+            .map(|_| {
+                let x = (rng.next_f32() * width as f32).floor();
+                let y = (rng.next_f32() * height as f32).floor();
+                let radius = min_radius + rng.next_f32() * (max_radius - min_radius);
+                (x, y, radius)
+            })
+            .collect();
+        Self::blobs(&blobs)
+    }
+}
+
+// Small deterministic generator for seeding, identical on every platform
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct PaintParams {
+    center_x: f32,
+    center_y: f32,
+}
+
+// Picks the storage format for the simulation field. WebGPU always supports
+// Rg32Float storage textures, but downlevel adapters (e.g. GL) may only allow
+// storage on Rgba32Float, which wastes two channels but works everywhere.
+pub fn preferred_field_format(adapter: &wgpu::Adapter) -> wgpu::TextureFormat {
+    let rg32 = adapter.get_texture_format_features(wgpu::TextureFormat::Rg32Float);
+    if rg32.allowed_usages.contains(wgpu::TextureUsages::STORAGE_BINDING) {
+        wgpu::TextureFormat::Rg32Float
+    } else {
+        wgpu::TextureFormat::Rgba32Float
+    }
+}
+
+// Picks a half-precision storage format for the field, if the adapter can write
+// one from compute shaders: Rg16Float at half the size of Rg32Float, or, where
+// the f32 field needs Rgba32Float, Rgba16Float at half of that. Steps still
+// compute in f32; only the stored field is rounded to f16.
+pub fn half_field_format(adapter: &wgpu::Adapter) -> Option<wgpu::TextureFormat> {
+    let storage = |format| {
+        adapter
+            .get_texture_format_features(format)
+            .allowed_usages
+            .contains(wgpu::TextureUsages::STORAGE_BINDING)
+    };
+    if storage(wgpu::TextureFormat::Rg16Float) {
+        Some(wgpu::TextureFormat::Rg16Float)
+    } else if preferred_field_format(adapter) == wgpu::TextureFormat::Rgba32Float && storage(wgpu::TextureFormat::Rgba16Float) {
+        Some(wgpu::TextureFormat::Rgba16Float)
+    } else {
+        None
+    }
+}
+
+// WGSL name of a storage texture format
+fn wgsl_storage_format(format: wgpu::TextureFormat) -> &'static str {
+    match format {
+        wgpu::TextureFormat::Rg32Float => "rg32float",
+        wgpu::TextureFormat::Rgba32Float => "rgba32float",
+        wgpu::TextureFormat::Rg16Float => "rg16float",
+        wgpu::TextureFormat::Rgba16Float => "rgba16float",
+        _ => panic!("Unsupported simulation field format: {:?}", format),
+    }
+}
+
+// Shaders are written against rg32float; swap in the field format actually in use
+pub(crate) fn field_shader(device: &wgpu::Device, label: &str, source: &str, format: wgpu::TextureFormat) -> wgpu::ShaderModule {
+    let source = source.replace("rg32float", wgsl_storage_format(format));
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    })
+}
+
+// Shader modules and pipelines for one field format. Compiling them is the slow
+// part of creating a simulation, so many small simulations (e.g. the runs of a
+// parameter sweep) can share one set.
+#[derive(Clone)]
+pub struct SimulationPipelines {
+    format: wgpu::TextureFormat,
+
+    compute_shader: wgpu::ShaderModule,
+    compute_pipeline: wgpu::ComputePipeline,
+    tiled_pipeline: wgpu::ComputePipeline,
+    blocked_pipeline: wgpu::ComputePipeline,
+    compute_pipeline_layout: wgpu::PipelineLayout,
+    compute_bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+
+    paint_pipeline: wgpu::ComputePipeline,
+    paint_bind_group_layout: wgpu::BindGroupLayout,
+
+    init_pipeline: wgpu::ComputePipeline,
+    init_bind_group_layout: wgpu::BindGroupLayout,
+
+    advect_pipeline: wgpu::ComputePipeline,
+    advect_bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl SimulationPipelines {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        // Create compute shader module
+        let compute_shader = field_shader(device, "Compute Shader", include_str!("compute.wgsl"), format);
+
+        // Create compute bind group layout (must match compute.wgsl bindings)
+        let compute_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Compute Bind Group Layout"),
+            entries: &[
+                // binding 0: texture_src
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // binding 1: texture_dst
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // binding 2: sampler
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                    count: None,
+                },
+                // binding 3: params uniform buffer
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Compute Pipeline Layout"),
+            bind_group_layouts: &[&compute_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Compute Pipeline"),
+            layout: Some(&compute_pipeline_layout),
+            module: &compute_shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let tiled_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Tiled Compute Pipeline"),
+            layout: Some(&compute_pipeline_layout),
+            module: &compute_shader,
+            entry_point: Some("main_tiled"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let blocked_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Blocked Compute Pipeline"),
+            layout: Some(&compute_pipeline_layout),
+            module: &compute_shader,
+            entry_point: Some("main_blocked"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        // Create sampler for texture reads - Nearest for Rg32Float (doesn't support filtering)
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        // Create paint shader and pipeline
+        let paint_shader = field_shader(device, "Paint Shader", include_str!("paint.wgsl"), format);
+
+        let paint_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Paint Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let paint_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Paint Pipeline Layout"),
+            bind_group_layouts: &[&paint_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let paint_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Paint Pipeline"),
+            layout: Some(&paint_pipeline_layout),
+            module: &paint_shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        // Create init shader and pipeline
+        let init_shader = field_shader(device, "Init Shader", include_str!("init.wgsl"), format);
+
+        let init_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Init Bind Group Layout"),
+            entries: &[
+                // binding 0: texture_dst
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // binding 1: init params uniform buffer
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let init_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Init Pipeline Layout"),
+            bind_group_layouts: &[&init_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let init_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Init Pipeline"),
+            layout: Some(&init_pipeline_layout),
+            module: &init_shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        // Create advection shader and pipeline (only dispatched while a flow is set)
+        let advect_shader = field_shader(device, "Advect Shader", include_str!("advect.wgsl"), format);
+
+        let advect_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Advect Bind Group Layout"),
+            entries: &[
+                // binding 0: texture_src
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // binding 1: texture_dst
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // binding 2: velocity_texture
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // binding 3: flow params uniform buffer
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let advect_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Advect Pipeline Layout"),
+            bind_group_layouts: &[&advect_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let advect_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Advect Pipeline"),
+            layout: Some(&advect_pipeline_layout),
+            module: &advect_shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Self {
+            format,
+            compute_shader,
+            compute_pipeline,
+            tiled_pipeline,
+            blocked_pipeline,
+            compute_pipeline_layout,
+            compute_bind_group_layout,
+            sampler,
+            paint_pipeline,
+            paint_bind_group_layout,
+            init_pipeline,
+            init_bind_group_layout,
+            advect_pipeline,
+            advect_bind_group_layout,
+        }
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub(crate) fn compute_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.compute_bind_group_layout
+    }
+
+    // A compute shader and its per-texel and tiled pipelines with F and k given
+    // by compiled expressions (None keeps the uniform setting)
+    fn expression_pipeline(&self, device: &wgpu::Device, feed: Option<&Expr>, kill: Option<&Expr>) -> ExpressionPipeline {
+        let mut source = include_str!("compute.wgsl").to_string();
+        if let Some(feed) = feed {
+            source = source.replace("return feed; // FEED_EXPRESSION", &format!("return max({}, 0.0);", feed.to_wgsl()));
+        }
+        if let Some(kill) = kill {
+            source = source.replace("return kill; // KILL_EXPRESSION", &format!("return max({}, 0.0);", kill.to_wgsl()));
+        }
+        let shader = field_shader(device, "Expression Compute Shader", &source, self.format);
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Expression Compute Pipeline"),
+            layout: Some(&self.compute_pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let tiled_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Expression Tiled Compute Pipeline"),
+            layout: Some(&self.compute_pipeline_layout),
+            module: &shader,
+            entry_point: Some("main_tiled"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        (shader, pipeline, tiled_pipeline)
+    }
+}
+
+// Shader, per-texel pipeline and tiled pipeline compiled with F/k expressions
+type ExpressionPipeline = (wgpu::ShaderModule, wgpu::ComputePipeline, wgpu::ComputePipeline);
+
+// Resources for the advection pass, created the first time a flow is set. The
+// advected field goes to a scratch texture, so reaction-diffusion still reads
+// one texture and writes the other and the ping-pong order is unchanged.
+struct Advection {
+    flow_buffer: wgpu::Buffer,
+    velocity_size: (u32, u32),
+    advect_bind_group_a: wgpu::BindGroup,  // A -> scratch
+    advect_bind_group_b: wgpu::BindGroup,  // B -> scratch
+    compute_bind_group_to_a: wgpu::BindGroup,  // scratch -> A
+    compute_bind_group_to_b: wgpu::BindGroup,  // scratch -> B
+}
+
+pub struct Simulation {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    pipelines: SimulationPipelines,
+
+    // Ping-pong textures
+    texture_a: wgpu::Texture,
+    texture_b: wgpu::Texture,
+    texture_a_view: wgpu::TextureView,
+    texture_b_view: wgpu::TextureView,
+    current_is_a: bool,
+    previous_steps: u32, // steps from the other texture's field to the current one
+    step_count: u64,  // Steps since the field was last replaced
+    fixed_time: f64,  // Time covered by fixed-size steps since then
+
+    // Compute bind groups
+    compute_bind_group_a_to_b: wgpu::BindGroup,
+    compute_bind_group_b_to_a: wgpu::BindGroup,
+    params_buffer: wgpu::Buffer,
+
+    // Paint bind groups
+    paint_bind_group_a: wgpu::BindGroup,
+    paint_bind_group_b: wgpu::BindGroup,
+    paint_params_buffer: wgpu::Buffer,
+
+    // Init bind groups (writes starting patterns on the GPU)
+    init_bind_group_a: wgpu::BindGroup,
+    init_bind_group_b: wgpu::BindGroup,
+    init_params_buffer: wgpu::Buffer,
+
+    // F/k expression sources and the shader and pipeline compiled from them
+    feed_expression: Option<String>,
+    kill_expression: Option<String>,
+    expression_pipeline: Option<ExpressionPipeline>,
+
+    advection: Option<Advection>,
+    spectral: Option<SpectralSolver>,
+    implicit: Option<ImplicitSolver>,
+    adaptive_stepper: Option<AdaptiveStepper>,
+    // Bumped whenever the field is replaced, so stale controller reads are dropped
+    time_step_epoch: u32,
+    time_step: Option<TimeStepState>, // latest controller read of this epoch
+    // The params' delta_time holds the controller's dt from the last adaptive step
+    controller_dt_in_params: bool,
+
+    // Parameters (call `update_params` after changing them)
+    pub feed_rate: f32,
+    pub kill_rate: f32,
+    pub diffuse_u: f32,
+    pub diffuse_v: f32,
+    pub delta_time: f32,
+    pub noise_strength: f32,
+    pub kernel_type: u32,
+    pub boundary_mode: u32,
+    pub map_mode: bool,
+    pub flow: Flow,
+    pub equation: Equation,
+    pub solver: Solver,
+    pub adaptive: Option<AdaptiveTimeStep>,
+    // Runs explicit steps with the workgroup-tiled kernel (main_tiled in compute.wgsl)
+    pub tiled_kernel: bool,
+    // Gray-Scott steps per dispatch with temporal blocking (main_blocked), 1 to MAX_BLOCK_STEPS
+    pub block_steps: u32,
+}
+
+impl Simulation {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, width: u32, height: u32, format: wgpu::TextureFormat) -> Self {
+        Self::with_pipelines(device, queue, width, height, &SimulationPipelines::new(device, format))
+    }
+
+    pub fn with_pipelines(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        pipelines: &SimulationPipelines,
+    ) -> Self {
+        let format = pipelines.format;
+
+        // Create simulation textures
+        let texture_desc = wgpu::TextureDescriptor {
+            label: Some("Simulation Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        };
+
+        let texture_a = device.create_texture(&texture_desc);
+        let texture_b = device.create_texture(&texture_desc);
+        let texture_a_view = texture_a.create_view(&wgpu::TextureViewDescriptor::default());
+        let texture_b_view = texture_b.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Create parameter buffer
+        let params = SimParams {
+            feed_rate: 0.055,
+            kill_rate: 0.062,
+            diffuse_u: 1.0,         // DA - standard value from tutorial
+            diffuse_v: 0.5,         // DB - standard value from tutorial
+            delta_time: 1.0,
+            noise_strength: 0.0,
+            grid_width: width,
+            grid_height: height,
+            kernel_type: 0,         // Default kernel
+            boundary_mode: 0,       // Wrap (toroidal)
+            map_mode: 0,
+            time: 0.0,
+            equation: 0,            // Gray-Scott
+            equation_params: [0.0; 3],
+            block_steps: 1,
+            _padding1: 0,
+            _padding2: 0,
+            _padding3: 0,
+        };
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Params Buffer"),
+            contents: bytemuck::cast_slice(&[params]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Create compute bind groups
+        let compute_bind_group_a_to_b = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Compute Bind Group A->B"),
+            layout: &pipelines.compute_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_a_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture_b_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&pipelines.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let compute_bind_group_b_to_a = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Compute Bind Group B->A"),
+            layout: &pipelines.compute_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_b_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture_a_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&pipelines.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let paint_params = PaintParams {
+            center_x: 0.0,
+            center_y: 0.0,
+        };
+
+        let paint_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Paint Params Buffer"),
+            contents: bytemuck::cast_slice(&[paint_params]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let paint_bind_group_a = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Paint Bind Group A"),
+            layout: &pipelines.paint_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_a_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture_b_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: paint_params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let paint_bind_group_b = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Paint Bind Group B"),
+            layout: &pipelines.paint_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_b_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture_a_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: paint_params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let init_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Init Params Buffer"),
+            contents: bytemuck::cast_slice(&[InitParams::center_seed()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let init_bind_group_a = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Init Bind Group A"),
+            layout: &pipelines.init_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_a_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: init_params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let init_bind_group_b = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Init Bind Group B"),
+            layout: &pipelines.init_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_b_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: init_params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut sim = Self {
+            device: device.clone(),
+            queue: queue.clone(),
+            width,
+            height,
+            format,
+            pipelines: pipelines.clone(),
+            texture_a,
+            texture_b,
+            texture_a_view,
+            texture_b_view,
+            current_is_a: true,
+            previous_steps: 1,
+            step_count: 0,
+            fixed_time: 0.0,
+            compute_bind_group_a_to_b,
+            compute_bind_group_b_to_a,
+            params_buffer,
+            paint_bind_group_a,
+            paint_bind_group_b,
+            paint_params_buffer,
+            init_bind_group_a,
+            init_bind_group_b,
+            init_params_buffer,
+            feed_expression: None,
+            kill_expression: None,
+            expression_pipeline: None,
+            advection: None,
+            spectral: None,
+            implicit: None,
+            adaptive_stepper: None,
+            time_step_epoch: 0,
+            time_step: None,
+            controller_dt_in_params: false,
+            feed_rate: 0.055,
+            kill_rate: 0.062,
+            diffuse_u: 1.0,
+            diffuse_v: 0.5,
+            delta_time: 1.0,
+            noise_strength: 0.0,
+            kernel_type: 0,
+            boundary_mode: 0,
+            map_mode: false,
+            flow: Flow::None,
+            equation: Equation::GrayScott,
+            solver: Solver::Explicit,
+            adaptive: None,
+            tiled_kernel: false,
+            block_steps: 1,
+        };
+
+        sim.init_field(&InitParams::center_seed());
+        sim
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub fn pipelines(&self) -> &SimulationPipelines {
+        &self.pipelines
+    }
+
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    // Whether texture A currently holds the latest field
+    pub fn current_is_a(&self) -> bool {
+        self.current_is_a
+    }
+
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    pub fn current_texture(&self) -> &wgpu::Texture {
+        if self.current_is_a { &self.texture_a } else { &self.texture_b }
+    }
+
+    pub fn next_texture(&self) -> &wgpu::Texture {
+        if self.current_is_a { &self.texture_b } else { &self.texture_a }
+    }
+
+    pub fn texture_a_view(&self) -> &wgpu::TextureView {
+        &self.texture_a_view
+    }
+
+    pub fn texture_b_view(&self) -> &wgpu::TextureView {
+        &self.texture_b_view
+    }
+
+    // Makes the texture last written by a pass the current field
+    pub fn swap(&mut self) {
+        self.current_is_a = !self.current_is_a;
+        self.previous_steps = 1;
+    }
+
+    // Steps between the field in the other ping-pong texture and the current
+    // one: 1, or up to block_steps after a temporally blocked dispatch
+    pub fn previous_steps(&self) -> u32 {
+        self.previous_steps
+    }
+
+    pub fn update_params(&mut self) {
+        let (equation, equation_params) = self.equation.uniform();
+        let params = SimParams {
+            feed_rate: self.feed_rate,
+            kill_rate: self.kill_rate,
+            diffuse_u: self.diffuse_u,
+            diffuse_v: self.diffuse_v,
+            delta_time: self.delta_time,
+            noise_strength: self.noise_strength,
+            grid_width: self.width,
+            grid_height: self.height,
+            kernel_type: self.kernel_type,
+            boundary_mode: self.boundary_mode,
+            map_mode: if self.map_mode { 1 } else { 0 },
+            time: self.step_count as f32,
+            equation,
+            equation_params,
+            block_steps: self.block_steps.clamp(1, MAX_BLOCK_STEPS),
+            _padding1: 0,
+            _padding2: 0,
+            _padding3: 0,
+        };
+        self.queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+        if self.adaptive.is_none() || self.adaptive_fallback().is_some() {
+            self.controller_dt_in_params = false;
+        }
+
+        if !self.flow.is_none() && self.advection.is_none() {
+            self.advection = Some(self.create_advection(None));
+        }
+        if let Some(advection) = &self.advection {
+            let flow = self.flow.uniform(self.width, self.height, self.delta_time, self.boundary_mode);
+            self.queue.write_buffer(&advection.flow_buffer, 0, bytemuck::cast_slice(&[flow]));
+        }
+
+        if self.solver == Solver::Spectral && self.solver_fallback().is_none() && self.spectral.is_none() {
+            self.spectral = Some(SpectralSolver::new(self).expect("solver_fallback rules out unsupported setups"));
+        }
+        if let Some(spectral) = &self.spectral {
+            spectral.update_params(self);
+        }
+        if let Solver::Implicit { theta, .. } = self.solver {
+            if self.solver_fallback().is_none() && self.implicit.is_none() {
+                self.implicit = Some(ImplicitSolver::new(self).expect("solver_fallback rules out unsupported setups"));
+            }
+            if let Some(implicit) = &self.implicit {
+                implicit.update_params(self, theta);
+            }
+        }
+
+        if let Some(adaptive) = self.adaptive {
+            if self.adaptive_stepper.is_none() {
+                let stepper = AdaptiveStepper::new(self);
+                stepper.reset(&self.queue, self.first_adaptive_dt(), self.time_step_epoch);
+                self.adaptive_stepper = Some(stepper);
+            }
+            if let Some(stepper) = &self.adaptive_stepper {
+                stepper.update_settings(self, &adaptive);
+            }
+        }
+    }
+
+    // Why the selected solver can't run on this simulation as it's set up, if it
+    // can't. Steps then use the explicit solver until the setup changes.
+    pub fn solver_fallback(&self) -> Option<String> {
+        match self.solver {
+            Solver::Explicit => None,
+            Solver::Spectral => spectral::unsupported_reason(self),
+            Solver::Implicit { .. } => implicit::unsupported_reason(self),
+        }
+    }
+
+    // Why adaptive time steps can't run on this simulation as it's set up, if
+    // they can't. Steps then use the fixed delta_time.
+    pub fn adaptive_fallback(&self) -> Option<String> {
+        if self.adaptive.is_none() {
+            None
+        } else if !self.solver.is_explicit() {
+            Some("adaptive time steps need the explicit solver".to_string())
+        } else if !self.flow.is_none() {
+            Some("adaptive time steps don't support flow advection".to_string())
+        } else {
+            None
+        }
+    }
+
+    // Simulated time since the field was last replaced. Adaptive steps are
+    // counted as of the last `sync_time_step`.
+    pub fn sim_time(&self) -> f64 {
+        self.fixed_time + self.time_step.map_or(0.0, |state| state.time)
+    }
+
+    // The size of the next step: delta_time, or the adaptive controller's dt as
+    // of the last `sync_time_step`
+    pub fn current_dt(&self) -> f32 {
+        match (&self.adaptive, &self.time_step) {
+            (Some(_), Some(state)) if self.adaptive_fallback().is_none() => state.dt,
+            _ => self.delta_time,
+        }
+    }
+
+    // Reads the adaptive controller's state back, or None if adaptive steps were
+    // never enabled. The future doesn't borrow the simulation.
+    pub fn read_time_step(&self) -> impl Future<Output = Result<Option<TimeStepState>, String>> + 'static {
+        let read = self.adaptive_stepper.as_ref().map(|stepper| stepper.read(&self.device, &self.queue));
+        async move {
+            match read {
+                Some(read) => Ok(Some(read.await?)),
+                None => Ok(None),
+            }
+        }
+    }
+
+    // Takes a state from `read_time_step` as the latest, unless the field has
+    // been replaced since it was read
+    pub fn sync_time_step(&mut self, state: TimeStepState) {
+        if state.epoch == self.time_step_epoch {
+            self.time_step = Some(state);
+        }
+    }
+
+    // Restarts the simulated time, with the field replaced
+    fn reset_time(&mut self) {
+        self.step_count = 0;
+        self.fixed_time = 0.0;
+        self.time_step_epoch = self.time_step_epoch.wrapping_add(1);
+        self.time_step = None;
+        if let Some(stepper) = &self.adaptive_stepper {
+            stepper.reset(&self.queue, self.first_adaptive_dt(), self.time_step_epoch);
+        }
+    }
+
+    // The adaptive controller starts from delta_time, within its bounds
+    fn first_adaptive_dt(&self) -> f32 {
+        match &self.adaptive {
+            Some(adaptive) => self.delta_time.clamp(adaptive.min_dt, adaptive.max_dt),
+            None => self.delta_time,
+        }
+    }
+
+    // Where the params uniform keeps delta_time, for the adaptive controller to overwrite
+    pub(crate) fn delta_time_slot(&self) -> (&wgpu::Buffer, u64) {
+        (&self.params_buffer, std::mem::offset_of!(SimParams, delta_time) as u64)
+    }
+
+    // The shader the compute pipeline was built from, with any F/k expressions
+    pub(crate) fn compute_shader(&self) -> &wgpu::ShaderModule {
+        match &self.expression_pipeline {
+            Some((shader, _, _)) => shader,
+            None => &self.pipelines.compute_shader,
+        }
+    }
+
+    // Sets the velocities for Flow::Texture: `width` x `height` interleaved (vx, vy)
+    // pairs in cells per unit time, stretched over the grid
+    pub fn set_flow_texture(&mut self, width: u32, height: u32, data: &[f32]) -> Result<(), String> {
+        let max_size = self.device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max_size || height > max_size {
+            return Err(format!("Velocity texture size must be 1 to {} per side, got {}x{}", max_size, width, height));
+        }
+        let expected = (width as usize).checked_mul(height as usize).and_then(|cells| cells.checked_mul(2));
+        if expected != Some(data.len()) {
+            return Err(format!(
+                "Expected {} values for a {}x{} velocity texture, got {}",
+                2 * width as u64 * height as u64,
+                width,
+                height,
+                data.len()
+            ));
+        }
+        self.advection = Some(self.create_advection(Some((width, height, data))));
+        self.update_params();
+        Ok(())
+    }
+
+    pub fn flow_texture_size(&self) -> Option<(u32, u32)> {
+        self.advection.as_ref().map(|a| a.velocity_size)
+    }
+
+    fn create_advection(&self, velocity: Option<(u32, u32, &[f32])>) -> Advection {
+        // A single zero texel until a velocity texture is set
+        let (velocity_width, velocity_height, velocity_data) = velocity.unwrap_or((1, 1, &[0.0, 0.0]));
+        let velocity_texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Velocity Texture"),
+            size: wgpu::Extent3d {
+                width: velocity_width,
+                height: velocity_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rg32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        self.queue.write_texture(
+            velocity_texture.as_image_copy(),
+            bytemuck::cast_slice(velocity_data),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(velocity_width * 8),
+                rows_per_image: Some(velocity_height),
+            },
+            velocity_texture.size(),
+        );
+        let velocity_view = velocity_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let scratch = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Advection Scratch Texture"),
+            size: self.texture_a.size(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+            view_formats: &[],
+        });
+        let scratch_view = scratch.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let flow_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Flow Params Buffer"),
+            size: std::mem::size_of::<FlowUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let advect_bind_group = |label: &str, src: &wgpu::TextureView| {
+            self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &self.pipelines.advect_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(src),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&scratch_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&velocity_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: flow_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+
+        let compute_bind_group = |label: &str, dst: &wgpu::TextureView| {
+            self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &self.pipelines.compute_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&scratch_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(dst),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&self.pipelines.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: self.params_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+
+        Advection {
+            advect_bind_group_a: advect_bind_group("Advect Bind Group A", &self.texture_a_view),
+            advect_bind_group_b: advect_bind_group("Advect Bind Group B", &self.texture_b_view),
+            compute_bind_group_to_a: compute_bind_group("Compute Bind Group Scratch->A", &self.texture_a_view),
+            compute_bind_group_to_b: compute_bind_group("Compute Bind Group Scratch->B", &self.texture_b_view),
+            flow_buffer,
+            velocity_size: (velocity_width, velocity_height),
+        }
+    }
+
+    // Makes F and/or k expressions of position and time (see expr.rs); None keeps
+    // the uniform setting. Recompiles the compute shader, so call it when the
+    // expressions change rather than every frame.
+    pub fn set_expressions(&mut self, feed: Option<&str>, kill: Option<&str>) -> Result<(), String> {
+        let parse = |name: &str, text: Option<&str>| {
+            text.map(|text| Expr::parse(text).map_err(|e| format!("{} expression: {}", name, e))).transpose()
+        };
+        let (feed_expr, kill_expr) = (parse("F", feed)?, parse("k", kill)?);
+
+        self.expression_pipeline = (feed_expr.is_some() || kill_expr.is_some())
+            .then(|| self.pipelines.expression_pipeline(&self.device, feed_expr.as_ref(), kill_expr.as_ref()));
+        self.feed_expression = feed.map(str::to_string);
+        self.kill_expression = kill.map(str::to_string);
+
+        // The spectral and implicit reaction passes are built from the same shader
+        if self.spectral.is_some() {
+            self.spectral = Some(SpectralSolver::new(self)?);
+        }
+        if self.implicit.is_some() {
+            self.implicit = Some(ImplicitSolver::new(self)?);
+        }
+        Ok(())
+    }
+
+    pub fn feed_expression(&self) -> Option<&str> {
+        self.feed_expression.as_deref()
+    }
+
+    pub fn kill_expression(&self) -> Option<&str> {
+        self.kill_expression.as_deref()
+    }
+
+    fn workgroups(&self) -> (u32, u32) {
+        (self.width.div_ceil(8), self.height.div_ceil(8))
+    }
+
+    // Replaces the current field with a pattern generated on the GPU
+    pub fn init_field(&mut self, init_params: &InitParams) {
+        let mut init_params = *init_params;
+        init_params.grid_width = self.width;
+        init_params.grid_height = self.height;
+        self.queue.write_buffer(&self.init_params_buffer, 0, bytemuck::cast_slice(&[init_params]));
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Init Encoder"),
+        });
+
+        {
+            let mut init_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Init Pass"),
+                timestamp_writes: None,
+            });
+
+            init_pass.set_pipeline(&self.pipelines.init_pipeline);
+
+            let bind_group = if self.current_is_a {
+                &self.init_bind_group_a
+            } else {
+                &self.init_bind_group_b
+            };
+
+            let (groups_x, groups_y) = self.workgroups();
+            init_pass.set_bind_group(0, bind_group, &[]);
+            init_pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+
+        self.queue.submit(Some(encoder.finish()));
+        self.reset_time();
+    }
+
+    // Replaces the current field with host data: interleaved (U, V) pairs, row-major
+    pub fn write_field(&mut self, data: &[f32]) -> Result<(), String> {
+        let expected = (self.width * self.height * 2) as usize;
+        if data.len() != expected {
+            return Err(format!("Expected {} values for a {}x{} field, got {}", expected, self.width, self.height, data.len()));
+        }
+
+        // Spread (U, V) pairs over however many channels the field format has
+        let channels = self.format.components() as usize;
+        let texels: Vec<f32> = if channels == 2 {
+            data.to_vec()
+        } else {
+            data.chunks(2)
+                .flat_map(|uv| {
+                    let mut texel = vec![0.0; channels];
+                    texel[..2].copy_from_slice(uv);
+                    texel
+                })
+                .collect()
+        };
+
+        self.queue.write_texture(
+            self.current_texture().as_image_copy(),
+            &readback::encode_channels(self.format, &texels),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(self.width * readback::texel_size(self.format)),
+                rows_per_image: Some(self.height),
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.reset_time();
+        Ok(())
+    }
+
+    // Copies the current field back: interleaved (U, V) pairs, row-major
+    pub fn read_field(&self) -> impl Future<Output = Result<Vec<f32>, String>> + 'static {
+        self.read_region(0, 0, self.width, self.height)
+    }
+
+    // Copies a `width` x `height` block of the current field starting at (x, y).
+    // The returned future doesn't borrow the simulation, so callers can release
+    // it (e.g. the app's RefCell) while the copy is in flight.
+    pub fn read_region(&self, x: u32, y: u32, width: u32, height: u32) -> impl Future<Output = Result<Vec<f32>, String>> + 'static {
+        let in_bounds = width > 0
+            && height > 0
+            && x.checked_add(width).is_some_and(|right| right <= self.width)
+            && y.checked_add(height).is_some_and(|bottom| bottom <= self.height);
+        let error = (!in_bounds).then(|| {
+            format!("Region {}x{} at ({}, {}) is outside the {}x{} grid", width, height, x, y, self.width, self.height)
+        });
+
+        let device = self.device.clone();
+        let queue = self.queue.clone();
+        let texture = self.current_texture().clone();
+
+        async move {
+            if let Some(error) = error {
+                return Err(error);
+            }
+            readback::read_texture_region(&device, &queue, &texture, x, y, width, height).await
+        }
+    }
+
+    // Copies the other ping-pong texture back. Right after `step` it holds the
+    // field `previous_steps()` steps earlier: one, or several after a
+    // temporally blocked dispatch.
+    pub fn read_previous_field(&self) -> impl Future<Output = Result<Vec<f32>, String>> + 'static {
+        let device = self.device.clone();
+        let queue = self.queue.clone();
+        let texture = self.next_texture().clone();
+        let (width, height) = (self.width, self.height);
+
+        async move { readback::read_texture_region(&device, &queue, &texture, 0, 0, width, height).await }
+    }
+
+    // Reads the (U, V) values of a single cell
+    pub fn read_cell(&self, x: u32, y: u32) -> impl Future<Output = Result<(f32, f32), String>> + 'static {
+        let region = self.read_region(x, y, 1, 1);
+        async move {
+            let values = region.await?;
+            Ok((values[0], values[1]))
+        }
+    }
+
+    // Advances the simulation by `steps` reaction-diffusion steps. With an
+    // adaptive time step each one is an attempt, which may be rejected.
+    pub fn step(&mut self, steps: u32) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Compute Encoder"),
+        });
+        self.encode_steps(&mut encoder, steps);
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    // Records `steps` steps into `encoder`, so a frame can submit them together
+    // with its other passes. The simulation counts the steps as taken right away.
+    pub fn encode_steps(&mut self, encoder: &mut wgpu::CommandEncoder, steps: u32) {
+        let adaptive = self.adaptive.is_some() && self.adaptive_fallback().is_none();
+        if adaptive {
+            self.controller_dt_in_params = steps > 0 || self.controller_dt_in_params;
+        } else if self.controller_dt_in_params && steps > 0 {
+            // Adaptive steps were turned off or fell back without update_params,
+            // so put delta_time back in place of the controller's last dt
+            let delta_time = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Delta Time Buffer"),
+                contents: bytemuck::bytes_of(&self.delta_time),
+                usage: wgpu::BufferUsages::COPY_SRC,
+            });
+            let (params_buffer, delta_time_offset) = self.delta_time_slot();
+            encoder.copy_buffer_to_buffer(&delta_time, 0, params_buffer, delta_time_offset, 4);
+            self.controller_dt_in_params = false;
+        }
+
+        // Expressions read t from the params, so each step copies its own time
+        // in from a buffer of them
+        let step_times = self.expression_pipeline.is_some().then(|| {
+            let times: Vec<f32> = (0..steps as u64).map(|i| (self.step_count + i) as f32).collect();
+            self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Step Times Buffer"),
+                contents: bytemuck::cast_slice(&times),
+                usage: wgpu::BufferUsages::COPY_SRC,
+            })
+        });
+
+        // Plain explicit steps share one compute pass, with whole blocks of
+        // block_steps steps per dispatch when temporal blocking is on
+        let fused = !adaptive && step_times.is_none() && self.flow.is_none() && self.explicit_solver_runs();
+        if fused {
+            let block_steps = self.block_steps_in_use();
+            let mut dispatches = 0;
+            let mut last_dispatch_steps = 1;
+            if steps > 0 {
+                let (pipeline, groups_x, groups_y) = self.explicit_pipeline();
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Compute Pass"),
+                    timestamp_writes: None,
+                });
+
+                let mut from_a = self.current_is_a;
+                let mut remaining = steps;
+                while remaining > 0 {
+                    let bind_group = if from_a {
+                        &self.compute_bind_group_a_to_b
+                    } else {
+                        &self.compute_bind_group_b_to_a
+                    };
+                    compute_pass.set_bind_group(0, bind_group, &[]);
+                    if block_steps > 1 && remaining >= block_steps {
+                        compute_pass.set_pipeline(&self.pipelines.blocked_pipeline);
+                        compute_pass.dispatch_workgroups(self.width.div_ceil(16), self.height.div_ceil(16), 1);
+                        remaining -= block_steps;
+                        last_dispatch_steps = block_steps;
+                    } else {
+                        compute_pass.set_pipeline(pipeline);
+                        compute_pass.dispatch_workgroups(groups_x, groups_y, 1);
+                        remaining -= 1;
+                        last_dispatch_steps = 1;
+                    }
+                    from_a = !from_a;
+                    dispatches += 1;
+                }
+            }
+
+            for _ in 0..dispatches {
+                self.swap();
+            }
+            if dispatches > 0 {
+                self.previous_steps = last_dispatch_steps;
+            }
+            for _ in 0..steps {
+                self.fixed_time += self.delta_time as f64;
+            }
+            self.step_count += steps as u64;
+            return;
+        }
+
+        for i in 0..steps {
+            if let Some(step_times) = &step_times {
+                let time_offset = std::mem::offset_of!(SimParams, time) as u64;
+                encoder.copy_buffer_to_buffer(step_times, i as u64 * 4, &self.params_buffer, time_offset, 4);
+            }
+
+            if let Some(stepper) = self.adaptive_stepper.as_ref().filter(|_| adaptive) {
+                stepper.encode(self, encoder);
+            } else {
+                self.encode_step(encoder, self.current_is_a);
+                self.fixed_time += self.delta_time as f64;
+            }
+
+            self.swap();
+            self.step_count += 1;
+        }
+    }
+
+    // Records one step from texture A or B into the other with the selected solver
+    pub(crate) fn encode_step(&self, encoder: &mut wgpu::CommandEncoder, from_a: bool) {
+        let (groups_x, groups_y) = self.workgroups();
+        let advection = self.advection.as_ref().filter(|_| !self.flow.is_none());
+
+        // Advect current -> scratch, then react-diffuse scratch -> next
+        if let Some(advection) = advection {
+            let mut advect_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Advect Pass"),
+                timestamp_writes: None,
+            });
+
+            advect_pass.set_pipeline(&self.pipelines.advect_pipeline);
+
+            let bind_group = if from_a {
+                &advection.advect_bind_group_a
+            } else {
+                &advection.advect_bind_group_b
+            };
+
+            advect_pass.set_bind_group(0, bind_group, &[]);
+            advect_pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+
+        let bind_group = match (advection, from_a) {
+            (Some(advection), true) => &advection.compute_bind_group_to_b,
+            (Some(advection), false) => &advection.compute_bind_group_to_a,
+            (None, true) => &self.compute_bind_group_a_to_b,
+            (None, false) => &self.compute_bind_group_b_to_a,
+        };
+
+        let supported = self.solver_fallback().is_none();
+        match (self.solver, &self.spectral, &self.implicit) {
+            (Solver::Spectral, Some(spectral), _) if supported => {
+                spectral.encode(encoder, bind_group, !from_a, (groups_x, groups_y));
+            }
+            (Solver::Implicit { cycles, .. }, _, Some(implicit)) if supported => {
+                implicit.encode(encoder, bind_group, !from_a, cycles);
+            }
+            _ => {
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Compute Pass"),
+                    timestamp_writes: None,
+                });
+
+                let (pipeline, groups_x, groups_y) = self.explicit_pipeline();
+                compute_pass.set_pipeline(pipeline);
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                compute_pass.dispatch_workgroups(groups_x, groups_y, 1);
+            }
+        }
+    }
+
+    // Why temporal blocking (block_steps > 1) can't run on this simulation as
+    // it's set up, if it can't. Steps then take one dispatch each.
+    pub fn blocking_fallback(&self) -> Option<String> {
+        if self.block_steps <= 1 {
+            None
+        } else if !self.equation.is_gray_scott() {
+            Some("temporal blocking only steps Gray-Scott".to_string())
+        } else if !self.explicit_solver_runs() {
+            Some("temporal blocking needs the explicit solver".to_string())
+        } else if self.adaptive.is_some() && self.adaptive_fallback().is_none() {
+            Some("temporal blocking needs a fixed time step".to_string())
+        } else if !self.flow.is_none() {
+            Some("temporal blocking doesn't support flow advection".to_string())
+        } else if self.expression_pipeline.is_some() {
+            Some("temporal blocking doesn't support F/k expressions, which read t every step".to_string())
+        } else {
+            None
+        }
+    }
+
+    // Steps per dispatch of the blocked kernel, or 1 if blocking is off or falls back
+    fn block_steps_in_use(&self) -> u32 {
+        match self.blocking_fallback() {
+            None => self.block_steps.clamp(1, MAX_BLOCK_STEPS),
+            Some(_) => 1,
+        }
+    }
+
+    // Whether steps run the explicit compute pass, i.e. no other solver takes over
+    fn explicit_solver_runs(&self) -> bool {
+        match self.solver {
+            Solver::Explicit => true,
+            Solver::Spectral => self.spectral.is_none() || self.solver_fallback().is_some(),
+            Solver::Implicit { .. } => self.implicit.is_none() || self.solver_fallback().is_some(),
+        }
+    }
+
+    // The explicit step's pipeline (per-texel or tiled, with any F/k expressions)
+    // and its workgroup counts
+    fn explicit_pipeline(&self) -> (&wgpu::ComputePipeline, u32, u32) {
+        let (groups_x, groups_y) = self.workgroups();
+        let (tiled_x, tiled_y) = (self.width.div_ceil(16), self.height.div_ceil(16));
+        match (&self.expression_pipeline, self.tiled_kernel) {
+            (Some((_, _, tiled)), true) => (tiled, tiled_x, tiled_y),
+            (None, true) => (&self.pipelines.tiled_pipeline, tiled_x, tiled_y),
+            (Some((_, pipeline, _)), false) => (pipeline, groups_x, groups_y),
+            (None, false) => (&self.pipelines.compute_pipeline, groups_x, groups_y),
+        }
+    }
+
+    // Paints a spot of V centered on grid cell (x, y)
+    pub fn paint(&mut self, x: f32, y: f32) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Paint Encoder"),
+        });
+        self.encode_paint(&mut encoder, x, y);
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    // Records `paint` into `encoder`
+    pub fn encode_paint(&mut self, encoder: &mut wgpu::CommandEncoder, x: f32, y: f32) {
+        let paint_params = PaintParams {
+            center_x: x,
+            center_y: y,
+        };
+
+        self.queue.write_buffer(
+            &self.paint_params_buffer,
+            0,
+            bytemuck::cast_slice(&[paint_params]),
+        );
+
+        {
+            let mut paint_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Paint Pass"),
+                timestamp_writes: None,
+            });
+
+            paint_pass.set_pipeline(&self.pipelines.paint_pipeline);
+
+            let bind_group = if self.current_is_a {
+                &self.paint_bind_group_a
+            } else {
+                &self.paint_bind_group_b
+            };
+
+            let (groups_x, groups_y) = self.workgroups();
+            paint_pass.set_bind_group(0, bind_group, &[]);
+            paint_pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+
+        // The paint pass wrote into the other texture
+        self.swap();
+    }
+}
+
+// Requests a device without a surface, for tests and native tools.
+// Falls back to a software adapter when no GPU is available.
+pub async fn request_headless_device() -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), String> {
+    request_headless_device_with(false).await
+}
+
+// Like `request_headless_device`, but `force_software` skips straight to the
+// software (fallback) adapter
+pub async fn request_headless_device_with(
+    force_software: bool,
+) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), String> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+
+    let hardware = if force_software {
+        None
+    } else {
+        instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await
+            .ok()
+    };
+
+    let adapter = match hardware {
+        Some(adapter) => adapter,
+        None => instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::LowPower,
+                compatible_surface: None,
+                force_fallback_adapter: true,
+            })
+            .await
+            .map_err(|e| format!("Failed to find adapter: {:?}", e))?,
+    };
+
+    // Downlevel adapters (e.g. GL) only expose float storage textures through
+    // their adapter-specific format features. Timestamps are taken when offered,
+    // for perf::PerfMonitor.
+    let required_features = adapter.features()
+        & (wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES | wgpu::Features::TIMESTAMP_QUERY);
+
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: Some("Headless Device"),
+            required_features,
+            required_limits: adapter.limits(),
+            memory_hints: Default::default(),
+            experimental_features: Default::default(),
+            trace: Default::default(),
+        })
+        .await
+        .map_err(|e| format!("Failed to create device: {}", e))?;
+
+    Ok((adapter, device, queue))
+}
+
+// Simulation on a headless adapter for GPU tests, or None (skip) if there is no adapter
+#[cfg(test)]
+pub(crate) fn headless_simulation(width: u32, height: u32) -> Option<Simulation> {
+    match pollster::block_on(request_headless_device()) {
+        Ok((adapter, device, queue)) => {
+            Some(Simulation::new(&device, &queue, width, height, preferred_field_format(&adapter)))
+        }
+        Err(e) => {
+            eprintln!("Skipping GPU test: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v_at(field: &[f32], width: u32, x: u32, y: u32) -> f32 {
+        field[((y * width + x) * 2 + 1) as usize]
+    }
+
+    #[test]
+    fn write_then_read_round_trips() {
+        let Some(mut sim) = headless_simulation(40, 24) else { return };
+
+        let data: Vec<f32> = (0..40 * 24 * 2).map(|i| i as f32 / 2000.0).collect();
+        sim.write_field(&data).unwrap();
+
+        assert_eq!(pollster::block_on(sim.read_field()).unwrap(), data);
+        assert!(sim.write_field(&data[1..]).is_err());
+    }
+
+    #[test]
+    fn new_simulation_starts_with_center_seed() {
+        let Some(sim) = headless_simulation(64, 64) else { return };
+
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        assert_eq!(field[0], 1.0);
+        assert_eq!(v_at(&field, 64, 0, 0), 0.0);
+        assert_eq!(v_at(&field, 64, 32, 32), 1.0);
+    }
+
+    #[test]
+    fn step_and_paint_swap_textures() {
+        let Some(mut sim) = headless_simulation(32, 32) else { return };
+
+        assert!(sim.current_is_a());
+        sim.step(3);
+        assert!(!sim.current_is_a());
+        sim.paint(4.0, 4.0);
+        assert!(sim.current_is_a());
+
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        assert_eq!(v_at(&field, 32, 4, 4), 1.0);
+    }
+
+    #[test]
+    fn init_after_odd_steps_writes_current_texture() {
+        let Some(mut sim) = headless_simulation(32, 32) else { return };
+
+        sim.step(1);
+        sim.init_field(&InitParams::uniform());
+        assert!(!sim.current_is_a());
+
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        assert!(field.chunks(2).all(|uv| uv == [1.0, 0.0]));
+
+        // A step from a uniform U=1, V=0 state stays there
+        sim.step(1);
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        assert!(field.chunks(2).all(|uv| uv == [1.0, 0.0]));
+    }
+
+    #[test]
+    fn region_and_cell_reads_match_the_full_field() {
+        let Some(mut sim) = headless_simulation(70, 20) else { return };
+
+        let data: Vec<f32> = (0..70 * 20 * 2).map(|i| i as f32).collect();
+        sim.write_field(&data).unwrap();
+
+        // 13 texels per row is deliberately not a multiple of the copy alignment
+        let region = pollster::block_on(sim.read_region(5, 3, 13, 4)).unwrap();
+        assert_eq!(region.len(), 13 * 4 * 2);
+        for row in 0..4 {
+            for col in 0..13 {
+                let src = (((3 + row) * 70 + 5 + col) * 2) as usize;
+                let dst = ((row * 13 + col) * 2) as usize;
+                assert_eq!(region[dst..dst + 2], data[src..src + 2]);
+            }
+        }
+
+        let cell = pollster::block_on(sim.read_cell(69, 19)).unwrap();
+        assert_eq!(cell, (data[(19 * 70 + 69) * 2], data[(19 * 70 + 69) * 2 + 1]));
+    }
+
+    #[test]
+    fn out_of_bounds_reads_are_rejected() {
+        let Some(sim) = headless_simulation(16, 16) else { return };
+
+        assert!(pollster::block_on(sim.read_region(10, 0, 7, 1)).is_err());
+        assert!(pollster::block_on(sim.read_region(0, 0, 0, 4)).is_err());
+        assert!(pollster::block_on(sim.read_cell(0, 16)).is_err());
+        assert!(pollster::block_on(sim.read_region(u32::MAX, 0, 2, 2)).is_err());
+    }
+
+    #[test]
+    fn blobs_are_written_where_requested() {
+        let Some(mut sim) = headless_simulation(64, 64) else { return };
+
+        sim.init_field(&InitParams::blobs(&[(10.0, 10.0, 3.0), (50.0, 40.0, 2.0)]));
+        let field = pollster::block_on(sim.read_field()).unwrap();
+
+        assert_eq!(v_at(&field, 64, 10, 10), 1.0);
+        assert_eq!(v_at(&field, 64, 13, 10), 1.0);
+        assert_eq!(v_at(&field, 64, 14, 10), 0.0);
+        assert_eq!(v_at(&field, 64, 50, 40), 1.0);
+        assert_eq!(v_at(&field, 64, 32, 32), 0.0);
+    }
+
+    #[test]
+    fn random_blobs_are_reproducible() {
+        let a = InitParams::random_blobs(7, 10, 100, 50, 2.0, 5.0);
+        let b = InitParams::random_blobs(7, 10, 100, 50, 2.0, 5.0);
+        let c = InitParams::random_blobs(8, 10, 100, 50, 2.0, 5.0);
+
+        assert_eq!(a.num_blobs, 10);
+        assert_eq!(a.blobs, b.blobs);
+        assert_ne!(a.blobs, c.blobs);
+        for blob in &a.blobs[..10] {
+            assert!(blob[0] < 100.0 && blob[1] < 50.0);
+            assert!((2.0..5.0).contains(&blob[2]));
+        }
+    }
+
+    #[test]
+    fn simulations_can_share_pipelines() {
+        let Some(first) = headless_simulation(16, 16) else { return };
+        let mut second = Simulation::with_pipelines(first.device(), first.queue(), 8, 8, first.pipelines());
+
+        second.init_field(&InitParams::uniform());
+        second.step(3);
+        assert_eq!(second.step_count(), 3);
+        assert_eq!(first.step_count(), 0);
+
+        let field = pollster::block_on(second.read_field()).unwrap();
+        assert_eq!(field.len(), 8 * 8 * 2);
+        assert!(field.chunks(2).all(|uv| uv == [1.0, 0.0]));
+    }
+
+    #[test]
+    fn feed_expression_varies_over_space_and_time() {
+        let Some(mut sim) = headless_simulation(16, 4) else { return };
+
+        // With V = 0 and no diffusion only the feed term acts: U += F * (1 - U)
+        sim.write_field(&[0.5, 0.0].repeat(16 * 4)).unwrap();
+        sim.set_expressions(Some("x * 0.1 + t * 0.01"), None).unwrap();
+        sim.diffuse_u = 0.0;
+        sim.update_params();
+        sim.step(2);
+        assert_eq!(sim.feed_expression(), Some("x * 0.1 + t * 0.01"));
+
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        for x in 0..16 {
+            let mut u = 0.5f32;
+            for t in 0..2 {
+                u += (x as f32 / 16.0 * 0.1 + t as f32 * 0.01) * (1.0 - u);
+            }
+            assert!((field[x * 2] - u).abs() < 1e-5, "x = {}: {} vs {}", x, field[x * 2], u);
+        }
+
+        assert!(sim.set_expressions(Some("x +"), None).unwrap_err().starts_with("F expression"));
+        // Constants the shader compiler would fold to inf are errors, not panics
+        assert!(sim.set_expressions(Some("1/0"), None).unwrap_err().contains("finite"));
+        assert!(sim.set_expressions(None, Some("2^200 * x")).unwrap_err().starts_with("k expression"));
+        sim.set_expressions(Some("x * (1/2) + 2^-3"), None).unwrap();
+        sim.set_expressions(None, None).unwrap();
+        assert_eq!(sim.feed_expression(), None);
+    }
+
+    #[test]
+    fn drift_flow_shifts_the_field() {
+        let Some(mut sim) = headless_simulation(8, 4) else { return };
+
+        // No reaction or diffusion, so only advection changes U
+        let ramp: Vec<f32> = (0..8 * 4).flat_map(|i| [(i % 8) as f32 * 0.1, 0.0]).collect();
+        sim.write_field(&ramp).unwrap();
+        sim.feed_rate = 0.0;
+        sim.kill_rate = 0.0;
+        sim.diffuse_u = 0.0;
+        sim.diffuse_v = 0.0;
+        sim.flow = Flow::Drift { vx: 1.0, vy: 0.0 };
+        sim.update_params();
+        sim.step(1);
+
+        // Moving one cell right per step, wrapping at the edge
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        for x in 0..8 {
+            let expected = ((x + 7) % 8) as f32 * 0.1;
+            assert!((field[x * 2] - expected).abs() < 1e-6, "x = {}: {}", x, field[x * 2]);
+        }
+
+        // Half a cell lands between neighbours
+        sim.write_field(&ramp).unwrap();
+        sim.flow = Flow::Drift { vx: 0.5, vy: 0.0 };
+        sim.update_params();
+        sim.step(1);
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        assert!((field[3 * 2] - 0.25).abs() < 1e-6);
+
+        // A uniform velocity texture behaves like the same drift
+        sim.write_field(&ramp).unwrap();
+        sim.set_flow_texture(2, 2, &[1.0, 0.0].repeat(4)).unwrap();
+        sim.flow = Flow::Texture { scale: 1.0 };
+        sim.update_params();
+        sim.step(1);
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        assert!((field[0] - 0.7).abs() < 1e-6);
+        assert!(sim.set_flow_texture(2, 2, &[0.0; 3]).is_err());
+        assert!(sim.set_flow_texture(u32::MAX, u32::MAX, &[0.0; 2]).is_err());
+        let too_wide = sim.device().limits().max_texture_dimension_2d + 1;
+        assert!(sim.set_flow_texture(too_wide, 1, &vec![0.0; too_wide as usize * 2]).is_err());
+    }
+
+    #[test]
+    fn other_equations_match_the_cpu_on_uniform_fields() {
+        let Some(mut sim) = headless_simulation(8, 8) else { return };
+
+        // Uniform fields have no spatial terms, leaving the local dynamics
+        sim.write_field(&[1.0, 0.0].repeat(64)).unwrap();
+        sim.equation = Equation::GinzburgLandau { alpha: 0.5, beta: 2.0 };
+        sim.delta_time = 0.1;
+        sim.update_params();
+        sim.step(1);
+        // dA/dt = A - (1 + 2i) |A|^2 A = -2i at A = 1
+        let (u, v) = pollster::block_on(sim.read_cell(3, 5)).unwrap();
+        assert!((u - 1.0).abs() < 1e-6 && (v + 0.2).abs() < 1e-6, "{} {}", u, v);
+
+        sim.write_field(&[0.5, 0.0].repeat(64)).unwrap();
+        sim.equation = Equation::SwiftHohenberg { r: 0.3, q0: 0.75, g: 1.0 };
+        sim.boundary_mode = 2;
+        sim.delta_time = 0.02;
+        sim.update_params();
+        sim.step(1);
+        let u0 = 0.5f32;
+        let expected = u0 + ((0.3 - 0.75f32.powi(4)) * u0 + u0 * u0 - u0 * u0 * u0) * 0.02;
+        let (u, _) = pollster::block_on(sim.read_cell(0, 7)).unwrap();
+        assert!((u - expected).abs() < 1e-6, "{} vs {}", u, expected);
+
+        // A single bump spreads with the biharmonic's signature: the cell two away
+        // feels -bilap = -1 * bump while the direct neighbours feel +8
+        let mut field = [0.0, 0.0].repeat(64);
+        field[(4 * 8 + 4) * 2] = 0.1;
+        sim.write_field(&field).unwrap();
+        sim.equation = Equation::SwiftHohenberg { r: 0.0, q0: 1e-3, g: 0.0 };
+        sim.update_params();
+        sim.step(1);
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        let at = |x: usize, y: usize| field[(y * 8 + x) * 2];
+        assert!((at(5, 4) - 0.1 * 8.0 * 0.02).abs() < 1e-5, "{}", at(5, 4));
+        assert!((at(6, 4) + 0.1 * 0.02).abs() < 1e-5, "{}", at(6, 4));
+    }
+
+    #[test]
+    fn steps_recorded_together_match_separate_submissions() {
+        let Some(mut sim) = headless_simulation(24, 24) else { return };
+        sim.init_field(&InitParams::random_blobs(7, 6, 24, 24, 2.0, 4.0));
+        let field = pollster::block_on(sim.read_field()).unwrap();
+
+        for _ in 0..5 {
+            sim.step(1);
+        }
+        let separate = pollster::block_on(sim.read_field()).unwrap();
+
+        sim.write_field(&field).unwrap();
+        let started_on_a = sim.current_is_a();
+        let mut encoder = sim.device().create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        sim.encode_steps(&mut encoder, 2);
+        sim.encode_steps(&mut encoder, 3);
+        assert_eq!(sim.step_count(), 5);
+        sim.queue().submit(Some(encoder.finish()));
+        assert_ne!(sim.current_is_a(), started_on_a);
+        assert_eq!(pollster::block_on(sim.read_field()).unwrap(), separate);
+    }
+
+    #[test]
+    fn tiled_kernel_matches_the_per_texel_kernel() {
+        // Not a multiple of the 16x16 tile, so edge workgroups are partly outside
+        let (width, height) = (37, 21);
+        let Some(mut sim) = headless_simulation(width, height) else { return };
+
+        let mut seed = 12345u32;
+        let field: Vec<f32> = (0..width * height * 2)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (seed >> 8) as f32 / (1u32 << 24) as f32
+            })
+            .collect();
+        let run = |sim: &mut Simulation, tiled: bool| {
+            sim.write_field(&field).unwrap();
+            sim.tiled_kernel = tiled;
+            sim.update_params();
+            sim.step(2);
+            pollster::block_on(sim.read_field()).unwrap()
+        };
+
+        for kernel_type in 0..5 {
+            for boundary_mode in 0..3 {
+                sim.kernel_type = kernel_type;
+                sim.boundary_mode = boundary_mode;
+                let per_texel = run(&mut sim, false);
+                let tiled = run(&mut sim, true);
+                let error = per_texel.iter().zip(&tiled).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+                assert!(error < 1e-6, "kernel {} boundary {}: {}", kernel_type, boundary_mode, error);
+            }
+        }
+
+        // Expressions and the other equations go through the tiled entry point too
+        sim.set_expressions(Some("0.03 + x * 0.02"), None).unwrap();
+        let per_texel = run(&mut sim, false);
+        assert_eq!(per_texel, run(&mut sim, true));
+        sim.equation = Equation::SwiftHohenberg { r: 0.3, q0: 0.75, g: 1.0 };
+        sim.delta_time = 0.02;
+        let per_texel = run(&mut sim, false);
+        assert_eq!(per_texel, run(&mut sim, true));
+    }
+
+    #[test]
+    fn blocked_steps_match_single_steps() {
+        // Not a multiple of the 16x16 block, and smaller than the largest halo
+        let (width, height) = (37, 7);
+        let Some(mut sim) = headless_simulation(width, height) else { return };
+
+        let mut seed = 777u32;
+        let field: Vec<f32> = (0..width * height * 2)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (seed >> 8) as f32 / (1u32 << 24) as f32
+            })
+            .collect();
+        // 7 steps: whole blocks, then single steps for the rest
+        let run = |sim: &mut Simulation, block_steps: u32| {
+            sim.write_field(&field).unwrap();
+            sim.block_steps = block_steps;
+            sim.update_params();
+            sim.step(7);
+            pollster::block_on(sim.read_field()).unwrap()
+        };
+
+        for kernel_type in 0..5 {
+            for boundary_mode in 0..3 {
+                sim.kernel_type = kernel_type;
+                sim.boundary_mode = boundary_mode;
+                let single = run(&mut sim, 1);
+                for block_steps in 2..=MAX_BLOCK_STEPS {
+                    let blocked = run(&mut sim, block_steps);
+                    let error = single.iter().zip(&blocked).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+                    assert!(
+                        error < 1e-6,
+                        "kernel {} boundary {} block {}: {}",
+                        kernel_type,
+                        boundary_mode,
+                        block_steps,
+                        error
+                    );
+                }
+            }
+        }
+        assert_eq!(sim.step_count(), 7);
+        assert_eq!(sim.blocking_fallback(), None);
+
+        // Other equations take one dispatch per step
+        sim.equation = Equation::SwiftHohenberg { r: 0.3, q0: 0.75, g: 1.0 };
+        sim.delta_time = 0.02;
+        let single = run(&mut sim, 1);
+        assert_eq!(run(&mut sim, 4), single);
+        assert!(sim.blocking_fallback().unwrap().contains("Gray-Scott"));
+    }
+
+    #[test]
+    fn half_precision_field_tracks_f32() {
+        let Ok((adapter, device, queue)) = pollster::block_on(request_headless_device()) else { return };
+        let Some(half_format) = half_field_format(&adapter) else {
+            eprintln!("Skipping half-precision test: no f16 storage format");
+            return;
+        };
+        let (width, height) = (48, 40);
+        let mut full = Simulation::new(&device, &queue, width, height, preferred_field_format(&adapter));
+        let mut half = Simulation::new(&device, &queue, width, height, half_format);
+
+        // A square of V in a U=1 background, as the scenes seed it
+        let field: Vec<f32> = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                let inside = (18..30).contains(&x) && (14..26).contains(&y);
+                if inside { [0.5, 0.25] } else { [1.0, 0.0] }
+            })
+            .collect();
+        half.write_field(&field).unwrap();
+        let stored = pollster::block_on(half.read_field()).unwrap();
+        assert_eq!(stored, field, "seed values are exact in f16");
+
+        // Rounding each step to f16 loses increments below half an ulp, so the
+        // fields drift apart over long runs; a few steps stay close
+        full.write_field(&field).unwrap();
+        full.step(10);
+        half.step(10);
+        let expected = pollster::block_on(full.read_field()).unwrap();
+        let actual = pollster::block_on(half.read_field()).unwrap();
+        let error = expected.iter().zip(&actual).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+        assert!(error < 0.005, "{}", error);
+        assert!(actual.iter().zip(&stored).any(|(a, b)| a != b), "the half field steps");
+    }
+}
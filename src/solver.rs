@@ -0,0 +1,144 @@
+// How each step integrates diffusion.
+//
+// - Explicit: forward Euler with the selected Laplacian kernel (compute.wgsl).
+//   Cheap, but delta_time has to stay below roughly 1 / (4 max(Du, Dv)).
+// - Spectral: integrating-factor Euler for periodic grids (spectral.rs). The
+//   reaction is taken explicitly, then diffusion is applied exactly in Fourier
+//   space:  u' = exp(dt D L) (u + dt R(u)),  where L is the Fourier symbol of the
+//   same Laplacian kernel. Diffusion is then stable for any delta_time and the
+//   step size is limited by the reaction alone. Needs wrap boundaries, a
+//   power-of-two grid and the Gray-Scott equation; otherwise steps fall back to
+//   the explicit solver.
+// - Implicit: the reaction is taken explicitly, then diffusion is solved as
+//     (I - theta dt D L) u' = (I + (1 - theta) dt D L) (u + dt R(u))
+//   with multigrid V-cycles on the GPU (implicit.rs). theta = 1 is backward
+//   Euler, stable for any delta_time; theta = 0.5 is Crank-Nicolson, more
+//   accurate but it lets the finest modes ring at large steps. Works with every
+//   boundary mode and grid size; `cycles` trades accuracy for speed. Gray-Scott only.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Solver {
+    #[default]
+    Explicit,
+    Spectral,
+    Implicit {
+        #[serde(default = "default_theta")]
+        theta: f32,
+        // V-cycles per step
+        #[serde(default = "default_cycles")]
+        cycles: u32,
+    },
+}
+
+fn default_theta() -> f32 {
+    1.0
+}
+
+fn default_cycles() -> u32 {
+    2
+}
+
+pub const MAX_CYCLES: u32 = 16;
+
+impl Solver {
+    pub fn is_explicit(&self) -> bool {
+        *self == Solver::Explicit
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Solver::Implicit { theta, cycles } = *self {
+            if !(0.5..=1.0).contains(&theta) {
+                return Err("Implicit theta must be between 0.5 and 1".to_string());
+            }
+            if !(1..=MAX_CYCLES).contains(&cycles) {
+                return Err(format!("Implicit cycles must be between 1 and {}", MAX_CYCLES));
+            }
+        }
+        Ok(())
+    }
+}
+
+// The Laplacian kernels of compute.wgsl as 5x5 weights, row-major from offset
+// (-2, -2): entry (dy + 2) * 5 + (dx + 2) weighs the cell at (x + dx, y + dy)
+pub(crate) fn kernel_weights(kernel_type: u32) -> [f32; 25] {
+    let mut weights = [0.0; 25];
+    let mut set = |dx: i32, dy: i32, weight: f32| weights[((dy + 2) * 5 + dx + 2) as usize] = weight;
+    match kernel_type {
+        0 => {
+            set(0, 0, -1.0);
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                set(dx, dy, 0.2);
+            }
+            for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                set(dx, dy, 0.05);
+            }
+        }
+        1 => {
+            set(0, 0, -0.8);
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                set(dx, dy, 0.2);
+            }
+        }
+        2 => {
+            set(0, 0, -0.8);
+            for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                set(dx, dy, 0.2);
+            }
+        }
+        3 => {
+            for (dx, dy, weight) in [
+                (-2, -2, 4.0),
+                (-1, -2, 2.0),
+                (-2, -1, 6.0),
+                (-2, 0, 8.0),
+                (-1, 0, 10.0),
+                (0, 0, -60.0),
+                (1, 0, 10.0),
+                (2, 0, 8.0),
+                (2, 1, 6.0),
+                (1, 2, 2.0),
+                (2, 2, 4.0),
+            ] {
+                set(dx, dy, weight / 60.0);
+            }
+        }
+        _ => {
+            for (dx, dy, weight) in [
+                (-1, -1, 0.15),
+                (0, -1, 0.10),
+                (1, -1, 0.05),
+                (-1, 0, 0.20),
+                (0, 0, -0.80),
+                (1, 0, 0.15),
+                (-1, 1, 0.05),
+                (0, 1, 0.05),
+                (1, 1, 0.05),
+            ] {
+                set(dx, dy, weight);
+            }
+        }
+    }
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernels_sum_to_zero() {
+        for kernel_type in 0..5 {
+            let sum: f32 = kernel_weights(kernel_type).iter().sum();
+            assert!(sum.abs() < 1e-6, "kernel {}: {}", kernel_type, sum);
+        }
+        let solver: Solver = serde_json::from_str(r#"{ "type": "spectral" }"#).unwrap();
+        assert_eq!(solver, Solver::Spectral);
+        let solver: Solver = serde_json::from_str(r#"{ "type": "implicit" }"#).unwrap();
+        assert_eq!(solver, Solver::Implicit { theta: 1.0, cycles: 2 });
+        assert!(Solver::Implicit { theta: 0.3, cycles: 2 }.validate().is_err());
+        assert!(Solver::Implicit { theta: 0.5, cycles: 0 }.validate().is_err());
+    }
+}
@@ -0,0 +1,1020 @@
+// Reaction-diffusion with 3 to 8 species, for systems the two-species
+// Gray-Scott field can't hold: cyclic competition, three-component Turing
+// systems and BZ-like oscillators. Species i follows
+//
+//   dc_i/dt = D_i * laplacian(c_i) + R_i
+//
+// where R_i is either the matrix form c_i * (g_i + sum_j A_ij * c_j) or a user
+// term over the concentrations c0..c7 (see expr.rs). The state lives in its own
+// pair of RGBA32Float textures per ping-pong side; `project` maps it into the
+// two-species field so the existing renderer can draw it.
+
+use std::future::Future;
+
+use serde::{Deserialize, Serialize};
+use wgpu::util::DeviceExt;
+
+use crate::expr::Expr;
+use crate::readback;
+use crate::simulation::{field_shader, Simulation, SplitMix64};
+
+pub const MIN_SPECIES: usize = 3;
+pub const MAX_SPECIES: usize = 8;
+
+const STATE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpeciesModel {
+    pub species: usize,
+    pub diffusion: Vec<f32>, // D_i, one per species
+    // Matrix form: growth g_i and interactions A_ij (rows of `species` entries).
+    // Left empty they're zero.
+    pub growth: Vec<f32>,
+    pub matrix: Vec<Vec<f32>>,
+    // Per-species reaction terms replacing the matrix form; None keeps it
+    pub terms: Vec<Option<String>>,
+    pub delta_time: f32,
+    pub boundary_mode: u32, // 0=wrap, 1=clamp, 2=reflect
+    // Seeding: each species starts at initial[i] plus uniform noise of this
+    // amplitude, drawn once per `patch` x `patch` block of cells
+    pub initial: Vec<f32>,
+    pub noise: f32,
+    pub patch: u32,
+}
+
+impl Default for SpeciesModel {
+    fn default() -> Self {
+        Self {
+            species: MIN_SPECIES,
+            diffusion: vec![0.2; MIN_SPECIES],
+            growth: Vec::new(),
+            matrix: Vec::new(),
+            terms: Vec::new(),
+            delta_time: 1.0,
+            boundary_mode: 0,
+            initial: Vec::new(),
+            noise: 0.5,
+            patch: 1,
+        }
+    }
+}
+
+// How the species are shown
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpeciesView {
+    // One species' concentration
+    Species { index: usize },
+    // sum_i weights[i] * c_i, clamped to 0..1
+    Weighted { weights: Vec<f32> },
+    // Which species is most concentrated, as (index + 1) / species
+    Dominant,
+}
+
+impl Default for SpeciesView {
+    fn default() -> Self {
+        SpeciesView::Species { index: 0 }
+    }
+}
+
+// (name, label) of the built-in models, see species_preset
+pub const SPECIES_PRESETS: [(&str, &str); 3] = [
+    ("cyclic_competition", "Cyclic Competition"),
+    ("bz", "Belousov-Zhabotinsky"),
+    ("competing_spots", "Competing Spots"),
+];
+
+pub fn species_preset(name: &str) -> Option<SpeciesModel> {
+    let name = name.to_ascii_lowercase().replace('-', "_");
+    Some(match name.as_str() {
+        // May-Leonard rock-paper-scissors: logistic growth, each species
+        // suppressed more by its predator than by its prey, giving spirals
+        "cyclic_competition" => SpeciesModel {
+            diffusion: vec![0.2; 3],
+            growth: vec![1.0; 3],
+            matrix: vec![vec![-1.0, -0.8, -1.3], vec![-1.3, -1.0, -0.8], vec![-0.8, -1.3, -1.0]],
+            delta_time: 0.2,
+            initial: vec![0.3; 3],
+            noise: 0.3,
+            ..Default::default()
+        },
+        // Turner's three-species BZ model: a eats b, b eats c, c eats a
+        "bz" => SpeciesModel {
+            diffusion: vec![0.5; 3],
+            matrix: vec![vec![0.0, 1.0, -1.0], vec![-1.0, 0.0, 1.0], vec![1.0, -1.0, 0.0]],
+            delta_time: 1.0,
+            initial: vec![0.5; 3],
+            noise: 0.5,
+            ..Default::default()
+        },
+        // Two Gray-Scott autocatalysts competing for one substrate c0; the
+        // one with the higher kill rate slowly loses ground
+        "competing_spots" => SpeciesModel {
+            diffusion: vec![1.0, 0.5, 0.5],
+            terms: vec![
+                Some("-c0 * c1 * c1 - c0 * c2 * c2 + 0.037 * (1 - c0)".to_string()),
+                Some("c0 * c1 * c1 - (0.037 + 0.06) * c1".to_string()),
+                Some("c0 * c2 * c2 - (0.037 + 0.058) * c2".to_string()),
+            ],
+            delta_time: 1.0,
+            initial: vec![1.0, 0.0, 0.0],
+            noise: 0.5,
+            patch: 8,
+            ..Default::default()
+        },
+        _ => return None,
+    })
+}
+
+// Uniform for species.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpeciesUniform {
+    grid_width: u32,
+    grid_height: u32,
+    count: u32,
+    boundary_mode: u32,
+    delta_time: f32,
+    time: f32,
+    _padding1: u32,
+    _padding2: u32,
+    diffusion: [f32; 8],
+    growth: [f32; 8],
+    matrix: [f32; 64],
+}
+
+// Uniform for species_seed.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SeedUniform {
+    grid_width: u32,
+    grid_height: u32,
+    count: u32,
+    patch_size: u32,
+    noise: f32,
+    seed: u32,
+    _padding1: u32,
+    _padding2: u32,
+    initial: [f32; 8],
+}
+
+// Uniform for species_view.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ViewUniform {
+    grid_width: u32,
+    grid_height: u32,
+    count: u32,
+    mode: u32,
+    weights: [f32; 8],
+}
+
+impl SpeciesModel {
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let model: Self = serde_json::from_str(text).map_err(|e| format!("Invalid species model: {}", e))?;
+        model.validate()?;
+        Ok(model)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let n = self.species;
+        if !(MIN_SPECIES..=MAX_SPECIES).contains(&n) {
+            return Err(format!("species must be between {} and {}", MIN_SPECIES, MAX_SPECIES));
+        }
+        if self.diffusion.len() != n {
+            return Err(format!("diffusion needs {} values", n));
+        }
+        for (name, values) in [("growth", &self.growth), ("terms", &vec![0.0; self.terms.len()]), ("initial", &self.initial)] {
+            if !values.is_empty() && values.len() != n {
+                return Err(format!("{} needs {} values or none", name, n));
+            }
+        }
+        if !self.matrix.is_empty() && (self.matrix.len() != n || self.matrix.iter().any(|row| row.len() != n)) {
+            return Err(format!("matrix needs {} rows of {} values or none", n, n));
+        }
+
+        let numbers = self.diffusion.iter().chain(&self.growth).chain(self.matrix.iter().flatten()).chain(&self.initial);
+        if numbers.chain([&self.noise]).any(|v| !v.is_finite()) {
+            return Err("Species settings must be finite numbers".to_string());
+        }
+        if self.diffusion.iter().any(|&d| d < 0.0) {
+            return Err("diffusion must be non-negative".to_string());
+        }
+        if !(self.delta_time.is_finite() && self.delta_time > 0.0) {
+            return Err("delta_time must be positive".to_string());
+        }
+        if self.patch == 0 {
+            return Err("patch must be at least 1".to_string());
+        }
+        if self.boundary_mode > 2 {
+            return Err("boundary_mode must be below 3".to_string());
+        }
+        self.parse_terms().map(|_| ())
+    }
+
+    fn parse_terms(&self) -> Result<Vec<Option<Expr>>, String> {
+        self.terms
+            .iter()
+            .enumerate()
+            .map(|(i, term)| {
+                term.as_deref()
+                    .map(|text| Expr::parse_reaction(text, self.species).map_err(|e| format!("Reaction term of c{}: {}", i, e)))
+                    .transpose()
+            })
+            .collect()
+    }
+
+    fn seed_uniform(&self, width: u32, height: u32, seed: u64) -> SeedUniform {
+        let mut uniform = SeedUniform {
+            grid_width: width,
+            grid_height: height,
+            count: self.species as u32,
+            patch_size: self.patch,
+            noise: self.noise,
+            seed: SplitMix64(seed).next_u64() as u32,
+            _padding1: 0,
+            _padding2: 0,
+            initial: [0.0; 8],
+        };
+        uniform.initial[..self.initial.len()].copy_from_slice(&self.initial);
+        uniform
+    }
+
+    fn uniform(&self, width: u32, height: u32, time: f32) -> SpeciesUniform {
+        let mut uniform = SpeciesUniform {
+            grid_width: width,
+            grid_height: height,
+            count: self.species as u32,
+            boundary_mode: self.boundary_mode,
+            delta_time: self.delta_time,
+            time,
+            _padding1: 0,
+            _padding2: 0,
+            diffusion: [0.0; 8],
+            growth: [0.0; 8],
+            matrix: [0.0; 64],
+        };
+        uniform.diffusion[..self.species].copy_from_slice(&self.diffusion);
+        uniform.growth[..self.growth.len()].copy_from_slice(&self.growth);
+        for (i, row) in self.matrix.iter().enumerate() {
+            uniform.matrix[i * 8..i * 8 + row.len()].copy_from_slice(row);
+        }
+        uniform
+    }
+}
+
+impl SpeciesView {
+    pub fn validate(&self, species: usize) -> Result<(), String> {
+        match self {
+            SpeciesView::Species { index } if *index >= species => {
+                Err(format!("Species index must be below {}", species))
+            }
+            SpeciesView::Weighted { weights } if weights.len() != species || weights.iter().any(|w| !w.is_finite()) => {
+                Err(format!("Weighted view needs {} finite weights", species))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn uniform(&self, width: u32, height: u32, species: usize) -> ViewUniform {
+        let mut uniform = ViewUniform {
+            grid_width: width,
+            grid_height: height,
+            count: species as u32,
+            mode: 0,
+            weights: [0.0; 8],
+        };
+        match self {
+            SpeciesView::Species { index } => uniform.weights[*index] = 1.0,
+            SpeciesView::Weighted { weights } => uniform.weights[..weights.len()].copy_from_slice(weights),
+            SpeciesView::Dominant => uniform.mode = 1,
+        }
+        uniform
+    }
+}
+
+// One ping-pong side: species 0-3 and 4-7
+struct SpeciesState {
+    lo: wgpu::Texture,
+    hi: wgpu::Texture,
+    lo_view: wgpu::TextureView,
+    hi_view: wgpu::TextureView,
+}
+
+pub struct MultiSpecies {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    width: u32,
+    height: u32,
+    model: SpeciesModel,
+    view: SpeciesView,
+
+    state_a: SpeciesState,
+    state_b: SpeciesState,
+    current_is_a: bool,
+    step_count: u64,
+    has_terms: bool,
+
+    compute_pipeline: wgpu::ComputePipeline,
+    compute_bind_group_a_to_b: wgpu::BindGroup,
+    compute_bind_group_b_to_a: wgpu::BindGroup,
+    params_buffer: wgpu::Buffer,
+
+    seed_pipeline: wgpu::ComputePipeline,
+    seed_bind_group_a: wgpu::BindGroup,
+    seed_bind_group_b: wgpu::BindGroup,
+    seed_buffer: wgpu::Buffer,
+
+    view_pipeline: wgpu::ComputePipeline,
+    view_bind_group_layout: wgpu::BindGroupLayout,
+    view_buffer: wgpu::Buffer,
+}
+
+fn texture_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+fn storage_entry(binding: u32, format: wgpu::TextureFormat) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::StorageTexture {
+            access: wgpu::StorageTextureAccess::WriteOnly,
+            format,
+            view_dimension: wgpu::TextureViewDimension::D2,
+        },
+        count: None,
+    }
+}
+
+fn uniform_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+impl MultiSpecies {
+    // A `width` x `height` grid of zeros; `randomize` or `write_field` seeds it.
+    // `field_format` is the format of the Simulation that `project` writes into.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        model: SpeciesModel,
+        field_format: wgpu::TextureFormat,
+    ) -> Result<Self, String> {
+        model.validate()?;
+        let terms = model.parse_terms()?;
+
+        let create_state = |label: &str| {
+            let texture_desc = wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: STATE_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::STORAGE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            };
+            let lo = device.create_texture(&texture_desc);
+            let hi = device.create_texture(&texture_desc);
+            SpeciesState {
+                lo_view: lo.create_view(&wgpu::TextureViewDescriptor::default()),
+                hi_view: hi.create_view(&wgpu::TextureViewDescriptor::default()),
+                lo,
+                hi,
+            }
+        };
+        let state_a = create_state("Species Texture A");
+        let state_b = create_state("Species Texture B");
+
+        // User terms are compiled in ahead of the matrix form
+        let mut source = include_str!("species.wgsl").to_string();
+        let branches: String = terms
+            .iter()
+            .enumerate()
+            .filter_map(|(i, term)| Some(format!("if i == {}u {{ return {}; }}\n    ", i, term.as_ref()?.to_wgsl())))
+            .collect();
+        source = source.replace("// REACTION_TERMS", &branches);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Species Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let compute_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Species Bind Group Layout"),
+            entries: &[
+                // binding 0: src_lo
+                texture_entry(0),
+                // binding 1: src_hi
+                texture_entry(1),
+                // binding 2: dst_lo
+                storage_entry(2, STATE_FORMAT),
+                // binding 3: dst_hi
+                storage_entry(3, STATE_FORMAT),
+                // binding 4: params uniform buffer
+                uniform_entry(4),
+            ],
+        });
+
+        let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Species Pipeline Layout"),
+            bind_group_layouts: &[&compute_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Species Pipeline"),
+            layout: Some(&compute_pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Species Params Buffer"),
+            size: std::mem::size_of::<SpeciesUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let compute_bind_group = |label: &str, src: &SpeciesState, dst: &SpeciesState| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &compute_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&src.lo_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&src.hi_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&dst.lo_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&dst.hi_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let compute_bind_group_a_to_b = compute_bind_group("Species Bind Group A->B", &state_a, &state_b);
+        let compute_bind_group_b_to_a = compute_bind_group("Species Bind Group B->A", &state_b, &state_a);
+
+        // Seeding straight into either state
+        let seed_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Species Seed Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("species_seed.wgsl").into()),
+        });
+
+        let seed_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Species Seed Bind Group Layout"),
+            entries: &[
+                // binding 0: dst_lo
+                storage_entry(0, STATE_FORMAT),
+                // binding 1: dst_hi
+                storage_entry(1, STATE_FORMAT),
+                // binding 2: seed uniform buffer
+                uniform_entry(2),
+            ],
+        });
+
+        let seed_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Species Seed Pipeline Layout"),
+            bind_group_layouts: &[&seed_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let seed_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Species Seed Pipeline"),
+            layout: Some(&seed_pipeline_layout),
+            module: &seed_shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let seed_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Species Seed Buffer"),
+            size: std::mem::size_of::<SeedUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let seed_bind_group = |label: &str, dst: &SpeciesState| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &seed_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&dst.lo_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&dst.hi_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: seed_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let seed_bind_group_a = seed_bind_group("Species Seed Bind Group A", &state_a);
+        let seed_bind_group_b = seed_bind_group("Species Seed Bind Group B", &state_b);
+
+        // Projection into the two-species field for display
+        let view_shader = field_shader(device, "Species View Shader", include_str!("species_view.wgsl"), field_format);
+
+        let view_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Species View Bind Group Layout"),
+            entries: &[
+                // binding 0: src_lo
+                texture_entry(0),
+                // binding 1: src_hi
+                texture_entry(1),
+                // binding 2: texture_dst (the simulation field)
+                storage_entry(2, field_format),
+                // binding 3: view uniform buffer
+                uniform_entry(3),
+            ],
+        });
+
+        let view_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Species View Pipeline Layout"),
+            bind_group_layouts: &[&view_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let view_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Species View Pipeline"),
+            layout: Some(&view_pipeline_layout),
+            module: &view_shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let view_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Species View Buffer"),
+            size: std::mem::size_of::<ViewUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut species = Self {
+            device: device.clone(),
+            queue: queue.clone(),
+            width,
+            height,
+            has_terms: terms.iter().any(Option::is_some),
+            model,
+            view: SpeciesView::default(),
+            state_a,
+            state_b,
+            current_is_a: true,
+            step_count: 0,
+            compute_pipeline,
+            compute_bind_group_a_to_b,
+            compute_bind_group_b_to_a,
+            params_buffer,
+            seed_pipeline,
+            seed_bind_group_a,
+            seed_bind_group_b,
+            seed_buffer,
+            view_pipeline,
+            view_bind_group_layout,
+            view_buffer,
+        };
+        species.update_params();
+        species.set_view(SpeciesView::default())?;
+        Ok(species)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn model(&self) -> &SpeciesModel {
+        &self.model
+    }
+
+    pub fn view(&self) -> &SpeciesView {
+        &self.view
+    }
+
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    fn current(&self) -> &SpeciesState {
+        if self.current_is_a {
+            &self.state_a
+        } else {
+            &self.state_b
+        }
+    }
+
+    fn update_params(&self) {
+        let params = self.model.uniform(self.width, self.height, self.step_count as f32);
+        self.queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+    }
+
+    pub fn set_view(&mut self, view: SpeciesView) -> Result<(), String> {
+        view.validate(self.model.species)?;
+        let uniform = view.uniform(self.width, self.height, self.model.species);
+        self.queue.write_buffer(&self.view_buffer, 0, bytemuck::cast_slice(&[uniform]));
+        self.view = view;
+        Ok(())
+    }
+
+    // Seeds every cell with the model's initial levels plus noise, on the GPU
+    pub fn randomize(&mut self, seed: u64) {
+        self.seed(self.model.seed_uniform(self.width, self.height, seed));
+    }
+
+    // Sets every cell to the model's initial levels, without noise
+    pub fn clear(&mut self) {
+        let mut uniform = self.model.seed_uniform(self.width, self.height, 0);
+        uniform.noise = 0.0;
+        self.seed(uniform);
+    }
+
+    fn seed(&mut self, uniform: SeedUniform) {
+        self.queue.write_buffer(&self.seed_buffer, 0, bytemuck::cast_slice(&[uniform]));
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Species Seed Encoder"),
+        });
+
+        {
+            let mut seed_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Species Seed Pass"),
+                timestamp_writes: None,
+            });
+
+            seed_pass.set_pipeline(&self.seed_pipeline);
+
+            let bind_group = if self.current_is_a {
+                &self.seed_bind_group_a
+            } else {
+                &self.seed_bind_group_b
+            };
+
+            seed_pass.set_bind_group(0, bind_group, &[]);
+            seed_pass.dispatch_workgroups(self.width.div_ceil(8), self.height.div_ceil(8), 1);
+        }
+
+        self.queue.submit(Some(encoder.finish()));
+        self.step_count = 0;
+        self.update_params();
+    }
+
+    // Replaces the current state with host data: `species` values per cell, row-major
+    pub fn write_field(&mut self, data: &[f32]) -> Result<(), String> {
+        let n = self.model.species;
+        let cells = (self.width * self.height) as usize;
+        if data.len() != cells * n {
+            return Err(format!("Expected {} values for {} species on a {}x{} grid, got {}", cells * n, n, self.width, self.height, data.len()));
+        }
+
+        let mut lo = vec![0.0f32; cells * 4];
+        let mut hi = vec![0.0f32; cells * 4];
+        for (cell, values) in data.chunks(n).enumerate() {
+            for (i, &value) in values.iter().enumerate() {
+                let target = if i < 4 { &mut lo } else { &mut hi };
+                target[cell * 4 + i % 4] = value;
+            }
+        }
+
+        let state = self.current();
+        for (texture, texels) in [(&state.lo, &lo), (&state.hi, &hi)] {
+            self.queue.write_texture(
+                texture.as_image_copy(),
+                bytemuck::cast_slice(texels),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.width * readback::texel_size(STATE_FORMAT)),
+                    rows_per_image: Some(self.height),
+                },
+                texture.size(),
+            );
+        }
+        self.step_count = 0;
+        self.update_params();
+        Ok(())
+    }
+
+    // Copies the current state back: `species` values per cell, row-major
+    pub fn read_field(&self) -> impl Future<Output = Result<Vec<f32>, String>> + 'static {
+        let device = self.device.clone();
+        let queue = self.queue.clone();
+        let state = self.current();
+        let (lo, hi) = (state.lo.clone(), state.hi.clone());
+        let n = self.model.species;
+
+        async move {
+            let lo = readback::read_texture_texels(&device, &queue, &lo).await?;
+            let hi = readback::read_texture_texels(&device, &queue, &hi).await?;
+            Ok(lo
+                .chunks(4)
+                .zip(hi.chunks(4))
+                .flat_map(|(lo, hi)| lo.iter().chain(hi).take(n).copied().collect::<Vec<_>>())
+                .collect())
+        }
+    }
+
+    // Advances all species by `steps` steps
+    pub fn step(&mut self, steps: u32) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Species Encoder"),
+        });
+        self.encode_steps(&mut encoder, steps);
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    // Records `steps` steps into `encoder`, to be submitted with a frame's other passes
+    pub fn encode_steps(&mut self, encoder: &mut wgpu::CommandEncoder, steps: u32) {
+        let (groups_x, groups_y) = (self.width.div_ceil(8), self.height.div_ceil(8));
+
+        // Reaction terms may read t, so each step copies its own time in from
+        // a buffer of them
+        let step_times = self.has_terms.then(|| {
+            let times: Vec<f32> = (0..steps as u64).map(|i| (self.step_count + i) as f32).collect();
+            self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Species Step Times Buffer"),
+                contents: bytemuck::cast_slice(&times),
+                usage: wgpu::BufferUsages::COPY_SRC,
+            })
+        });
+
+        for i in 0..steps {
+            if let Some(step_times) = &step_times {
+                let time_offset = std::mem::offset_of!(SpeciesUniform, time) as u64;
+                encoder.copy_buffer_to_buffer(step_times, i as u64 * 4, &self.params_buffer, time_offset, 4);
+            }
+
+            {
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Species Pass"),
+                    timestamp_writes: None,
+                });
+
+                compute_pass.set_pipeline(&self.compute_pipeline);
+
+                let bind_group = if self.current_is_a {
+                    &self.compute_bind_group_a_to_b
+                } else {
+                    &self.compute_bind_group_b_to_a
+                };
+
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                compute_pass.dispatch_workgroups(groups_x, groups_y, 1);
+            }
+
+            self.current_is_a = !self.current_is_a;
+            self.step_count += 1;
+        }
+    }
+
+    // Writes the current view into the simulation's current field (V channel),
+    // where the renderer, stats and spectrum pick it up
+    pub fn project(&self, sim: &Simulation) -> Result<(), String> {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Species View Encoder"),
+        });
+        self.encode_project(&mut encoder, sim)?;
+        self.queue.submit(Some(encoder.finish()));
+        Ok(())
+    }
+
+    // Records the projection into `encoder`, after any steps recorded there
+    pub fn encode_project(&self, encoder: &mut wgpu::CommandEncoder, sim: &Simulation) -> Result<(), String> {
+        if (sim.width(), sim.height()) != (self.width, self.height) {
+            return Err(format!("Simulation is {}x{}, species grid is {}x{}", sim.width(), sim.height(), self.width, self.height));
+        }
+        let target = if sim.current_is_a() {
+            sim.texture_a_view()
+        } else {
+            sim.texture_b_view()
+        };
+        let state = self.current();
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Species View Bind Group"),
+            layout: &self.view_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&state.lo_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&state.hi_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(target),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.view_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        {
+            let mut view_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Species View Pass"),
+                timestamp_writes: None,
+            });
+
+            view_pass.set_pipeline(&self.view_pipeline);
+            view_pass.set_bind_group(0, &bind_group, &[]);
+            view_pass.dispatch_workgroups(self.width.div_ceil(8), self.height.div_ceil(8), 1);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::headless_simulation;
+
+    #[test]
+    fn models_validate() {
+        for (name, _) in SPECIES_PRESETS {
+            species_preset(name).unwrap().validate().unwrap();
+        }
+        assert!(species_preset("Cyclic-Competition").is_some());
+
+        let model = SpeciesModel::from_json(r#"{ "species": 4, "diffusion": [0.1, 0.1, 0.1, 0.1], "terms": [null, "c3 - c0", null, null] }"#).unwrap();
+        assert_eq!(model.terms[1].as_deref(), Some("c3 - c0"));
+
+        for bad in [
+            r#"{ "species": 2, "diffusion": [0.1, 0.1] }"#,
+            r#"{ "species": 3, "diffusion": [0.1, 0.1] }"#,
+            r#"{ "species": 3, "diffusion": [0.1, 0.1, 0.1], "matrix": [[1, 0, 0], [0, 1]] }"#,
+            r#"{ "species": 3, "diffusion": [0.1, 0.1, 0.1], "terms": [null, "c3", null] }"#,
+            r#"{ "species": 3, "diffusion": [0.1, 0.1, 0.1], "delta_time": 0 }"#,
+        ] {
+            assert!(SpeciesModel::from_json(bad).is_err(), "{}", bad);
+        }
+        assert!(SpeciesView::Weighted { weights: vec![1.0; 2] }.validate(3).is_err());
+        assert!(SpeciesView::Species { index: 3 }.validate(3).is_err());
+    }
+
+    #[test]
+    fn reactions_match_the_cpu() {
+        let Some(sim) = headless_simulation(4, 4) else { return };
+
+        // No diffusion on a uniform field, so every cell follows the same ODE
+        let model = SpeciesModel {
+            species: 5,
+            diffusion: vec![0.0; 5],
+            growth: vec![0.5, 0.0, 0.0, 0.0, 0.1],
+            matrix: (0..5).map(|i| (0..5).map(|j| if i == j { -1.0 } else { 0.2 }).collect()).collect(),
+            terms: vec![None, Some("c0 * c4 - c1".to_string()), None, None, None],
+            delta_time: 0.5,
+            ..Default::default()
+        };
+        let mut species = MultiSpecies::new(sim.device(), sim.queue(), 4, 4, model.clone(), sim.format()).unwrap();
+        let start = [0.2, 0.4, 0.6, 0.3, 0.5];
+        species.write_field(&start.repeat(16)).unwrap();
+        species.step(1);
+
+        let mut expected = start;
+        for i in 0..5 {
+            let rate = match i {
+                1 => start[0] * start[4] - start[1],
+                _ => start[i] * (model.growth[i] + (0..5).map(|j| model.matrix[i][j] * start[j]).sum::<f32>()),
+            };
+            expected[i] = (start[i] + rate * model.delta_time).clamp(0.0, 1.0);
+        }
+
+        let field = pollster::block_on(species.read_field()).unwrap();
+        assert_eq!(field.len(), 16 * 5);
+        for (i, (&gpu, &cpu)) in field[..5].iter().zip(&expected).enumerate() {
+            assert!((gpu - cpu).abs() < 1e-6, "c{}: {} vs {}", i, gpu, cpu);
+        }
+    }
+
+    #[test]
+    fn steps_in_one_encoder_each_see_their_time() {
+        let Some(sim) = headless_simulation(4, 4) else { return };
+        let model = SpeciesModel {
+            diffusion: vec![0.0; 3],
+            terms: vec![Some("t".to_string()), None, None],
+            delta_time: 0.1,
+            ..Default::default()
+        };
+        let mut species = MultiSpecies::new(sim.device(), sim.queue(), 4, 4, model, sim.format()).unwrap();
+        species.write_field(&[0.0, 0.5, 0.5].repeat(16)).unwrap();
+
+        // t = 0, 1, 2 on the three steps, submitted together
+        let mut encoder = sim.device().create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        species.encode_steps(&mut encoder, 3);
+        species.encode_project(&mut encoder, &sim).unwrap();
+        sim.queue().submit(Some(encoder.finish()));
+
+        let field = pollster::block_on(species.read_field()).unwrap();
+        assert!((field[0] - 0.3).abs() < 1e-6, "{}", field[0]);
+        assert_eq!(species.step_count(), 3);
+        assert!((pollster::block_on(sim.read_cell(1, 2)).unwrap().1 - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn randomize_seeds_patches_around_the_initial_levels() {
+        let Some(sim) = headless_simulation(16, 12) else { return };
+        let model = SpeciesModel {
+            species: 5,
+            diffusion: vec![0.1; 5],
+            initial: vec![0.2, 0.5, 0.8, 0.0, 1.0],
+            noise: 0.1,
+            patch: 4,
+            ..Default::default()
+        };
+        let mut species = MultiSpecies::new(sim.device(), sim.queue(), 16, 12, model.clone(), sim.format()).unwrap();
+        let seeded = |species: &mut MultiSpecies, seed: u64| {
+            species.randomize(seed);
+            pollster::block_on(species.read_field()).unwrap()
+        };
+        let field = seeded(&mut species, 3);
+
+        for y in 0..12 {
+            for x in 0..16 {
+                let cell = &field[(y * 16 + x) * 5..][..5];
+                let corner = &field[((y / 4 * 4) * 16 + x / 4 * 4) * 5..][..5];
+                assert_eq!(cell, corner, "({}, {}) differs from its patch", x, y);
+                for (i, &c) in cell.iter().enumerate() {
+                    assert!((0.0..=1.0).contains(&c) && (c - model.initial[i]).abs() <= 0.1 + 1e-6, "c{} = {}", i, c);
+                }
+            }
+        }
+        assert_ne!(field[..5], field[4 * 5..][..5]);
+        assert_eq!(seeded(&mut species, 3), field);
+        assert_ne!(seeded(&mut species, 4), field);
+        assert_eq!(species.step_count(), 0);
+
+        species.clear();
+        let cleared = pollster::block_on(species.read_field()).unwrap();
+        assert!(cleared.chunks(5).all(|cell| cell == model.initial.as_slice()));
+    }
+
+    #[test]
+    fn views_project_into_the_field() {
+        let Some(sim) = headless_simulation(4, 4) else { return };
+        let mut species = MultiSpecies::new(sim.device(), sim.queue(), 4, 4, SpeciesModel::default(), sim.format()).unwrap();
+        species.write_field(&[0.2, 0.7, 0.1].repeat(16)).unwrap();
+
+        let project = |species: &mut MultiSpecies, view: SpeciesView| {
+            species.set_view(view).unwrap();
+            species.project(&sim).unwrap();
+            pollster::block_on(sim.read_cell(1, 2)).unwrap().1
+        };
+        assert_eq!(project(&mut species, SpeciesView::Species { index: 1 }), 0.7);
+        assert!((project(&mut species, SpeciesView::Weighted { weights: vec![1.0, 0.0, 2.0] }) - 0.4).abs() < 1e-6);
+        assert_eq!(project(&mut species, SpeciesView::Dominant), 2.0 / 3.0);
+    }
+}
@@ -0,0 +1,123 @@
+// Multi-species reaction-diffusion compute shader
+// Up to 8 concentrations per cell in two ping-pong RGBA32Float textures
+// (lo = c0..c3, hi = c4..c7). Each species follows
+//   dc_i/dt = D_i * laplacian(c_i) + R_i
+// where R_i is a user reaction term compiled in by MultiSpecies, or by default
+// the matrix form R_i = c_i * (g_i + sum_j A_ij * c_j).
+
+struct SpeciesParams {
+    grid_width: u32,
+    grid_height: u32,
+    count: u32,          // number of species, 3..8
+    boundary_mode: u32,  // 0=wrap, 1=clamp, 2=reflect
+    delta_time: f32,
+    time: f32,           // steps since the field was seeded, for reaction terms
+    _padding1: u32,
+    _padding2: u32,
+    diffusion: array<vec4<f32>, 2>,
+    growth: array<vec4<f32>, 2>,
+    matrix: array<vec4<f32>, 16>, // row i is matrix[2i], matrix[2i + 1]
+}
+
+@group(0) @binding(0) var src_lo: texture_2d<f32>;
+@group(0) @binding(1) var src_hi: texture_2d<f32>;
+@group(0) @binding(2) var dst_lo: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(3) var dst_hi: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(4) var<uniform> params: SpeciesParams;
+
+// Same as apply_boundary in compute.wgsl; neighbours are at most one cell away
+fn apply_boundary(coord: i32, size: i32) -> i32 {
+    if params.boundary_mode == 0u {
+        return (coord + size) % size;
+    } else if params.boundary_mode == 1u {
+        return clamp(coord, 0, size - 1);
+    } else {
+        var c = coord;
+        if c < 0 {
+            c = -c;
+        }
+        if c >= size {
+            c = 2 * (size - 1) - c;
+        }
+        return clamp(c, 0, size - 1);
+    }
+}
+
+fn matrix_entry(i: u32, j: u32) -> f32 {
+    return params.matrix[i * 2u + j / 4u][j % 4u];
+}
+
+fn load_pair(x: i32, y: i32) -> array<vec4<f32>, 2> {
+    let coord = vec2<i32>(
+        apply_boundary(x, i32(params.grid_width)),
+        apply_boundary(y, i32(params.grid_height)),
+    );
+    return array<vec4<f32>, 2>(textureLoad(src_lo, coord, 0), textureLoad(src_hi, coord, 0));
+}
+
+// Default 9-point stencil from compute.wgsl, for all species at once:
+// [0.05, 0.2, 0.05]
+// [0.2, -1.0, 0.2]
+// [0.05, 0.2, 0.05]
+fn laplacian(pos: vec2<i32>) -> array<vec4<f32>, 2> {
+    var sum = array<vec4<f32>, 2>(vec4<f32>(0.0), vec4<f32>(0.0));
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            var weight = 0.05;
+            if dx == 0 && dy == 0 {
+                weight = -1.0;
+            } else if dx == 0 || dy == 0 {
+                weight = 0.2;
+            }
+            let c = load_pair(pos.x + dx, pos.y + dy);
+            sum[0] += c[0] * weight;
+            sum[1] += c[1] * weight;
+        }
+    }
+    return sum;
+}
+
+// Reaction term of species i. MultiSpecies compiles user terms in at the marker.
+fn reaction(i: u32, x: f32, y: f32, t: f32, r: f32, theta: f32, values: array<f32, 8>) -> f32 {
+    var c = values;
+    // REACTION_TERMS
+    var rate = params.growth[i / 4u][i % 4u];
+    for (var j = 0u; j < params.count; j++) {
+        rate += matrix_entry(i, j) * c[j];
+    }
+    return c[i] * rate;
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if global_id.x >= params.grid_width || global_id.y >= params.grid_height {
+        return;
+    }
+
+    let pos = vec2<i32>(global_id.xy);
+    let lo = textureLoad(src_lo, pos, 0);
+    let hi = textureLoad(src_hi, pos, 0);
+    var lap = laplacian(pos);
+
+    var values: array<f32, 8>;
+    for (var i = 0u; i < 4u; i++) {
+        values[i] = lo[i];
+        values[i + 4u] = hi[i];
+    }
+
+    let norm_x = f32(pos.x) / f32(params.grid_width);
+    let norm_y = f32(pos.y) / f32(params.grid_height);
+    let r = distance(vec2<f32>(norm_x, norm_y), vec2<f32>(0.5));
+    let theta = atan2(norm_y - 0.5, norm_x - 0.5);
+
+    var next = array<vec4<f32>, 2>(vec4<f32>(0.0), vec4<f32>(0.0));
+    for (var i = 0u; i < params.count; i++) {
+        let rate = params.diffusion[i / 4u][i % 4u] * lap[i / 4u][i % 4u]
+            + reaction(i, norm_x, norm_y, params.time, r, theta, values);
+        // Forward Euler, clamped to the valid range like the two-species model
+        next[i / 4u][i % 4u] = clamp(values[i] + rate * params.delta_time, 0.0, 1.0);
+    }
+
+    textureStore(dst_lo, pos, next[0]);
+    textureStore(dst_hi, pos, next[1]);
+}
@@ -0,0 +1,43 @@
+// Seeds the multi-species state on the GPU: each species starts at its
+// initial level plus uniform noise, drawn once per `patch` x `patch` block of
+// cells, so seeding doesn't build and upload the whole grid from the CPU.
+
+struct SeedParams {
+    grid_width: u32,
+    grid_height: u32,
+    count: u32,          // number of species, 3..8
+    patch_size: u32,     // cells per side of a noise patch
+    noise: f32,          // amplitude of the noise around each initial level
+    seed: u32,
+    _padding1: u32,
+    _padding2: u32,
+    initial: array<vec4<f32>, 2>,
+}
+
+@group(0) @binding(0) var dst_lo: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(1) var dst_hi: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(2) var<uniform> params: SeedParams;
+
+// Same hash as init.wgsl, in [0, 1]
+fn hash(p: vec2<u32>) -> f32 {
+    var h = p.x * 374761393u + p.y * 668265263u;
+    h = (h ^ (h >> 13u)) * 1274126177u;
+    return f32(h) / 4294967295.0;
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if global_id.x >= params.grid_width || global_id.y >= params.grid_height {
+        return;
+    }
+
+    let p = (global_id.xy / params.patch_size) ^ vec2<u32>(params.seed, params.seed * 2654435761u);
+    var values = array<vec4<f32>, 2>(vec4<f32>(0.0), vec4<f32>(0.0));
+    for (var i = 0u; i < params.count; i++) {
+        let noise = (hash(p + vec2<u32>(i * 7919u, i * 104729u)) * 2.0 - 1.0) * params.noise;
+        values[i / 4u][i % 4u] = clamp(params.initial[i / 4u][i % 4u] + noise, 0.0, 1.0);
+    }
+
+    textureStore(dst_lo, vec2<i32>(global_id.xy), values[0]);
+    textureStore(dst_hi, vec2<i32>(global_id.xy), values[1]);
+}
@@ -0,0 +1,49 @@
+// Maps the multi-species state to a single value for the gradient renderer.
+// The result goes into the V channel of the two-species field texture, so the
+// renderer, stats and spectrum work on it unchanged.
+
+struct ViewParams {
+    grid_width: u32,
+    grid_height: u32,
+    count: u32,
+    mode: u32,  // 0=weighted sum, 1=dominant species
+    weights: array<vec4<f32>, 2>,
+}
+
+@group(0) @binding(0) var src_lo: texture_2d<f32>;
+@group(0) @binding(1) var src_hi: texture_2d<f32>;
+@group(0) @binding(2) var texture_dst: texture_storage_2d<rg32float, write>;
+@group(0) @binding(3) var<uniform> view: ViewParams;
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if global_id.x >= view.grid_width || global_id.y >= view.grid_height {
+        return;
+    }
+
+    let pos = vec2<i32>(global_id.xy);
+    let lo = textureLoad(src_lo, pos, 0);
+    let hi = textureLoad(src_hi, pos, 0);
+
+    var value = dot(lo, view.weights[0]) + dot(hi, view.weights[1]);
+
+    if view.mode == 1u {
+        // Index of the most concentrated species, spread evenly over (0, 1]
+        var best = 0u;
+        var best_value = -1.0;
+        for (var i = 0u; i < view.count; i++) {
+            var c = lo[i % 4u];
+            if i >= 4u {
+                c = hi[i % 4u];
+            }
+            if c > best_value {
+                best = i;
+                best_value = c;
+            }
+        }
+        value = f32(best + 1u) / f32(view.count);
+    }
+
+    value = clamp(value, 0.0, 1.0);
+    textureStore(texture_dst, pos, vec4<f32>(1.0 - value, value, 0.0, 1.0));
+}
@@ -0,0 +1,343 @@
+// GPU resources for Solver::Spectral (see solver.rs). Each step is
+//
+//   1. reaction_step (compute.wgsl): u + dt R(u) into the FFT input, packed U + iV
+//   2. forward FFT (fft.rs)
+//   3. diffuse (spectral.wgsl): scale by exp(dt D L(k)) per species
+//   4. inverse FFT
+//   5. store_field (spectral.wgsl): clamp and write the next field texture
+//
+// The reaction pass uses the simulation's own compute shader, so F/k
+// expressions, the parameter map, noise and advection all carry over.
+
+use crate::fft::Fft2d;
+use crate::simulation::{field_shader, Simulation};
+use crate::solver::kernel_weights;
+
+// Uniform for spectral.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpectralUniform {
+    grid_width: u32,
+    grid_height: u32,
+    _padding1: u32,
+    _padding2: u32,
+    diffuse_u: f32,
+    diffuse_v: f32,
+    delta_time: f32,
+    _padding3: f32,
+    kernel: [f32; 28],
+}
+
+// Why the spectral solver can't step `sim` as it's set up, if it can't
+pub(crate) fn unsupported_reason(sim: &Simulation) -> Option<String> {
+    if sim.boundary_mode != 0 {
+        Some("the spectral solver needs wrap boundaries".to_string())
+    } else if !sim.equation.is_gray_scott() {
+        Some("the spectral solver only integrates Gray-Scott".to_string())
+    } else if !(sim.width().is_power_of_two() && sim.height().is_power_of_two()) {
+        Some(format!("the spectral solver needs a power-of-two grid, not {}x{}", sim.width(), sim.height()))
+    } else {
+        None
+    }
+}
+
+pub(crate) struct SpectralSolver {
+    fft: Fft2d,
+    params_buffer: wgpu::Buffer,
+
+    reaction_pipeline: wgpu::ComputePipeline,
+    reaction_bind_group: wgpu::BindGroup, // group 1: the FFT input
+
+    diffuse_pipeline: wgpu::ComputePipeline,
+    store_pipeline: wgpu::ComputePipeline,
+    bind_group_to_a: wgpu::BindGroup, // FFT output -> A
+    bind_group_to_b: wgpu::BindGroup, // FFT output -> B
+}
+
+impl SpectralSolver {
+    // Builds the passes for `sim`'s grid and current compute shader. Fails if
+    // unsupported_reason does.
+    pub(crate) fn new(sim: &Simulation) -> Result<Self, String> {
+        if let Some(reason) = unsupported_reason(sim) {
+            return Err(reason);
+        }
+        let device = sim.device();
+        let fft = Fft2d::new(device, sim.width(), sim.height())?;
+
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        // Reaction: compute.wgsl's reaction_step entry point
+        let reaction_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Spectral Reaction Bind Group Layout"),
+            entries: &[
+                // binding 0: reacted_field (the FFT input)
+                storage_entry(0),
+            ],
+        });
+
+        let reaction_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Spectral Reaction Pipeline Layout"),
+            bind_group_layouts: &[sim.pipelines().compute_bind_group_layout(), &reaction_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let reaction_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Spectral Reaction Pipeline"),
+            layout: Some(&reaction_pipeline_layout),
+            module: sim.compute_shader(),
+            entry_point: Some("reaction_step"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let reaction_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Spectral Reaction Bind Group"),
+            layout: &reaction_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: fft.input_buffer().as_entire_binding(),
+            }],
+        });
+
+        // Diffusion and store: spectral.wgsl
+        let shader = field_shader(device, "Spectral Shader", include_str!("spectral.wgsl"), sim.format());
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Spectral Bind Group Layout"),
+            entries: &[
+                // binding 0: spectrum (the FFT output)
+                storage_entry(0),
+                // binding 1: params uniform buffer
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 2: texture_dst
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: sim.format(),
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Spectral Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let create_pipeline = |label, entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let diffuse_pipeline = create_pipeline("Spectral Diffuse Pipeline", "diffuse");
+        let store_pipeline = create_pipeline("Spectral Store Pipeline", "store_field");
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Spectral Params Buffer"),
+            size: std::mem::size_of::<SpectralUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let create_bind_group = |label, dst: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: fft.output_buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(dst),
+                    },
+                ],
+            })
+        };
+        let bind_group_to_a = create_bind_group("Spectral Bind Group ->A", sim.texture_a_view());
+        let bind_group_to_b = create_bind_group("Spectral Bind Group ->B", sim.texture_b_view());
+
+        let solver = Self {
+            fft,
+            params_buffer,
+            reaction_pipeline,
+            reaction_bind_group,
+            diffuse_pipeline,
+            store_pipeline,
+            bind_group_to_a,
+            bind_group_to_b,
+        };
+        solver.update_params(sim);
+        Ok(solver)
+    }
+
+    // Copies the diffusion rates, time step and kernel from the simulation
+    pub(crate) fn update_params(&self, sim: &Simulation) {
+        let mut kernel = [0.0; 28];
+        kernel[..25].copy_from_slice(&kernel_weights(sim.kernel_type));
+        let params = SpectralUniform {
+            grid_width: sim.width(),
+            grid_height: sim.height(),
+            _padding1: 0,
+            _padding2: 0,
+            diffuse_u: sim.diffuse_u,
+            diffuse_v: sim.diffuse_v,
+            delta_time: sim.delta_time,
+            _padding3: 0.0,
+            kernel,
+        };
+        sim.queue().write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+    }
+
+    // Records one step reading the field through `compute_bind_group` (the one
+    // the explicit solver would use) and writing texture A or B
+    pub(crate) fn encode(&self, encoder: &mut wgpu::CommandEncoder, compute_bind_group: &wgpu::BindGroup, to_a: bool, workgroups: (u32, u32)) {
+        let (groups_x, groups_y) = workgroups;
+        let bind_group = if to_a { &self.bind_group_to_a } else { &self.bind_group_to_b };
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Spectral Forward Pass"),
+                timestamp_writes: None,
+            });
+
+            pass.set_pipeline(&self.reaction_pipeline);
+            pass.set_bind_group(0, compute_bind_group, &[]);
+            pass.set_bind_group(1, &self.reaction_bind_group, &[]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+
+            self.fft.encode(&mut pass, false);
+
+            pass.set_pipeline(&self.diffuse_pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+
+        // With an odd number of FFT stages the spectrum ends up in the other buffer
+        let (input, output) = (self.fft.input_buffer(), self.fft.output_buffer());
+        if input != output {
+            encoder.copy_buffer_to_buffer(output, 0, input, 0, input.size());
+        }
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Spectral Inverse Pass"),
+                timestamp_writes: None,
+            });
+
+            self.fft.encode(&mut pass, true);
+
+            pass.set_pipeline(&self.store_pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::{headless_simulation, SplitMix64};
+    use crate::solver::Solver;
+
+    #[test]
+    fn small_steps_match_the_explicit_solver() {
+        let Some(mut sim) = headless_simulation(32, 16) else { return };
+        let mut rng = SplitMix64(11);
+        let start: Vec<f32> = (0..32 * 16 * 2).map(|_| rng.next_f32()).collect();
+        sim.delta_time = 0.002;
+
+        // Every kernel, including the asymmetric one whose symbol is complex
+        for kernel_type in 0..5 {
+            sim.kernel_type = kernel_type;
+            let mut run = |solver| {
+                sim.solver = solver;
+                sim.update_params();
+                sim.write_field(&start).unwrap();
+                sim.step(1);
+                pollster::block_on(sim.read_field()).unwrap()
+            };
+            let explicit = run(Solver::Explicit);
+            let spectral = run(Solver::Spectral);
+            for (i, (a, b)) in explicit.iter().zip(&spectral).enumerate() {
+                assert!((a - b).abs() < 1e-4, "kernel {}, value {}: {} vs {}", kernel_type, i, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn diffusion_is_exact_at_large_time_steps() {
+        let Some(mut sim) = headless_simulation(32, 16) else { return };
+        // No V and no feed, so U only diffuses
+        sim.feed_rate = 0.0;
+        sim.kill_rate = 0.0;
+        sim.delta_time = 50.0;
+        sim.solver = Solver::Spectral;
+        sim.update_params();
+
+        let wave = |x: u32| (2.0 * std::f32::consts::PI * 3.0 * x as f32 / 32.0).cos();
+        let start: Vec<f32> = (0..32 * 16).flat_map(|i| [0.5 + 0.1 * wave(i % 32), 0.0]).collect();
+        sim.write_field(&start).unwrap();
+        sim.step(1);
+
+        // The default kernel's symbol at (3, 0) is -0.6 + 0.6 cos(2 pi 3 / 32)
+        let symbol = -0.6 + 0.6 * (2.0 * std::f32::consts::PI * 3.0 / 32.0).cos();
+        let amplitude = 0.1 * (symbol * sim.delta_time).exp();
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        for (i, uv) in field.chunks(2).enumerate() {
+            let expected = 0.5 + amplitude * wave(i as u32 % 32);
+            assert!((uv[0] - expected).abs() < 1e-5, "cell {}: {} vs {}", i, uv[0], expected);
+            assert!(uv[1].abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn unsupported_setups_fall_back() {
+        let Some(mut sim) = headless_simulation(32, 16) else { return };
+        sim.solver = Solver::Spectral;
+        assert!(sim.solver_fallback().is_none());
+        sim.boundary_mode = 1;
+        assert!(sim.solver_fallback().is_some());
+        sim.update_params();
+        sim.step(2);
+        assert!(sim.current_is_a());
+
+        let Some(mut sim) = headless_simulation(48, 16) else { return };
+        sim.solver = Solver::Spectral;
+        assert!(sim.solver_fallback().unwrap().contains("power-of-two"));
+        sim.update_params();
+        sim.step(1);
+    }
+}
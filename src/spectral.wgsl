@@ -0,0 +1,99 @@
+// Spectral diffusion for the integrating-factor solver (spectral.rs).
+// The field is packed as the complex number Z = U + iV and transformed with one
+// FFT. U and V diffuse at different rates, so `diffuse` splits each pair of
+// mirrored frequencies k, -k back into the two spectra:
+//   U(k) = (Z(k) + conj(Z(-k))) / 2,  V(k) = (Z(k) - conj(Z(-k))) / 2i
+// scales them by exp(dt D L(k)), with L the Fourier symbol of the Laplacian
+// kernel, and packs them again. `store_field` writes the inverse transform back
+// to the field texture.
+
+struct SpectralParams {
+    grid_width: u32,
+    grid_height: u32,
+    _padding1: u32,
+    _padding2: u32,
+    diffuse_u: f32,
+    diffuse_v: f32,
+    delta_time: f32,
+    _padding3: f32,
+    kernel: array<vec4<f32>, 7>, // 5x5 weights row-major from (-2, -2), see solver.rs
+}
+
+@group(0) @binding(0) var<storage, read_write> spectrum: array<vec2<f32>>;
+@group(0) @binding(1) var<uniform> params: SpectralParams;
+@group(0) @binding(2) var texture_dst: texture_storage_2d<rg32float, write>;
+
+const PI: f32 = 3.14159265358979;
+
+fn complex_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+fn conj(a: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x, -a.y);
+}
+
+// L(k) = sum of w(d) exp(2 pi i k.d / N): shifting by d multiplies the forward
+// transform (exp(-2 pi i k x / N)) by exp(2 pi i k.d / N)
+fn symbol(k: vec2<u32>) -> vec2<f32> {
+    let frequency = 2.0 * PI * vec2<f32>(k) / vec2<f32>(f32(params.grid_width), f32(params.grid_height));
+    var sum = vec2<f32>(0.0);
+    for (var i = 0; i < 25; i++) {
+        let weight = params.kernel[i / 4][i % 4];
+        if weight != 0.0 {
+            let phase = dot(frequency, vec2<f32>(f32(i % 5 - 2), f32(i / 5 - 2)));
+            sum += weight * vec2<f32>(cos(phase), sin(phase));
+        }
+    }
+    return sum;
+}
+
+// exp(dt D L) for a complex L
+fn decay(lambda: vec2<f32>, rate: f32) -> vec2<f32> {
+    let exponent = lambda * rate * params.delta_time;
+    return exp(exponent.x) * vec2<f32>(cos(exponent.y), sin(exponent.y));
+}
+
+@compute @workgroup_size(8, 8)
+fn diffuse(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let width = params.grid_width;
+    let height = params.grid_height;
+    if global_id.x >= width || global_id.y >= height {
+        return;
+    }
+
+    // Each pair is handled once, by the thread of the lower index
+    let mirror = vec2<u32>((width - global_id.x) % width, (height - global_id.y) % height);
+    let index = global_id.y * width + global_id.x;
+    let mirror_index = mirror.y * width + mirror.x;
+    if mirror_index < index {
+        return;
+    }
+
+    let z = spectrum[index];
+    let z_mirror = conj(spectrum[mirror_index]);
+    let u = (z + z_mirror) * 0.5;
+    let v_times_2i = z - z_mirror;
+    let v = vec2<f32>(v_times_2i.y, -v_times_2i.x) * 0.5;
+
+    let lambda = symbol(global_id.xy);
+    let new_u = complex_mul(decay(lambda, params.diffuse_u), u);
+    let new_v = complex_mul(decay(lambda, params.diffuse_v), v);
+
+    // U(-k) = conj(U(k)) for real fields, and likewise for V
+    let i_v = vec2<f32>(-new_v.y, new_v.x);
+    let i_v_mirror = vec2<f32>(new_v.y, new_v.x);
+    spectrum[index] = new_u + i_v;
+    spectrum[mirror_index] = conj(new_u) + i_v_mirror;
+}
+
+@compute @workgroup_size(8, 8)
+fn store_field(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if global_id.x >= params.grid_width || global_id.y >= params.grid_height {
+        return;
+    }
+
+    let uv = spectrum[global_id.y * params.grid_width + global_id.x];
+    let result = clamp(uv, vec2<f32>(0.0), vec2<f32>(1.0));
+    textureStore(texture_dst, vec2<i32>(global_id.xy), vec4<f32>(result, 0.0, 1.0));
+}
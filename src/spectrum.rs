@@ -0,0 +1,450 @@
+// Radially averaged power spectrum of the V field (spectrum.wgsl + fft.rs), used
+// to tell pattern classes apart and to measure their characteristic wavelength.
+// The analysis runs on the largest power-of-two square in the top-left corner of
+// the grid; on a periodic grid of power-of-two size that is the whole field.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use wgpu::util::DeviceExt;
+
+use crate::fft::Fft2d;
+use crate::readback;
+use crate::simulation::{self, Simulation};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpectrumParams {
+    size: u32,
+    num_bins: u32,
+    _padding1: u32,
+    _padding2: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrum {
+    pub size: u32,              // side of the analysed square, in cells
+    pub radial_power: Vec<f32>, // mean power per ring; ring r holds r cycles per `size` cells
+}
+
+impl Spectrum {
+    // Ring with the most power, ignoring the mean (ring 0). None for a flat field.
+    pub fn dominant_bin(&self) -> Option<usize> {
+        let (bin, &power) = self
+            .radial_power
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        (power > 1e-12).then_some(bin)
+    }
+
+    // Wavelength in cells of the strongest ring, refined between rings by fitting
+    // a parabola through the peak and its neighbours
+    pub fn dominant_wavelength(&self) -> Option<f32> {
+        let bin = self.dominant_bin()?;
+        let mut peak = bin as f32;
+        if let (Some(&left), Some(&right)) = (self.radial_power.get(bin - 1), self.radial_power.get(bin + 1)) {
+            let center = self.radial_power[bin];
+            let curvature = left - 2.0 * center + right;
+            if bin > 1 && curvature < 0.0 {
+                peak += (0.5 * (left - right) / curvature).clamp(-0.5, 0.5);
+            }
+        }
+        Some(self.size as f32 / peak)
+    }
+}
+
+// Largest power of two that fits in both grid dimensions
+pub fn analysis_size(width: u32, height: u32) -> u32 {
+    let side = width.min(height);
+    if side < 2 {
+        0
+    } else {
+        1 << side.ilog2()
+    }
+}
+
+pub struct SpectrumAnalyzer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    size: u32,
+    num_bins: u32,
+
+    fft: Fft2d,
+    load_pipeline: wgpu::ComputePipeline,
+    log_magnitude_pipeline: wgpu::ComputePipeline,
+    radial_rows_pipeline: wgpu::ComputePipeline,
+    radial_sum_pipeline: wgpu::ComputePipeline,
+    load_bind_group_a: wgpu::BindGroup,
+    load_bind_group_b: wgpu::BindGroup,
+    analysis_bind_group: wgpu::BindGroup,
+
+    radial_buffer: wgpu::Buffer,
+    // Cells per ring, to turn the ring sums into means
+    bin_counts: Arc<Vec<u32>>,
+
+    // Log-magnitude spectrum for display, same format as the field textures
+    #[allow(dead_code)] // Kept alive for the view
+    view_texture: wgpu::Texture,
+    view_texture_view: wgpu::TextureView,
+}
+
+impl SpectrumAnalyzer {
+    pub fn new(sim: &Simulation) -> Result<Self, String> {
+        let device = sim.device();
+        let size = analysis_size(sim.width(), sim.height());
+        let fft = Fft2d::new(device, size, size)?;
+        let num_bins = size / 2 + 1;
+
+        let shader = simulation::field_shader(device, "Spectrum Shader", include_str!("spectrum.wgsl"), sim.format());
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Spectrum Params Buffer"),
+            contents: bytemuck::cast_slice(&[SpectrumParams {
+                size,
+                num_bins,
+                _padding1: 0,
+                _padding2: 0,
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let row_bins_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Spectrum Row Bins Buffer"),
+            size: size as u64 * num_bins as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let radial_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Spectrum Radial Buffer"),
+            size: num_bins as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let view_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Spectrum View Texture"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: sim.format(),
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view_texture_view = view_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let params_entry = wgpu::BindGroupLayoutEntry {
+            binding: 2,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let load_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Spectrum Load Bind Group Layout"),
+            entries: &[
+                // binding 0: field texture
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // binding 1: FFT input buffer
+                storage_entry(1, false),
+                // binding 2: params uniform buffer
+                params_entry,
+            ],
+        });
+
+        let analysis_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Spectrum Analysis Bind Group Layout"),
+            entries: &[
+                // binding 2: params uniform buffer
+                params_entry,
+                // binding 3: FFT output buffer
+                storage_entry(3, true),
+                // binding 4: log-magnitude view texture
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: sim.format(),
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // binding 5: per-row ring sums
+                storage_entry(5, false),
+                // binding 6: ring sums
+                storage_entry(6, false),
+            ],
+        });
+
+        let create_pipeline = |label, layout: &wgpu::BindGroupLayout, entry_point| {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let load_pipeline = create_pipeline("Spectrum Load Pipeline", &load_bind_group_layout, "load_field");
+        let log_magnitude_pipeline =
+            create_pipeline("Spectrum Log Magnitude Pipeline", &analysis_bind_group_layout, "log_magnitude");
+        let radial_rows_pipeline =
+            create_pipeline("Spectrum Radial Rows Pipeline", &analysis_bind_group_layout, "radial_rows");
+        let radial_sum_pipeline =
+            create_pipeline("Spectrum Radial Sum Pipeline", &analysis_bind_group_layout, "radial_sum");
+
+        let create_load_bind_group = |label, view| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &load_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: fft.input_buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+
+        let load_bind_group_a = create_load_bind_group("Spectrum Load Bind Group A", sim.texture_a_view());
+        let load_bind_group_b = create_load_bind_group("Spectrum Load Bind Group B", sim.texture_b_view());
+
+        let analysis_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Spectrum Analysis Bind Group"),
+            layout: &analysis_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: fft.output_buffer().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&view_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: row_bins_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: radial_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        // Same ring assignment as radial_rows in spectrum.wgsl
+        let wavenumber = |i: u32| if i >= size / 2 { i as i64 - size as i64 } else { i as i64 };
+        let mut bin_counts = vec![0u32; num_bins as usize];
+        for y in 0..size {
+            let ky = wavenumber(y) as f32;
+            for x in 0..size {
+                let kx = wavenumber(x) as f32;
+                let r = (kx * kx + ky * ky).sqrt().round() as usize;
+                if r < bin_counts.len() {
+                    bin_counts[r] += 1;
+                }
+            }
+        }
+
+        Ok(Self {
+            device: device.clone(),
+            queue: sim.queue().clone(),
+            size,
+            num_bins,
+            fft,
+            load_pipeline,
+            log_magnitude_pipeline,
+            radial_rows_pipeline,
+            radial_sum_pipeline,
+            load_bind_group_a,
+            load_bind_group_b,
+            analysis_bind_group,
+            radial_buffer,
+            bin_counts: Arc::new(bin_counts),
+            view_texture,
+            view_texture_view,
+        })
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    // Centred log-magnitude spectrum in the V channel, refreshed by `compute`
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view_texture_view
+    }
+
+    // Transforms the simulation's current field and updates the view texture
+    // and the ring sums
+    pub fn compute(&self, sim: &Simulation) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Spectrum Encoder"),
+        });
+        self.encode(&mut encoder, sim);
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    // Records `compute`'s passes into `encoder`
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, sim: &Simulation) {
+        let mut spectrum_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Spectrum Pass"),
+            timestamp_writes: None,
+        });
+
+        let load_bind_group = if sim.current_is_a() {
+            &self.load_bind_group_a
+        } else {
+            &self.load_bind_group_b
+        };
+        let groups = self.size.div_ceil(8);
+
+        spectrum_pass.set_pipeline(&self.load_pipeline);
+        spectrum_pass.set_bind_group(0, load_bind_group, &[]);
+        spectrum_pass.dispatch_workgroups(groups, groups, 1);
+
+        self.fft.encode(&mut spectrum_pass, false);
+
+        spectrum_pass.set_bind_group(0, &self.analysis_bind_group, &[]);
+        spectrum_pass.set_pipeline(&self.log_magnitude_pipeline);
+        spectrum_pass.dispatch_workgroups(groups, groups, 1);
+
+        spectrum_pass.set_pipeline(&self.radial_rows_pipeline);
+        spectrum_pass.dispatch_workgroups(self.size.div_ceil(64), 1, 1);
+
+        spectrum_pass.set_pipeline(&self.radial_sum_pipeline);
+        spectrum_pass.dispatch_workgroups(self.num_bins.div_ceil(64), 1, 1);
+    }
+
+    // Runs `compute` and reads the radial spectrum back
+    pub fn analyze(&self, sim: &Simulation) -> impl Future<Output = Result<Spectrum, String>> + 'static {
+        self.compute(sim);
+
+        let device = self.device.clone();
+        let queue = self.queue.clone();
+        let radial_buffer = self.radial_buffer.clone();
+        let bin_counts = self.bin_counts.clone();
+        let size = self.size;
+
+        async move {
+            let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Spectrum Staging Buffer"),
+                size: radial_buffer.size(),
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Spectrum Readback Encoder"),
+            });
+            encoder.copy_buffer_to_buffer(&radial_buffer, 0, &staging_buffer, 0, radial_buffer.size());
+            queue.submit(Some(encoder.finish()));
+
+            readback::map_buffer(&device, &staging_buffer).await?;
+
+            let radial_power = {
+                let mapped = staging_buffer.slice(..).get_mapped_range();
+                let sums: &[f32] = bytemuck::cast_slice(&mapped);
+                sums.iter()
+                    .zip(bin_counts.iter())
+                    .map(|(&sum, &count)| if count > 0 { sum / count as f32 } else { 0.0 })
+                    .collect()
+            };
+            staging_buffer.unmap();
+
+            Ok(Spectrum { size, radial_power })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::headless_simulation;
+
+    #[test]
+    fn stripes_peak_at_their_wavelength() {
+        let Some(mut sim) = headless_simulation(64, 64) else { return };
+        let analyzer = SpectrumAnalyzer::new(&sim).unwrap();
+
+        // Diagonal stripes with wavenumber (6, 8), i.e. ring 10 and wavelength 6.4
+        let data: Vec<f32> = (0..64 * 64)
+            .flat_map(|i| {
+                let (x, y) = ((i % 64) as f32, (i / 64) as f32);
+                let phase = 2.0 * std::f32::consts::PI * (6.0 * x + 8.0 * y) / 64.0;
+                [1.0, 0.25 + 0.2 * phase.cos()]
+            })
+            .collect();
+        sim.write_field(&data).unwrap();
+
+        let spectrum = pollster::block_on(analyzer.analyze(&sim)).unwrap();
+        assert_eq!(spectrum.size, 64);
+        assert_eq!(spectrum.radial_power.len(), 33);
+        assert_eq!(spectrum.dominant_bin(), Some(10));
+        assert!((spectrum.dominant_wavelength().unwrap() - 6.4).abs() < 0.1);
+
+        // Mean power 0.25^2 sits alone in ring 0
+        assert!((spectrum.radial_power[0] - 0.0625).abs() < 1e-4);
+    }
+
+    #[test]
+    fn uniform_field_has_no_dominant_wavelength() {
+        let Some(mut sim) = headless_simulation(40, 48) else { return };
+        let analyzer = SpectrumAnalyzer::new(&sim).unwrap();
+        assert_eq!(analyzer.size(), 32);
+
+        sim.init_field(&crate::simulation::InitParams::uniform());
+        let spectrum = pollster::block_on(analyzer.analyze(&sim)).unwrap();
+        assert_eq!(spectrum.dominant_wavelength(), None);
+    }
+}
@@ -0,0 +1,98 @@
+// Power spectrum of the V channel, built around the FFT in fft.wgsl.
+// load_field copies an N x N block of V into the FFT input buffer; after the FFT,
+// log_magnitude draws the centred log power spectrum into a texture and the two
+// radial passes sum the power into rings of integer wavenumber.
+//
+// Power is normalised as |X / N^2|^2, i.e. the squared amplitude of each Fourier
+// coefficient, so a field V = a*cos(...) puts a^2/4 into each of its two peaks.
+
+struct SpectrumParams {
+    size: u32,          // N, the side of the analysed square
+    num_bins: u32,      // N/2 + 1 radial bins
+    _padding1: u32,
+    _padding2: u32,
+}
+
+// load_field
+@group(0) @binding(0) var field: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> fft_input: array<vec2<f32>>;
+@group(0) @binding(2) var<uniform> params: SpectrumParams;
+
+// log_magnitude, radial_rows, radial_sum
+@group(0) @binding(3) var<storage, read> fft_output: array<vec2<f32>>;
+@group(0) @binding(4) var spectrum_view: texture_storage_2d<rg32float, write>;
+@group(0) @binding(5) var<storage, read_write> row_bins: array<f32>;
+@group(0) @binding(6) var<storage, read_write> radial: array<f32>;
+
+fn power_at(index: u32) -> f32 {
+    let n2 = f32(params.size * params.size);
+    let c = fft_output[index] / n2;
+    return dot(c, c);
+}
+
+// Signed wavenumber of FFT index i
+fn wavenumber(i: u32) -> i32 {
+    let n = i32(params.size);
+    let k = i32(i);
+    return select(k, k - n, k >= n / 2);
+}
+
+@compute @workgroup_size(8, 8)
+fn load_field(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if global_id.x >= params.size || global_id.y >= params.size {
+        return;
+    }
+    let v = textureLoad(field, vec2<i32>(global_id.xy), 0).g;
+    fft_input[global_id.y * params.size + global_id.x] = vec2<f32>(v, 0.0);
+}
+
+// Maps power 1e-8..1 onto 0..1 in V, with zero frequency in the middle of the texture
+@compute @workgroup_size(8, 8)
+fn log_magnitude(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let n = params.size;
+    if global_id.x >= n || global_id.y >= n {
+        return;
+    }
+    let source = (global_id.xy + vec2<u32>(n / 2u)) % vec2<u32>(n);
+    let power = power_at(source.y * n + source.x);
+    let level = clamp(log2(max(power, 1e-20)) / (8.0 * log2(10.0)) + 1.0, 0.0, 1.0);
+    textureStore(spectrum_view, vec2<i32>(global_id.xy), vec4<f32>(1.0 - level, level, 0.0, 1.0));
+}
+
+// One invocation per spectrum row: sums the row's power into its own set of bins
+@compute @workgroup_size(64)
+fn radial_rows(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let y = global_id.x;
+    if y >= params.size {
+        return;
+    }
+
+    let row_start = y * params.num_bins;
+    for (var r = 0u; r < params.num_bins; r = r + 1u) {
+        row_bins[row_start + r] = 0.0;
+    }
+
+    let ky = f32(wavenumber(y));
+    for (var x = 0u; x < params.size; x = x + 1u) {
+        let kx = f32(wavenumber(x));
+        let r = u32(round(sqrt(kx * kx + ky * ky)));
+        if r < params.num_bins {
+            row_bins[row_start + r] += power_at(y * params.size + x);
+        }
+    }
+}
+
+// One invocation per radial bin: adds up the per-row sums
+@compute @workgroup_size(64)
+fn radial_sum(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let r = global_id.x;
+    if r >= params.num_bins {
+        return;
+    }
+
+    var total = 0.0;
+    for (var y = 0u; y < params.size; y = y + 1u) {
+        total += row_bins[y * params.num_bins + r];
+    }
+    radial[r] = total;
+}
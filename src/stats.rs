@@ -0,0 +1,536 @@
+// Live field statistics. A two-pass GPU reduction (stats.wgsl) summarises the
+// current field into one `FieldStats` sample and stores it in a ring buffer on
+// the GPU; the host drains new samples asynchronously as a time series.
+
+use std::future::Future;
+
+use wgpu::util::DeviceExt;
+
+use crate::readback;
+use crate::simulation::Simulation;
+
+// Number of samples kept on the GPU between reads
+pub const STATS_HISTORY_LEN: u32 = 256;
+
+// Values per sample in the flattened form handed to JS (see `FieldStats::to_record`)
+pub const STATS_RECORD_LEN: usize = 11;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct FieldStats {
+    pub mean: [f32; 2],      // (U, V)
+    pub variance: [f32; 2],
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    pub coverage: f32,       // fraction of cells with V above the threshold
+    pub step: u32,           // simulation step the sample was taken at
+    pub change: f32,         // RMS change per cell per step since the other ping-pong field
+    _padding: u32,
+}
+
+impl FieldStats {
+    // CPU version of the GPU reduction, for fields that were read back anyway.
+    // `field` and `previous` are interleaved (U, V) pairs as returned by
+    // `Simulation::read_field` / `read_previous_field`, with `previous` paired
+    // with the steps between them (`Simulation::previous_steps`).
+    pub fn from_field(field: &[f32], previous: Option<(&[f32], u32)>, v_threshold: f32, step: u32) -> Self {
+        let count = (field.len() / 2).max(1) as f64;
+        let mut stats = FieldStats {
+            min: [f32::MAX; 2],
+            max: [f32::MIN; 2],
+            step,
+            ..Default::default()
+        };
+
+        let mut sum = [0.0f64; 2];
+        let mut above = 0usize;
+        for uv in field.chunks_exact(2) {
+            for channel in 0..2 {
+                sum[channel] += uv[channel] as f64;
+                stats.min[channel] = stats.min[channel].min(uv[channel]);
+                stats.max[channel] = stats.max[channel].max(uv[channel]);
+            }
+            if uv[1] > v_threshold {
+                above += 1;
+            }
+        }
+
+        let mean = sum.map(|total| total / count);
+        let mut m2 = [0.0f64; 2];
+        for uv in field.chunks_exact(2) {
+            for channel in 0..2 {
+                m2[channel] += (uv[channel] as f64 - mean[channel]).powi(2);
+            }
+        }
+
+        stats.mean = mean.map(|m| m as f32);
+        stats.variance = m2.map(|m| (m / count) as f32);
+        stats.coverage = (above as f64 / count) as f32;
+        if let Some((previous, steps)) = previous {
+            let diff_sq: f64 = field.iter().zip(previous).map(|(&a, &b)| (a as f64 - b as f64).powi(2)).sum();
+            stats.change = ((diff_sq / count).sqrt() / steps.max(1) as f64) as f32;
+        }
+        stats
+    }
+
+    // [step, mean_u, mean_v, var_u, var_v, min_u, min_v, max_u, max_v, coverage, change]
+    pub fn to_record(&self) -> [f64; STATS_RECORD_LEN] {
+        [
+            self.step as f64,
+            self.mean[0] as f64,
+            self.mean[1] as f64,
+            self.variance[0] as f64,
+            self.variance[1] as f64,
+            self.min[0] as f64,
+            self.min[1] as f64,
+            self.max[0] as f64,
+            self.max[1] as f64,
+            self.coverage as f64,
+            self.change as f64,
+        ]
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct StatsParams {
+    grid_width: u32,
+    grid_height: u32,
+    num_partials: u32,
+    slot: u32,
+    v_threshold: f32,
+    step: u32,
+    previous_steps: u32,
+    _padding2: u32,
+}
+
+// Size of one `Partial` in stats.wgsl
+const PARTIAL_SIZE: u64 = 48;
+
+pub struct FieldStatsRecorder {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    width: u32,
+    height: u32,
+    groups_x: u32,
+    groups_y: u32,
+
+    reduce_pipeline: wgpu::ComputePipeline,
+    finalize_pipeline: wgpu::ComputePipeline,
+    bind_group_a: wgpu::BindGroup,
+    bind_group_b: wgpu::BindGroup,
+    params_buffer: wgpu::Buffer,
+    history_buffer: wgpu::Buffer,
+
+    // Samples written to / handed out from the ring buffer so far
+    written: u64,
+    read: u64,
+
+    pub v_threshold: f32,
+}
+
+impl FieldStatsRecorder {
+    pub fn new(sim: &Simulation) -> Self {
+        let device = sim.device();
+        let groups_x = sim.width().div_ceil(16);
+        let groups_y = sim.height().div_ceil(16);
+        let num_partials = groups_x * groups_y;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Stats Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("stats.wgsl").into()),
+        });
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Stats Params Buffer"),
+            contents: bytemuck::cast_slice(&[StatsParams {
+                grid_width: sim.width(),
+                grid_height: sim.height(),
+                num_partials,
+                slot: 0,
+                v_threshold: 0.2,
+                step: 0,
+                previous_steps: 1,
+                _padding2: 0,
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let partials_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Stats Partials Buffer"),
+            size: num_partials as u64 * PARTIAL_SIZE,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let history_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Stats History Buffer"),
+            size: STATS_HISTORY_LEN as u64 * std::mem::size_of::<FieldStats>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Stats Bind Group Layout"),
+            entries: &[
+                // binding 0: field texture
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // binding 1: per-workgroup partials
+                storage_entry(1),
+                // binding 2: history ring buffer
+                storage_entry(2),
+                // binding 3: params uniform buffer
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 4: previous field texture (the other ping-pong texture)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Stats Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let create_pipeline = |label, entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let reduce_pipeline = create_pipeline("Stats Reduce Pipeline", "reduce_field");
+        let finalize_pipeline = create_pipeline("Stats Finalize Pipeline", "finalize");
+
+        let create_bind_group = |label, view, previous_view| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: partials_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: history_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::TextureView(previous_view),
+                    },
+                ],
+            })
+        };
+
+        let bind_group_a = create_bind_group("Stats Bind Group A", sim.texture_a_view(), sim.texture_b_view());
+        let bind_group_b = create_bind_group("Stats Bind Group B", sim.texture_b_view(), sim.texture_a_view());
+
+        Self {
+            device: device.clone(),
+            queue: sim.queue().clone(),
+            width: sim.width(),
+            height: sim.height(),
+            groups_x,
+            groups_y,
+            reduce_pipeline,
+            finalize_pipeline,
+            bind_group_a,
+            bind_group_b,
+            params_buffer,
+            history_buffer,
+            written: 0,
+            read: 0,
+            v_threshold: 0.2,
+        }
+    }
+
+    // Reduces the simulation's current field into the next history slot
+    pub fn record(&mut self, sim: &Simulation) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Stats Encoder"),
+        });
+        self.encode_record(&mut encoder, sim);
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    // Records `record`'s passes into `encoder`. The slot's params are written
+    // with the queue, so submit before encoding another record.
+    pub fn encode_record(&mut self, encoder: &mut wgpu::CommandEncoder, sim: &Simulation) {
+        let params = StatsParams {
+            grid_width: self.width,
+            grid_height: self.height,
+            num_partials: self.groups_x * self.groups_y,
+            slot: (self.written % STATS_HISTORY_LEN as u64) as u32,
+            v_threshold: self.v_threshold,
+            step: sim.step_count() as u32,
+            previous_steps: sim.previous_steps(),
+            _padding2: 0,
+        };
+        self.queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+
+        {
+            let mut stats_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Stats Pass"),
+                timestamp_writes: None,
+            });
+
+            let bind_group = if sim.current_is_a() {
+                &self.bind_group_a
+            } else {
+                &self.bind_group_b
+            };
+            stats_pass.set_bind_group(0, bind_group, &[]);
+
+            stats_pass.set_pipeline(&self.reduce_pipeline);
+            stats_pass.dispatch_workgroups(self.groups_x, self.groups_y, 1);
+
+            stats_pass.set_pipeline(&self.finalize_pipeline);
+            stats_pass.dispatch_workgroups(1, 1, 1);
+        }
+
+        self.written += 1;
+    }
+
+    // Drops any samples that haven't been read yet
+    pub fn clear(&mut self) {
+        self.read = self.written;
+    }
+
+    // Total number of samples recorded so far; a read cursor starting here only
+    // sees future samples
+    pub fn samples_written(&self) -> u64 {
+        self.written
+    }
+
+    // Returns the samples recorded since the previous `read_new`, oldest first. If
+    // more than STATS_HISTORY_LEN were recorded in between, only the newest survive.
+    pub fn read_new(&mut self) -> impl Future<Output = Result<Vec<FieldStats>, String>> + 'static {
+        let mut cursor = self.read;
+        let read = self.read_since(&mut cursor);
+        self.read = cursor;
+        read
+    }
+
+    // Like `read_new`, but for an independent reader that keeps its own cursor
+    pub fn read_since(&self, cursor: &mut u64) -> impl Future<Output = Result<Vec<FieldStats>, String>> + 'static {
+        let start = (*cursor).max(self.written.saturating_sub(STATS_HISTORY_LEN as u64));
+        let end = self.written;
+        *cursor = end;
+
+        let device = self.device.clone();
+        let queue = self.queue.clone();
+        let history_buffer = self.history_buffer.clone();
+
+        async move {
+            if start == end {
+                return Ok(Vec::new());
+            }
+
+            let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Stats Staging Buffer"),
+                size: history_buffer.size(),
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Stats Readback Encoder"),
+            });
+            encoder.copy_buffer_to_buffer(&history_buffer, 0, &staging_buffer, 0, history_buffer.size());
+            queue.submit(Some(encoder.finish()));
+
+            readback::map_buffer(&device, &staging_buffer).await?;
+
+            let samples = {
+                let mapped = staging_buffer.slice(..).get_mapped_range();
+                let history: &[FieldStats] = bytemuck::cast_slice(&mapped);
+                (start..end)
+                    .map(|i| history[(i % STATS_HISTORY_LEN as u64) as usize])
+                    .collect()
+            };
+            staging_buffer.unmap();
+
+            Ok(samples)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{headless_simulation, InitParams};
+
+    #[test]
+    fn stats_match_cpu_reference() {
+        let Some(mut sim) = headless_simulation(37, 29) else { return };
+        let mut recorder = FieldStatsRecorder::new(&sim);
+        recorder.v_threshold = 0.5;
+
+        let data: Vec<f32> = (0..37 * 29)
+            .flat_map(|i| [((i * 7) % 13) as f32 / 13.0, ((i * 5) % 11) as f32 / 11.0])
+            .collect();
+        sim.write_field(&data).unwrap();
+        recorder.record(&sim);
+
+        let samples = pollster::block_on(recorder.read_new()).unwrap();
+        assert_eq!(samples.len(), 1);
+        let stats = samples[0];
+
+        for channel in 0..2 {
+            let values: Vec<f64> = data.iter().skip(channel).step_by(2).map(|&v| v as f64).collect();
+            let n = values.len() as f64;
+            let mean = values.iter().sum::<f64>() / n;
+            let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / n;
+            let min = values.iter().cloned().fold(f64::MAX, f64::min);
+            let max = values.iter().cloned().fold(f64::MIN, f64::max);
+
+            assert!((stats.mean[channel] as f64 - mean).abs() < 1e-5);
+            assert!((stats.variance[channel] as f64 - variance).abs() < 1e-5);
+            assert_eq!(stats.min[channel] as f64, min);
+            assert_eq!(stats.max[channel] as f64, max);
+        }
+
+        let above = data.iter().skip(1).step_by(2).filter(|&&v| v > 0.5).count();
+        assert!((stats.coverage as f64 - above as f64 / (37.0 * 29.0)).abs() < 1e-6);
+
+        // The CPU path agrees with the GPU reduction
+        let cpu = FieldStats::from_field(&data, None, 0.5, stats.step);
+        for channel in 0..2 {
+            assert!((cpu.mean[channel] - stats.mean[channel]).abs() < 1e-5);
+            assert!((cpu.variance[channel] - stats.variance[channel]).abs() < 1e-5);
+        }
+        assert_eq!((cpu.min, cpu.max), (stats.min, stats.max));
+        assert!((cpu.coverage - stats.coverage).abs() < 1e-6);
+
+        // Nothing new since the last read
+        assert!(pollster::block_on(recorder.read_new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn change_measures_the_last_step() {
+        let Some(mut sim) = headless_simulation(32, 32) else { return };
+        let mut recorder = FieldStatsRecorder::new(&sim);
+
+        // A uniform U=1, V=0 field is a fixed point of the reaction
+        sim.init_field(&InitParams::uniform());
+        sim.step(2);
+        recorder.record(&sim);
+
+        sim.paint(16.0, 16.0);
+        recorder.record(&sim);
+
+        // Independent readers each see every sample
+        let mut cursor = 0;
+        let samples = pollster::block_on(recorder.read_since(&mut cursor)).unwrap();
+        assert_eq!(cursor, 2);
+        assert_eq!(samples[0].change, 0.0);
+        assert!(samples[1].change > 0.0);
+        assert_eq!(pollster::block_on(recorder.read_new()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn change_is_per_step_after_blocked_steps() {
+        let Some(mut sim) = headless_simulation(32, 32) else { return };
+        let mut recorder = FieldStatsRecorder::new(&sim);
+
+        let mut seed = 99u32;
+        let data: Vec<f32> = (0..32 * 32 * 2)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (seed >> 8) as f32 / (1u32 << 24) as f32
+            })
+            .collect();
+        sim.write_field(&data).unwrap();
+        sim.block_steps = 4;
+        sim.update_params();
+        sim.step(4);
+        if sim.blocking_fallback().is_some() {
+            return;
+        }
+        // One fused dispatch, so the other texture is four steps back
+        assert_eq!(sim.previous_steps(), 4);
+        recorder.record(&sim);
+
+        let field = pollster::block_on(sim.read_field()).unwrap();
+        let previous = pollster::block_on(sim.read_previous_field()).unwrap();
+        let rms = (field.iter().zip(&previous).map(|(a, b)| (a - b) * (a - b)).sum::<f32>() / (32.0 * 32.0)).sqrt();
+        let stats = pollster::block_on(recorder.read_new()).unwrap()[0];
+        assert!(rms > 0.0);
+        assert!((stats.change - rms / 4.0).abs() < 1e-5 * rms);
+        let cpu = FieldStats::from_field(&field, Some((&previous, sim.previous_steps())), 0.2, stats.step);
+        assert!((cpu.change - stats.change).abs() < 1e-5 * rms);
+
+        sim.block_steps = 1;
+        sim.update_params();
+        sim.step(1);
+        assert_eq!(sim.previous_steps(), 1);
+    }
+
+    #[test]
+    fn history_keeps_the_newest_samples() {
+        let Some(mut sim) = headless_simulation(16, 16) else { return };
+        let mut recorder = FieldStatsRecorder::new(&sim);
+
+        let extra = 5;
+        for _ in 0..STATS_HISTORY_LEN + extra {
+            sim.step(1);
+            recorder.record(&sim);
+        }
+
+        let samples = pollster::block_on(recorder.read_new()).unwrap();
+        assert_eq!(samples.len(), STATS_HISTORY_LEN as usize);
+        assert_eq!(samples[0].step, extra + 1);
+        assert_eq!(samples.last().unwrap().step, STATS_HISTORY_LEN + extra);
+    }
+}
@@ -0,0 +1,147 @@
+// Field statistics via a two-pass parallel reduction
+// Pass 1 (reduce_field): each 16x16 workgroup reduces its tile of the field to one partial.
+// Pass 2 (finalize): a single workgroup folds the partials together and writes the
+// result into the history ring buffer.
+//
+// The change statistic compares the field against the other ping-pong texture,
+// which holds the field `previous_steps` steps back right after a simulation
+// step (more than one after a temporally blocked dispatch), and is divided by
+// that count so it stays a change per step.
+//
+// Partials carry a running mean and sum of squared deviations (M2) instead of raw
+// sums, combined with Chan's parallel formula, so the variance stays accurate on
+// 2048x2048 grids in f32.
+
+struct StatsParams {
+    grid_width: u32,
+    grid_height: u32,
+    num_partials: u32,
+    slot: u32,          // history slot written by finalize
+    v_threshold: f32,   // cells with V above this count towards coverage
+    step: u32,          // simulation step the sample was taken at
+    previous_steps: u32, // steps between previous_field and field
+    _padding2: u32,
+}
+
+struct Partial {
+    count: f32,
+    above: f32,
+    mean: vec2<f32>,
+    m2: vec2<f32>,
+    min_uv: vec2<f32>,
+    max_uv: vec2<f32>,
+    diff_sq: f32,       // sum of squared change against the previous field
+}
+
+struct FieldStats {
+    mean: vec2<f32>,
+    variance: vec2<f32>,
+    min_uv: vec2<f32>,
+    max_uv: vec2<f32>,
+    coverage: f32,
+    step: u32,
+    change: f32,        // RMS change per cell per step since previous_field
+    _padding: u32,
+}
+
+@group(0) @binding(0) var field: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> partials: array<Partial>;
+@group(0) @binding(2) var<storage, read_write> history: array<FieldStats>;
+@group(0) @binding(3) var<uniform> params: StatsParams;
+@group(0) @binding(4) var previous_field: texture_2d<f32>;
+
+const WORKGROUP_SIZE: u32 = 256u;
+
+var<workgroup> scratch: array<Partial, WORKGROUP_SIZE>;
+
+fn empty_partial() -> Partial {
+    return Partial(0.0, 0.0, vec2<f32>(0.0), vec2<f32>(0.0), vec2<f32>(3.4e38), vec2<f32>(-3.4e38), 0.0);
+}
+
+fn combine(a: Partial, b: Partial) -> Partial {
+    if a.count == 0.0 {
+        return b;
+    }
+    if b.count == 0.0 {
+        return a;
+    }
+
+    let n = a.count + b.count;
+    let delta = b.mean - a.mean;
+
+    var out: Partial;
+    out.count = n;
+    out.above = a.above + b.above;
+    out.mean = a.mean + delta * (b.count / n);
+    out.m2 = a.m2 + b.m2 + delta * delta * (a.count * b.count / n);
+    out.min_uv = min(a.min_uv, b.min_uv);
+    out.max_uv = max(a.max_uv, b.max_uv);
+    out.diff_sq = a.diff_sq + b.diff_sq;
+    return out;
+}
+
+// Tree reduction over `scratch`; the result ends up in scratch[0]
+fn reduce_scratch(index: u32) {
+    for (var stride = WORKGROUP_SIZE / 2u; stride > 0u; stride = stride / 2u) {
+        workgroupBarrier();
+        if index < stride {
+            scratch[index] = combine(scratch[index], scratch[index + stride]);
+        }
+    }
+    workgroupBarrier();
+}
+
+@compute @workgroup_size(16, 16)
+fn reduce_field(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    var partial = empty_partial();
+
+    if global_id.x < params.grid_width && global_id.y < params.grid_height {
+        let uv = textureLoad(field, vec2<i32>(global_id.xy), 0).rg;
+        let diff = uv - textureLoad(previous_field, vec2<i32>(global_id.xy), 0).rg;
+        partial.count = 1.0;
+        partial.above = select(0.0, 1.0, uv.y > params.v_threshold);
+        partial.mean = uv;
+        partial.min_uv = uv;
+        partial.max_uv = uv;
+        partial.diff_sq = dot(diff, diff);
+    }
+
+    scratch[local_index] = partial;
+    reduce_scratch(local_index);
+
+    if local_index == 0u {
+        partials[workgroup_id.y * num_workgroups.x + workgroup_id.x] = scratch[0];
+    }
+}
+
+@compute @workgroup_size(256)
+fn finalize(@builtin(local_invocation_index) local_index: u32) {
+    var partial = empty_partial();
+    for (var i = local_index; i < params.num_partials; i = i + WORKGROUP_SIZE) {
+        partial = combine(partial, partials[i]);
+    }
+
+    scratch[local_index] = partial;
+    reduce_scratch(local_index);
+
+    if local_index == 0u {
+        let total = scratch[0];
+        let count = max(total.count, 1.0);
+
+        var stats: FieldStats;
+        stats.mean = total.mean;
+        stats.variance = total.m2 / count;
+        stats.min_uv = total.min_uv;
+        stats.max_uv = total.max_uv;
+        stats.coverage = total.above / count;
+        stats.step = params.step;
+        stats.change = sqrt(total.diff_sq / count) / f32(max(params.previous_steps, 1u));
+        stats._padding = 0u;
+        history[params.slot] = stats;
+    }
+}
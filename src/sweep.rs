@@ -0,0 +1,360 @@
+// Batch parameter sweeps. Every combination of (F, k, Du, Dv, kernel) runs on
+// its own small grid from the same seeded starting field; all runs share one set
+// of compiled pipelines. The result renders as a contact sheet (kill rate across,
+// everything else down) with a CSV/JSON manifest of per-run statistics.
+
+use serde::{Deserialize, Serialize};
+
+use crate::classify::{self, Classification, ClassifierConfig};
+use crate::image;
+use crate::simulation::{InitParams, Simulation, SimulationPipelines};
+use crate::stats::FieldStats;
+
+// Pixels between contact sheet tiles
+const TILE_GAP: u32 = 2;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SweepConfig {
+    pub feed: Vec<f32>,
+    pub kill: Vec<f32>,
+    pub diffuse_u: Vec<f32>,
+    pub diffuse_v: Vec<f32>,
+    pub kernel: Vec<u32>,
+
+    pub grid_size: u32,      // side of each run's grid
+    pub steps: u32,
+    pub seed: u64,           // every run starts from the same seeded blobs
+    pub seed_blobs: u32,
+    pub delta_time: f32,
+    pub boundary_mode: u32,
+    pub batch_size: u32,     // runs kept on the GPU at once
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self {
+            feed: vec![0.022, 0.03, 0.038, 0.046, 0.054, 0.062],
+            kill: vec![0.051, 0.055, 0.059, 0.063, 0.067],
+            diffuse_u: vec![1.0],
+            diffuse_v: vec![0.5],
+            kernel: vec![0],
+            grid_size: 128,
+            steps: 5000,
+            seed: 1,
+            seed_blobs: 8,
+            delta_time: 1.0,
+            boundary_mode: 0,
+            batch_size: 64,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunParams {
+    pub feed: f32,
+    pub kill: f32,
+    pub diffuse_u: f32,
+    pub diffuse_v: f32,
+    pub kernel: u32,
+}
+
+impl SweepConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let axes = [
+            ("feed", self.feed.len()),
+            ("kill", self.kill.len()),
+            ("diffuse_u", self.diffuse_u.len()),
+            ("diffuse_v", self.diffuse_v.len()),
+            ("kernel", self.kernel.len()),
+        ];
+        if let Some((name, _)) = axes.iter().find(|(_, len)| *len == 0) {
+            return Err(format!("Sweep axis '{}' has no values", name));
+        }
+        if self.grid_size < 8 {
+            return Err(format!("Sweep grid size must be at least 8, got {}", self.grid_size));
+        }
+        if self.batch_size == 0 {
+            return Err("Sweep batch size must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    // All combinations, kill rate varying fastest, then feed, Dv, Du and kernel
+    pub fn runs(&self) -> Vec<RunParams> {
+        let mut runs = Vec::new();
+        for &kernel in &self.kernel {
+            for &diffuse_u in &self.diffuse_u {
+                for &diffuse_v in &self.diffuse_v {
+                    for &feed in &self.feed {
+                        for &kill in &self.kill {
+                            runs.push(RunParams { feed, kill, diffuse_u, diffuse_v, kernel });
+                        }
+                    }
+                }
+            }
+        }
+        runs
+    }
+
+    pub fn seed_field(&self) -> InitParams {
+        let size = self.grid_size;
+        let radius = size as f32 / 32.0;
+        InitParams::random_blobs(self.seed, self.seed_blobs as usize, size, size, radius.max(2.0), (2.0 * radius).max(3.0))
+    }
+}
+
+pub struct RunResult {
+    pub params: RunParams,
+    pub stats: FieldStats,
+    pub classification: Classification,
+    pub v: Vec<f32>,    // final V field, row-major
+}
+
+pub struct SweepResult {
+    pub config: SweepConfig,
+    pub runs: Vec<RunResult>,
+}
+
+// Runs every combination in `config`, `batch_size` grids at a time
+pub async fn run_sweep(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    format: wgpu::TextureFormat,
+    config: &SweepConfig,
+) -> Result<SweepResult, String> {
+    config.validate()?;
+
+    let pipelines = SimulationPipelines::new(device, format);
+    let seed_field = config.seed_field();
+    let size = config.grid_size;
+    let classifier = ClassifierConfig::default();
+    let all_runs = config.runs();
+    let mut results = Vec::with_capacity(all_runs.len());
+
+    for batch in all_runs.chunks(config.batch_size as usize) {
+        // Queue all of the batch's work before waiting on any of it
+        let mut sims = Vec::with_capacity(batch.len());
+        for params in batch {
+            let mut sim = Simulation::with_pipelines(device, queue, size, size, &pipelines);
+            sim.feed_rate = params.feed;
+            sim.kill_rate = params.kill;
+            sim.diffuse_u = params.diffuse_u;
+            sim.diffuse_v = params.diffuse_v;
+            sim.kernel_type = params.kernel;
+            sim.delta_time = config.delta_time;
+            sim.boundary_mode = config.boundary_mode;
+            sim.update_params();
+            sim.init_field(&seed_field);
+            sim.step(config.steps);
+            sims.push(sim);
+        }
+
+        for (params, sim) in batch.iter().zip(&sims) {
+            let field = sim.read_field().await?;
+            let previous = if config.steps > 0 { Some(sim.read_previous_field().await?) } else { None };
+            let previous = previous.as_deref().map(|previous| (previous, sim.previous_steps()));
+
+            let stats = FieldStats::from_field(&field, previous, 0.2, config.steps);
+            let v = classify::v_channel(&field);
+            let periodic = config.boundary_mode == 0;
+            let classification = classify::classify(&v, size, size, stats.change, periodic, &classifier);
+
+            results.push(RunResult {
+                params: *params,
+                stats,
+                classification,
+                v,
+            });
+        }
+    }
+
+    Ok(SweepResult {
+        config: config.clone(),
+        runs: results,
+    })
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    index: usize,
+    tile_x: u32,
+    tile_y: u32,
+    #[serde(flatten)]
+    params: RunParams,
+    class: &'static str,
+    mean_u: f32,
+    mean_v: f32,
+    variance_u: f32,
+    variance_v: f32,
+    min_u: f32,
+    min_v: f32,
+    max_u: f32,
+    max_v: f32,
+    coverage: f32,
+    change: f32,
+    high_components: u32,
+    low_components: u32,
+    roundness: f32,
+    coherence: f32,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    config: &'a SweepConfig,
+    runs: Vec<ManifestEntry>,
+}
+
+impl SweepResult {
+    fn columns(&self) -> u32 {
+        self.config.kill.len() as u32
+    }
+
+    fn manifest_entries(&self) -> Vec<ManifestEntry> {
+        self.runs
+            .iter()
+            .enumerate()
+            .map(|(index, run)| {
+                let features = &run.classification.features;
+                ManifestEntry {
+                    index,
+                    tile_x: index as u32 % self.columns(),
+                    tile_y: index as u32 / self.columns(),
+                    params: run.params,
+                    class: run.classification.class.name(),
+                    mean_u: run.stats.mean[0],
+                    mean_v: run.stats.mean[1],
+                    variance_u: run.stats.variance[0],
+                    variance_v: run.stats.variance[1],
+                    min_u: run.stats.min[0],
+                    min_v: run.stats.min[1],
+                    max_u: run.stats.max[0],
+                    max_v: run.stats.max[1],
+                    coverage: run.stats.coverage,
+                    change: run.stats.change,
+                    high_components: features.high_components,
+                    low_components: features.low_components,
+                    roundness: features.roundness,
+                    coherence: features.coherence,
+                }
+            })
+            .collect()
+    }
+
+    pub fn manifest_json(&self) -> Result<String, String> {
+        let manifest = Manifest {
+            config: &self.config,
+            runs: self.manifest_entries(),
+        };
+        serde_json::to_string_pretty(&manifest).map_err(|e| format!("Failed to write manifest: {}", e))
+    }
+
+    pub fn manifest_csv(&self) -> String {
+        let mut csv = String::from(
+            "index,tile_x,tile_y,feed,kill,diffuse_u,diffuse_v,kernel,class,mean_u,mean_v,variance_u,variance_v,\
+             min_u,min_v,max_u,max_v,coverage,change,high_components,low_components,roundness,coherence\n",
+        );
+        for e in self.manifest_entries() {
+            let p = e.params;
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                e.index, e.tile_x, e.tile_y, p.feed, p.kill, p.diffuse_u, p.diffuse_v, p.kernel, e.class,
+                e.mean_u, e.mean_v, e.variance_u, e.variance_v, e.min_u, e.min_v, e.max_u, e.max_v,
+                e.coverage, e.change, e.high_components, e.low_components, e.roundness, e.coherence,
+            ));
+        }
+        csv
+    }
+
+    // One tile per run in manifest order, `kill.len()` tiles per row
+    pub fn contact_sheet_png(&self, gradient: &[(f32, [f32; 3])]) -> Result<Vec<u8>, String> {
+        let tile = self.config.grid_size;
+        let columns = self.columns().max(1);
+        let rows = (self.runs.len() as u32).div_ceil(columns).max(1);
+        let width = columns * tile + (columns + 1) * TILE_GAP;
+        let height = rows * tile + (rows + 1) * TILE_GAP;
+
+        let mut rgb = vec![24u8; (width * height * 3) as usize];
+        for (index, run) in self.runs.iter().enumerate() {
+            let left = TILE_GAP + (index as u32 % columns) * (tile + TILE_GAP);
+            let top = TILE_GAP + (index as u32 / columns) * (tile + TILE_GAP);
+            let pixels = image::colorize(&run.v, gradient);
+            for (y, row) in pixels.chunks((tile * 3) as usize).enumerate() {
+                let start = (((top + y as u32) * width + left) * 3) as usize;
+                rgb[start..start + row.len()].copy_from_slice(row);
+            }
+        }
+
+        image::encode_png(width, height, &rgb)
+    }
+
+    // Writes contact_sheet.png, manifest.csv and manifest.json into `dir`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_outputs(&self, dir: &std::path::Path, gradient: &[(f32, [f32; 3])]) -> Result<(), String> {
+        let write = |name: &str, bytes: &[u8]| {
+            std::fs::write(dir.join(name), bytes).map_err(|e| format!("Failed to write {}: {}", name, e))
+        };
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        write("contact_sheet.png", &self.contact_sheet_png(gradient)?)?;
+        write("manifest.csv", self.manifest_csv().as_bytes())?;
+        write("manifest.json", self.manifest_json()?.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::request_headless_device;
+
+    #[test]
+    fn runs_cover_every_combination() {
+        let config = SweepConfig {
+            feed: vec![0.02, 0.03],
+            kill: vec![0.05, 0.06, 0.07],
+            diffuse_u: vec![1.0],
+            diffuse_v: vec![0.4, 0.5],
+            kernel: vec![0],
+            ..Default::default()
+        };
+        let runs = config.runs();
+        assert_eq!(runs.len(), 12);
+        assert_eq!((runs[0].feed, runs[0].kill), (0.02, 0.05));
+        assert_eq!((runs[1].feed, runs[1].kill), (0.02, 0.06));
+        assert_eq!((runs[3].feed, runs[3].kill), (0.03, 0.05));
+        assert_eq!(runs[6].diffuse_v, 0.5);
+
+        let empty = SweepConfig { kernel: Vec::new(), ..Default::default() };
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn small_sweep_produces_sheet_and_manifest() {
+        let Ok((adapter, device, queue)) = pollster::block_on(request_headless_device()) else { return };
+        let config = SweepConfig {
+            feed: vec![0.03, 0.055],
+            kill: vec![0.062, 0.09],
+            grid_size: 16,
+            steps: 20,
+            batch_size: 3,
+            ..Default::default()
+        };
+
+        let format = crate::simulation::preferred_field_format(&adapter);
+        let result = pollster::block_on(run_sweep(&device, &queue, format, &config)).unwrap();
+        assert_eq!(result.runs.len(), 4);
+        assert!(result.runs.iter().all(|run| run.v.len() == 16 * 16 && run.stats.step == 20));
+
+        let csv = result.manifest_csv();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.lines().nth(2).unwrap().starts_with("1,1,0,0.03,0.09,"));
+
+        let json: serde_json::Value = serde_json::from_str(&result.manifest_json().unwrap()).unwrap();
+        assert_eq!(json["runs"].as_array().unwrap().len(), 4);
+        assert_eq!(json["config"]["grid_size"], 16);
+
+        let png = result.contact_sheet_png(&image::DEFAULT_GRADIENT).unwrap();
+        let reader = png::Decoder::new(std::io::Cursor::new(png)).read_info().unwrap();
+        let expected = 2 * 16 + 3 * TILE_GAP;
+        assert_eq!((reader.info().width, reader.info().height), (expected, expected));
+    }
+}
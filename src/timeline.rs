@@ -0,0 +1,253 @@
+// Keyframe animation of the model parameters and the display gradient. Time is
+// the simulation step count rather than wall time, so a timeline plays out the
+// same way on every run no matter how fast frames are drawn.
+
+use serde::{Deserialize, Serialize};
+
+use crate::scene::{self, ColorStop};
+use crate::simulation::Simulation;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    Hold, // keeps the previous value until the keyframe is reached
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    // Maps progress through a segment (0..1) to the blend factor
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::Hold => 0.0,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+// Values left out of a keyframe aren't animated by it: each parameter
+// interpolates between the keyframes that do set it
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keyframe {
+    pub step: u64,
+    // How values approach this keyframe from the previous one
+    #[serde(default)]
+    pub easing: Easing,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed_rate: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kill_rate: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diffuse_u: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diffuse_v: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noise_strength: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gradient: Option<Vec<ColorStop>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeline {
+    pub keyframes: Vec<Keyframe>,
+    pub repeat: bool, // start over once the last keyframe is reached
+}
+
+// Animated values at one step; None for parameters no keyframe sets
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimelineValues {
+    pub feed_rate: Option<f32>,
+    pub kill_rate: Option<f32>,
+    pub diffuse_u: Option<f32>,
+    pub diffuse_v: Option<f32>,
+    pub noise_strength: Option<f32>,
+    pub gradient: Option<Vec<ColorStop>>,
+}
+
+impl TimelineValues {
+    // Copies the animated model parameters into the simulation. The caller
+    // uploads them with update_params.
+    pub fn apply(&self, sim: &mut Simulation) {
+        let targets = [
+            (self.feed_rate, &mut sim.feed_rate),
+            (self.kill_rate, &mut sim.kill_rate),
+            (self.diffuse_u, &mut sim.diffuse_u),
+            (self.diffuse_v, &mut sim.diffuse_v),
+            (self.noise_strength, &mut sim.noise_strength),
+        ];
+        for (value, target) in targets {
+            if let Some(value) = value {
+                *target = value;
+            }
+        }
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Gradients with the same number of stops blend stop by stop; otherwise the
+// old gradient is kept until the keyframe is reached
+fn mix_gradients(a: &[ColorStop], b: &[ColorStop], t: f32) -> Vec<ColorStop> {
+    if a.len() != b.len() {
+        return a.to_vec();
+    }
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| ColorStop {
+            position: lerp(a.position, b.position, t),
+            color: [0, 1, 2].map(|i| lerp(a.color[i], b.color[i], t)),
+        })
+        .collect()
+}
+
+impl Timeline {
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let timeline: Self = serde_json::from_str(text).map_err(|e| format!("Invalid timeline: {}", e))?;
+        timeline.validate()?;
+        Ok(timeline)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.keyframes.windows(2).any(|pair| pair[1].step <= pair[0].step) {
+            return Err("Timeline keyframes must have increasing steps".to_string());
+        }
+        for key in &self.keyframes {
+            let values = [key.feed_rate, key.kill_rate, key.diffuse_u, key.diffuse_v, key.noise_strength];
+            if values.iter().flatten().any(|v| !(v.is_finite() && *v >= 0.0)) {
+                return Err(format!("Keyframe at step {} has a negative or non-finite value", key.step));
+            }
+            if let Some(gradient) = &key.gradient {
+                scene::validate_gradient(gradient).map_err(|e| format!("Keyframe at step {}: {}", key.step, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Interpolates one parameter between the keyframes that set it, holding
+    // the first and last values outside their range
+    fn track<T: Clone>(
+        &self,
+        step: u64,
+        value: impl Fn(&Keyframe) -> Option<&T>,
+        mix: impl Fn(&T, &T, f32) -> T,
+    ) -> Option<T> {
+        let mut previous: Option<(u64, &T)> = None;
+        for key in &self.keyframes {
+            let Some(v) = value(key) else { continue };
+            if key.step <= step {
+                previous = Some((key.step, v));
+                continue;
+            }
+            return Some(match previous {
+                None => v.clone(),
+                Some((start, a)) => {
+                    let t = (step - start) as f32 / (key.step - start) as f32;
+                    mix(a, v, key.easing.apply(t))
+                }
+            });
+        }
+        previous.map(|(_, v)| v.clone())
+    }
+
+    pub fn evaluate(&self, step: u64) -> TimelineValues {
+        let step = match self.keyframes.last() {
+            Some(last) if self.repeat && last.step > 0 => step % last.step,
+            _ => step,
+        };
+        let scalar = |value: fn(&Keyframe) -> Option<&f32>| self.track(step, value, |a, b, t| lerp(*a, *b, t));
+
+        TimelineValues {
+            feed_rate: scalar(|k| k.feed_rate.as_ref()),
+            kill_rate: scalar(|k| k.kill_rate.as_ref()),
+            diffuse_u: scalar(|k| k.diffuse_u.as_ref()),
+            diffuse_v: scalar(|k| k.diffuse_v.as_ref()),
+            noise_strength: scalar(|k| k.noise_strength.as_ref()),
+            gradient: self.track(step, |k| k.gradient.as_ref(), |a, b, t| mix_gradients(a, b, t)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline(text: &str) -> Timeline {
+        Timeline::from_json(text).unwrap()
+    }
+
+    #[test]
+    fn parameters_interpolate_between_their_own_keyframes() {
+        let t = timeline(
+            r#"{ "keyframes": [
+                { "step": 100, "feed_rate": 0.02, "kill_rate": 0.05 },
+                { "step": 200, "feed_rate": 0.04 },
+                { "step": 300, "easing": "hold", "kill_rate": 0.07 }
+            ] }"#,
+        );
+
+        // Held before the first keyframe and after the last
+        assert_eq!(t.evaluate(0).feed_rate, Some(0.02));
+        assert_eq!(t.evaluate(1000).feed_rate, Some(0.04));
+        assert_eq!(t.evaluate(150).feed_rate, Some(0.03));
+
+        // kill_rate skips the keyframe at 200, and its segment is a hold
+        assert_eq!(t.evaluate(250).kill_rate, Some(0.05));
+        assert_eq!(t.evaluate(300).kill_rate, Some(0.07));
+        assert_eq!(t.evaluate(150).diffuse_u, None);
+    }
+
+    #[test]
+    fn easing_and_repeat() {
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!((easing.apply(0.0), easing.apply(1.0)), (0.0, 1.0));
+        }
+
+        let t = timeline(
+            r#"{ "repeat": true, "keyframes": [
+                { "step": 0, "noise_strength": 0.0 },
+                { "step": 100, "easing": "ease_in", "noise_strength": 1.0 }
+            ] }"#,
+        );
+        assert_eq!(t.evaluate(50).noise_strength, Some(0.25));
+        assert_eq!(t.evaluate(250).noise_strength, Some(0.25));
+    }
+
+    #[test]
+    fn gradients_blend_stop_by_stop() {
+        let t = timeline(
+            r#"{ "keyframes": [
+                { "step": 0, "gradient": [{ "position": 0.0, "color": [0, 0, 0] }, { "position": 0.5, "color": [1, 1, 1] }] },
+                { "step": 10, "gradient": [{ "position": 0.2, "color": [1, 0, 0] }, { "position": 1.0, "color": [1, 1, 1] }] },
+                { "step": 20, "gradient": [{ "position": 0.0, "color": [0, 0, 1] }] }
+            ] }"#,
+        );
+        let mid = t.evaluate(5).gradient.unwrap();
+        assert_eq!(mid[0], ColorStop { position: 0.1, color: [0.5, 0.0, 0.0] });
+        assert_eq!(mid[1].position, 0.75);
+
+        // A different stop count switches over at the keyframe
+        assert_eq!(t.evaluate(19).gradient.unwrap().len(), 2);
+        assert_eq!(t.evaluate(20).gradient.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn invalid_timelines_are_rejected() {
+        assert!(Timeline::from_json(r#"{ "keyframes": [{ "step": 10 }, { "step": 10 }] }"#).is_err());
+        assert!(Timeline::from_json(r#"{ "keyframes": [{ "step": 0, "feed_rate": -1.0 }] }"#).is_err());
+        assert!(Timeline::from_json(r#"{ "keyframes": [{ "step": 0, "gradient": [] }] }"#).is_err());
+        assert!(Timeline::from_json(r#"{ "keyframes": [{ "step": 0, "easing": "bounce" }] }"#).is_err());
+    }
+}